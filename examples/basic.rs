@@ -18,7 +18,7 @@ async fn main() -> Result<()> {
     let response = client.generate_content(None, request).await?;
 
     if let Some(candidate) = response.candidates.first() {
-        if let Some(Part::Text { text }) = candidate.content.parts.first() {
+        if let Some(Part::Text { text, .. }) = candidate.content.parts.first() {
             println!("Response:\n{}", text);
         }
     }
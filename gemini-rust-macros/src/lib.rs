@@ -0,0 +1,419 @@
+//! Procedural macros for gemini-rust
+//!
+//! These macros are re-exported from `gemini_rust` when the `macros` feature is enabled and
+//! should not be used directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Expr, ExprLit, FnArg, GenericArgument, ItemFn, Lit, Meta, Pat,
+    PathArguments, ReturnType, Type,
+};
+
+/// Generate a function declaration and an argument-deserializing wrapper for a function
+///
+/// For a function `name`, this emits alongside it:
+///
+/// - `name_declaration() -> gemini_rust::Result<gemini_rust::FunctionDeclaration>`, built
+///   from the function's doc comment (description) and its `# Arguments` section (parameter
+///   descriptions)
+/// - `name_invoke(args) -> gemini_rust::Result<serde_json::Value>`, which deserializes the
+///   model's arguments, calls the function, and serializes its output
+///
+/// `Option<T>` parameters are declared as optional. Strings, numbers, booleans and sequences
+/// of them are described directly; any other parameter type must implement
+/// `schemars::JsonSchema`, and its derived schema is declared. Tuples, maps, references and
+/// types without a `JsonSchema` implementation are rejected at compile time; a derived schema
+/// the API cannot express, such as that of a recursive type, makes `name_declaration` return
+/// `Error::SchemaValidation`. Functions returning `Result<T, E>` have their errors converted
+/// into `Error::FunctionCall`.
+///
+/// ```ignore
+/// /// Get the current weather for a city
+/// ///
+/// /// # Arguments
+/// ///
+/// /// * `city` - Name of the city
+/// /// * `units` - Temperature units, celsius or fahrenheit
+/// #[gemini_tool]
+/// async fn get_weather(city: String, units: Option<String>) -> Result<Weather, ApiError> {
+///     // ...
+/// }
+///
+/// let tool = Tool::functions(vec![get_weather_declaration()?]);
+/// let output = get_weather_invoke(function_call.args).await?;
+/// ```
+#[proc_macro_attribute]
+pub fn gemini_tool(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[gemini_tool] does not take arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let function = parse_macro_input!(item as ItemFn);
+    match expand(function) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand(function: ItemFn) -> syn::Result<TokenStream2> {
+    let docs = Docs::parse(&function.attrs);
+    let vis = &function.vis;
+    let ident = &function.sig.ident;
+    let name = ident.to_string();
+    let description = docs.description;
+    let declaration_ident = format_ident!("{}_declaration", ident);
+    let invoke_ident = format_ident!("{}_invoke", ident);
+
+    if !function.sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &function.sig.generics,
+            "#[gemini_tool] functions cannot be generic",
+        ));
+    }
+
+    let mut params = Vec::new();
+    let mut extractions = Vec::new();
+    let mut arg_idents = Vec::new();
+
+    for input in &function.sig.inputs {
+        let pat_type = match input {
+            FnArg::Typed(pat_type) => pat_type,
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "#[gemini_tool] cannot be applied to methods",
+                ))
+            }
+        };
+        let arg_ident = match pat_type.pat.as_ref() {
+            Pat::Ident(pat_ident) => &pat_ident.ident,
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "#[gemini_tool] parameters must be plain identifiers",
+                ))
+            }
+        };
+
+        let arg_name = arg_ident.to_string();
+        let arg_name = arg_name.trim_start_matches("r#");
+        let ty = pat_type.ty.as_ref();
+        let (required, schema_ty) = match generic_inner(ty, "Option") {
+            Some(inner) => (false, inner),
+            None => (true, ty),
+        };
+        let schema = property_schema(schema_ty, docs.arguments.get(arg_name))?;
+
+        params.push(quote! {
+            .property(#arg_name, #schema, #required)
+        });
+
+        let missing = if required {
+            quote! {
+                return ::std::result::Result::Err(::gemini_rust::Error::FunctionCall(
+                    ::std::format!("missing required argument `{}` for {}", #arg_name, #name),
+                ))
+            }
+        } else {
+            quote! { ::std::option::Option::None }
+        };
+        extractions.push(quote! {
+            let #arg_ident: #ty = match args.remove(#arg_name) {
+                ::std::option::Option::Some(value) => {
+                    ::gemini_rust::__private::serde_json::from_value(value).map_err(|e| {
+                        ::gemini_rust::Error::FunctionCall(::std::format!(
+                            "invalid argument `{}` for {}: {}",
+                            #arg_name, #name, e
+                        ))
+                    })?
+                }
+                ::std::option::Option::None => #missing,
+            };
+        });
+        arg_idents.push(arg_ident.clone());
+    }
+
+    let call = if function.sig.asyncness.is_some() {
+        quote! { #ident(#(#arg_idents),*).await }
+    } else {
+        quote! { #ident(#(#arg_idents),*) }
+    };
+
+    let returns_result = match &function.sig.output {
+        ReturnType::Type(_, ty) => last_segment_is(ty, "Result"),
+        ReturnType::Default => false,
+    };
+    let output = if returns_result {
+        quote! {
+            match #call {
+                ::std::result::Result::Ok(value) => {
+                    ::gemini_rust::__private::serde_json::to_value(value)
+                        .map_err(::gemini_rust::Error::from)
+                }
+                ::std::result::Result::Err(e) => ::std::result::Result::Err(
+                    ::gemini_rust::Error::FunctionCall(::std::string::ToString::to_string(&e)),
+                ),
+            }
+        }
+    } else {
+        quote! {
+            ::gemini_rust::__private::serde_json::to_value(#call)
+                .map_err(::gemini_rust::Error::from)
+        }
+    };
+
+    Ok(quote! {
+        #function
+
+        /// Function declaration generated by `#[gemini_tool]`
+        #vis fn #declaration_ident() -> ::gemini_rust::Result<::gemini_rust::FunctionDeclaration> {
+            ::std::result::Result::Ok(
+                ::gemini_rust::FunctionBuilder::new(#name)
+                    .description(#description)
+                    #(#params)*
+                    .build(),
+            )
+        }
+
+        /// Argument-deserializing wrapper generated by `#[gemini_tool]`
+        #[allow(unused_mut)]
+        #vis async fn #invoke_ident(
+            mut args: ::std::collections::HashMap<
+                ::std::string::String,
+                ::gemini_rust::__private::serde_json::Value,
+            >,
+        ) -> ::gemini_rust::Result<::gemini_rust::__private::serde_json::Value> {
+            #(#extractions)*
+            #output
+        }
+    })
+}
+
+/// How a parameter type is described
+enum JsonType<'a> {
+    /// A JSON primitive with this type name
+    Primitive(&'static str),
+    /// An array of the given item type
+    Array(&'a Type),
+    /// A type described by its `schemars::JsonSchema` implementation
+    Derived(&'a Type),
+}
+
+/// Build a `PropertySchema` expression for a parameter type
+///
+/// Derived schemas can fail, so the expression uses `?` and belongs in a function returning
+/// `gemini_rust::Result`.
+fn property_schema(ty: &Type, description: Option<&String>) -> syn::Result<TokenStream2> {
+    let description = match description {
+        Some(description) => {
+            quote! { ::std::option::Option::Some(::std::string::String::from(#description)) }
+        }
+        None => quote! { ::std::option::Option::None },
+    };
+    let (property_type, items) = match json_type(ty)? {
+        JsonType::Primitive(property_type) => (property_type, None),
+        JsonType::Array(item_ty) => ("array", Some(property_schema(item_ty, None)?)),
+        JsonType::Derived(ty) => {
+            return Ok(quote! {
+                ::gemini_rust::__private::property_schema::<#ty>(#description)?
+            })
+        }
+    };
+    let items = match items {
+        Some(schema) => quote! { ::std::option::Option::Some(::std::boxed::Box::new(#schema)) },
+        None => quote! { ::std::option::Option::None },
+    };
+
+    Ok(quote! {
+        ::gemini_rust::functions::PropertySchema {
+            property_type: ::std::string::String::from(#property_type),
+            description: #description,
+            nullable: ::std::option::Option::None,
+            enum_values: ::std::option::Option::None,
+            items: #items,
+            properties: ::std::option::Option::None,
+            required: ::std::option::Option::None,
+        }
+    })
+}
+
+/// Map a Rust type onto a JSON primitive, an array of its item type, or a derived schema
+fn json_type(ty: &Type) -> syn::Result<JsonType<'_>> {
+    match ty {
+        Type::Reference(reference) => Err(syn::Error::new_spanned(
+            reference,
+            "#[gemini_tool] parameters must be owned types",
+        )),
+        Type::Paren(paren) => json_type(&paren.elem),
+        Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last().expect("path has a segment");
+            let json_type = match segment.ident.to_string().as_str() {
+                "String" | "char" => "string",
+                "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+                | "u128" | "usize" => "integer",
+                "f32" | "f64" => "number",
+                "bool" => "boolean",
+                "Option" => {
+                    let inner = generic_inner(ty, "Option").expect("Option has a type argument");
+                    return json_type(inner);
+                }
+                "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => {
+                    let item = first_generic(segment).ok_or_else(|| {
+                        syn::Error::new_spanned(ty, "#[gemini_tool] cannot infer the item type")
+                    })?;
+                    return Ok(JsonType::Array(item));
+                }
+                "HashMap" | "BTreeMap" | "IndexMap" | "Map" | "Value" => {
+                    return Err(syn::Error::new_spanned(
+                        ty,
+                        "#[gemini_tool] cannot declare free-form objects; use a struct \
+                         deriving `schemars::JsonSchema` instead",
+                    ))
+                }
+                _ => return Ok(JsonType::Derived(ty)),
+            };
+            Ok(JsonType::Primitive(json_type))
+        }
+        Type::Slice(slice) => Ok(JsonType::Array(&slice.elem)),
+        Type::Array(array) => Ok(JsonType::Array(&array.elem)),
+        other => Err(syn::Error::new_spanned(
+            other,
+            "#[gemini_tool] cannot declare this parameter type; use a type deriving \
+             `schemars::JsonSchema` instead",
+        )),
+    }
+}
+
+fn last_segment_is(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name),
+        _ => false,
+    }
+}
+
+/// Return `T` when `ty` is `Wrapper<T>`
+fn generic_inner<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    match ty {
+        Type::Path(path) if last_segment_is(ty, wrapper) => {
+            first_generic(path.path.segments.last()?)
+        }
+        _ => None,
+    }
+}
+
+fn first_generic(segment: &syn::PathSegment) -> Option<&Type> {
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => {
+            arguments.args.iter().find_map(|argument| match argument {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Description and per-argument docs extracted from a doc comment
+struct Docs {
+    description: String,
+    arguments: std::collections::HashMap<String, String>,
+}
+
+impl Docs {
+    fn parse(attrs: &[Attribute]) -> Self {
+        let lines: Vec<String> = attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .filter_map(|attr| match &attr.meta {
+                Meta::NameValue(name_value) => match &name_value.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(s), ..
+                    }) => Some(s.value().trim().to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        let mut description = Vec::new();
+        let mut arguments: std::collections::HashMap<String, String> =
+            std::collections::HashMap::new();
+        let mut section: Option<String> = None;
+        let mut current: Option<String> = None;
+        let mut in_code = false;
+
+        for line in &lines {
+            if line.starts_with("```") || line.starts_with("~~~") {
+                in_code = !in_code;
+            }
+            // Lines starting with `#` inside code fences are code, such as hidden doctest lines
+            if let Some(heading) = line.strip_prefix('#').filter(|_| !in_code) {
+                section = Some(heading.trim_start_matches('#').trim().to_lowercase());
+                current = None;
+                continue;
+            }
+
+            match section.as_deref() {
+                None => description.push(line.as_str()),
+                Some("arguments") | Some("parameters") => {
+                    let item = line
+                        .strip_prefix('*')
+                        .or_else(|| line.strip_prefix('-'))
+                        .map(str::trim);
+                    match item.and_then(parse_argument_line) {
+                        Some((name, text)) => {
+                            arguments.insert(name.clone(), text);
+                            current = Some(name);
+                        }
+                        None if !line.is_empty() => {
+                            // Continuation of the previous argument's description
+                            let previous = current.as_ref().and_then(|n| arguments.get_mut(n));
+                            if let Some(text) = previous {
+                                text.push(' ');
+                                text.push_str(line);
+                            }
+                        }
+                        None => current = None,
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+
+        let description = description
+            .split(|line| line.is_empty())
+            .map(|paragraph| paragraph.join(" "))
+            .filter(|paragraph| !paragraph.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        Self {
+            description,
+            arguments,
+        }
+    }
+}
+
+/// Parse "`name` - description" (or "`name`: description")
+fn parse_argument_line(item: &str) -> Option<(String, String)> {
+    let rest = item.strip_prefix('`')?;
+    let end = rest.find('`')?;
+    let name = rest[..end].to_string();
+    let text = rest[end + 1..]
+        .trim_start()
+        .trim_start_matches(['-', ':', '—'])
+        .trim()
+        .to_string();
+    Some((name, text))
+}
@@ -0,0 +1,347 @@
+//! Agents driving the reason → call tools → observe loop
+
+use crate::{
+    client::GeminiClient,
+    error::Result,
+    functions::{FunctionCall, FunctionResponse, ToolRegistry},
+    memory::{FullHistory, Memory},
+    models::{Content, GenerateContentRequest, GenerateContentResponse, Part, Role},
+};
+use tracing::debug;
+
+/// Predicate deciding from a response that the agent should stop
+pub type StopCondition = Box<dyn Fn(&GenerateContentResponse) -> bool + Send + Sync>;
+
+/// A model, system prompt, tools and memory run as a loop until a termination condition
+///
+/// Each turn sends the remembered history to the model. Function calls in the answer are
+/// executed with the tool registry and their results fed back; the loop ends when the model
+/// answers without calls, or earlier when the turn limit, token budget or stop condition is
+/// reached. Function calls left unanswered by an early stop are not kept in memory.
+pub struct Agent {
+    client: GeminiClient,
+    model: Option<String>,
+    system_prompt: Option<String>,
+    tools: ToolRegistry,
+    memory: Box<dyn Memory>,
+    max_turns: usize,
+    token_budget: Option<u64>,
+    stop_when: Option<StopCondition>,
+}
+
+/// Why an agent run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The model answered without calling a function
+    Completed,
+    /// The turn limit was reached
+    MaxTurns,
+    /// The token budget was used up
+    TokenBudget,
+    /// The stop condition matched a response
+    StopCondition,
+}
+
+/// The outcome of an agent run
+#[derive(Debug, Clone)]
+pub struct AgentRun {
+    /// The last model response
+    pub response: GenerateContentResponse,
+    /// Number of model calls made
+    pub turns: usize,
+    /// Total tokens reported across all turns
+    pub total_tokens: u64,
+    /// Why the run ended
+    pub stop_reason: StopReason,
+}
+
+/// Progress of a streamed agent run
+#[cfg(feature = "streaming")]
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    /// New answer text
+    TextDelta(String),
+    /// The model called a function, which is about to be executed
+    ToolCall(FunctionCall),
+    /// A function finished; its result is sent back on the next turn
+    ToolResult(FunctionResponse),
+    /// The run ended
+    Finished(Box<AgentRun>),
+}
+
+/// What follows a model response
+enum Step {
+    Call(Vec<FunctionCall>),
+    Stop(StopReason),
+}
+
+impl Agent {
+    /// Start building an agent on a client
+    pub fn builder(client: GeminiClient) -> AgentBuilder {
+        AgentBuilder {
+            agent: Agent {
+                client,
+                model: None,
+                system_prompt: None,
+                tools: ToolRegistry::new(),
+                memory: Box::new(FullHistory::new()),
+                max_turns: 10,
+                token_budget: None,
+                stop_when: None,
+            },
+        }
+    }
+
+    /// The agent's memory
+    pub fn memory(&self) -> &dyn Memory {
+        self.memory.as_ref()
+    }
+
+    /// The agent's memory, e.g. to clear it between tasks
+    pub fn memory_mut(&mut self) -> &mut dyn Memory {
+        self.memory.as_mut()
+    }
+
+    /// Run the loop for a user message until a termination condition is reached
+    pub async fn run(&mut self, input: impl Into<Content>) -> Result<AgentRun> {
+        self.memory.push(input.into());
+        let mut total_tokens = 0;
+        let mut turn = 0;
+        loop {
+            turn += 1;
+            self.memory
+                .prepare(&self.client, self.model.as_deref())
+                .await?;
+            let response = self
+                .client
+                .generate_content(self.model.as_deref(), self.request())
+                .await?;
+            match self.decide(&response, turn, &mut total_tokens) {
+                Step::Call(calls) => {
+                    self.call_tools(&calls).await?;
+                }
+                Step::Stop(stop_reason) => {
+                    return Ok(AgentRun {
+                        response,
+                        turns: turn,
+                        total_tokens,
+                        stop_reason,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Like [`run`](Self::run), but streams answer text and tool activity as it happens
+    #[cfg(feature = "streaming")]
+    pub fn run_stream(
+        &mut self,
+        input: impl Into<Content>,
+    ) -> std::pin::Pin<Box<dyn futures::Stream<Item = Result<AgentEvent>> + Send + '_>> {
+        use futures::{FutureExt, StreamExt};
+
+        let (events, receiver) = futures::channel::mpsc::unbounded();
+        let input = input.into();
+        let driver = async move {
+            if let Err(error) = self.drive_stream(input, &events).await {
+                let _ = events.unbounded_send(Err(error));
+            }
+        };
+        Box::pin(futures::stream::select(
+            receiver,
+            driver.into_stream().filter_map(|()| async { None }),
+        ))
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn drive_stream(
+        &mut self,
+        input: Content,
+        events: &futures::channel::mpsc::UnboundedSender<Result<AgentEvent>>,
+    ) -> Result<()> {
+        use crate::streaming::StreamAccumulator;
+        use futures::StreamExt;
+
+        let send = |event| {
+            let _ = events.unbounded_send(Ok(event));
+        };
+
+        self.memory.push(input);
+        let mut total_tokens = 0;
+        let mut turn = 0;
+        loop {
+            turn += 1;
+            self.memory
+                .prepare(&self.client, self.model.as_deref())
+                .await?;
+            let mut stream = Box::pin(
+                self.client
+                    .stream_generate_content(self.model.as_deref(), self.request())
+                    .await?,
+            );
+            let mut accumulator = StreamAccumulator::new();
+            while let Some(chunk) = stream.next().await {
+                if let Some(text) = accumulator.process_chunk(chunk?) {
+                    send(AgentEvent::TextDelta(text));
+                }
+            }
+            let response = accumulator.finalize().ok_or_else(|| {
+                crate::Error::Streaming("Stream ended without a response".to_string())
+            })?;
+
+            match self.decide(&response, turn, &mut total_tokens) {
+                Step::Call(calls) => {
+                    for call in &calls {
+                        send(AgentEvent::ToolCall(call.clone()));
+                    }
+                    for result in self.call_tools(&calls).await? {
+                        send(AgentEvent::ToolResult(result));
+                    }
+                }
+                Step::Stop(stop_reason) => {
+                    send(AgentEvent::Finished(Box::new(AgentRun {
+                        response,
+                        turns: turn,
+                        total_tokens,
+                        stop_reason,
+                    })));
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn request(&self) -> GenerateContentRequest {
+        let mut request = GenerateContentRequest::new(self.memory.history());
+        request.system_instruction = self.system_prompt.clone().map(Content::system);
+        if !self.tools.declarations().is_empty() {
+            request.tools = Some(vec![self.tools.tool()]);
+        }
+        request
+    }
+
+    /// Record a response and decide whether to call tools or stop
+    fn decide(
+        &mut self,
+        response: &GenerateContentResponse,
+        turn: usize,
+        total_tokens: &mut u64,
+    ) -> Step {
+        if let Some(usage) = &response.usage_metadata {
+            *total_tokens += usage.total_token_count.max(0) as u64;
+        }
+        let Some(candidate) = response.candidates.first() else {
+            return Step::Stop(StopReason::Completed);
+        };
+        let calls: Vec<FunctionCall> = candidate
+            .content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::FunctionCall { function_call, .. } => Some(function_call.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let stop = if self.stop_when.as_ref().is_some_and(|stop| stop(response)) {
+            Some(StopReason::StopCondition)
+        } else if calls.is_empty() {
+            Some(StopReason::Completed)
+        } else if self
+            .token_budget
+            .is_some_and(|budget| *total_tokens >= budget)
+        {
+            Some(StopReason::TokenBudget)
+        } else if turn >= self.max_turns {
+            Some(StopReason::MaxTurns)
+        } else {
+            None
+        };
+
+        match stop {
+            Some(reason) => {
+                if calls.is_empty() {
+                    self.memory.push(candidate.content.clone());
+                }
+                debug!("Agent stopped after {} turns: {:?}", turn, reason);
+                Step::Stop(reason)
+            }
+            None => {
+                self.memory.push(candidate.content.clone());
+                Step::Call(calls)
+            }
+        }
+    }
+
+    /// Execute function calls and remember their results
+    async fn call_tools(&mut self, calls: &[FunctionCall]) -> Result<Vec<FunctionResponse>> {
+        debug!("Agent executing {} function calls", calls.len());
+        let results = self.tools.execute_all(calls).await?;
+        self.memory.push(Content {
+            role: Role::User,
+            parts: results
+                .iter()
+                .cloned()
+                .map(|function_response| Part::FunctionResponse { function_response })
+                .collect(),
+        });
+        Ok(results)
+    }
+}
+
+/// Builder for [`Agent`]
+pub struct AgentBuilder {
+    agent: Agent,
+}
+
+impl AgentBuilder {
+    /// Use a model other than the client's default
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.agent.model = Some(model.into());
+        self
+    }
+
+    /// Set the system prompt sent with every turn
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.agent.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Set the functions the model may call
+    pub fn tools(mut self, tools: ToolRegistry) -> Self {
+        self.agent.tools = tools;
+        self
+    }
+
+    /// Set the history strategy (default: [`FullHistory`])
+    pub fn memory(mut self, memory: impl Memory + 'static) -> Self {
+        self.agent.memory = Box::new(memory);
+        self
+    }
+
+    /// Maximum model calls per run (default 10, at least 1)
+    pub fn max_turns(mut self, max_turns: usize) -> Self {
+        self.agent.max_turns = max_turns.max(1);
+        self
+    }
+
+    /// Stop once the reported token usage of a run reaches this many tokens
+    pub fn token_budget(mut self, tokens: u64) -> Self {
+        self.agent.token_budget = Some(tokens);
+        self
+    }
+
+    /// Stop as soon as a response matches the predicate
+    pub fn stop_when(
+        mut self,
+        condition: impl Fn(&GenerateContentResponse) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.agent.stop_when = Some(Box::new(condition));
+        self
+    }
+
+    /// Build the agent
+    pub fn build(self) -> Agent {
+        self.agent
+    }
+}
@@ -0,0 +1,237 @@
+//! Audio output
+//!
+//! Speech models return raw 16-bit PCM as inline data parts with a MIME type such as
+//! `audio/L16;codec=pcm;rate=24000`. [`AudioChunk`] holds one decoded part with its sample
+//! rate, [`GenerateContentResponse::audio_chunks`] collects them from a response, and
+//! [`write_wav`] turns a sequence of chunks into a playable WAV file; [`read_wav`] goes the
+//! other way. Streams yield chunks as they arrive through
+//! [`GeminiStreamExt::audio`](crate::streaming::GeminiStreamExt::audio).
+
+use crate::{
+    error::{Error, Result},
+    models::{GenerateContentResponse, InlineData, Part},
+};
+use base64::Engine;
+use std::io::Write;
+use std::path::Path;
+
+/// Sample rate assumed when the MIME type does not state one
+pub const DEFAULT_SAMPLE_RATE: u32 = 24_000;
+
+/// A piece of 16-bit little-endian PCM audio
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioChunk {
+    /// Raw PCM bytes, two per sample per channel
+    pub data: Vec<u8>,
+    /// Samples per second
+    pub sample_rate: u32,
+    /// Number of interleaved channels
+    pub channels: u16,
+}
+
+impl AudioChunk {
+    /// Decode an inline data part, `None` if it is not audio
+    ///
+    /// Fails for audio that is not raw PCM and for invalid base64.
+    pub fn from_inline_data(inline_data: &InlineData) -> Result<Option<Self>> {
+        let mut params = inline_data.mime_type.split(';').map(str::trim);
+        let essence = params.next().unwrap_or_default().to_ascii_lowercase();
+        if !essence.starts_with("audio/") {
+            return Ok(None);
+        }
+        if essence != "audio/l16" && essence != "audio/pcm" {
+            return Err(Error::InvalidResponse(format!(
+                "Unsupported audio format {}, expected raw PCM",
+                inline_data.mime_type
+            )));
+        }
+
+        let mut sample_rate = DEFAULT_SAMPLE_RATE;
+        let mut channels = 1;
+        for param in params {
+            match param.split_once('=') {
+                Some(("rate", value)) => sample_rate = value.parse().unwrap_or(sample_rate),
+                Some(("channels", value)) => channels = value.parse().unwrap_or(channels),
+                _ => {}
+            }
+        }
+
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(&inline_data.data)
+            .map_err(|e| Error::InvalidResponse(format!("Invalid base64 audio data: {}", e)))?;
+        Ok(Some(Self {
+            data,
+            sample_rate,
+            channels,
+        }))
+    }
+
+    /// The samples, interleaved by channel
+    pub fn samples(&self) -> Vec<i16> {
+        self.data
+            .chunks_exact(2)
+            .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect()
+    }
+
+    /// Length of the audio
+    pub fn duration(&self) -> std::time::Duration {
+        let frames = self.data.len() as u64 / (2 * self.channels.max(1) as u64);
+        std::time::Duration::from_secs_f64(frames as f64 / self.sample_rate.max(1) as f64)
+    }
+
+    /// Cut the audio into consecutive pieces no longer than `max`
+    ///
+    /// Cuts fall on frame boundaries, so every piece keeps whole samples for each channel.
+    pub fn split(&self, max: std::time::Duration) -> Vec<AudioChunk> {
+        let frame_len = 2 * self.channels.max(1) as usize;
+        let frames = ((max.as_secs_f64() * self.sample_rate as f64) as usize).max(1);
+        self.data
+            .chunks(frames * frame_len)
+            .map(|data| AudioChunk {
+                data: data.to_vec(),
+                sample_rate: self.sample_rate,
+                channels: self.channels,
+            })
+            .collect()
+    }
+}
+
+/// Format fields of a WAV file's `fmt ` chunk
+struct WavFormat {
+    tag: u16,
+    channels: u16,
+    sample_rate: u32,
+    byte_rate: u32,
+    bits: u16,
+}
+
+fn invalid_wav(reason: &str) -> Error {
+    Error::Config(format!("Unsupported WAV file: {}", reason))
+}
+
+/// The format and sample data of a WAV file
+fn parse_wav(bytes: &[u8]) -> Result<(WavFormat, &[u8])> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid_wav("missing RIFF/WAVE header"));
+    }
+
+    let mut format = None;
+    let mut pos = 12;
+    while let Some(header) = bytes.get(pos..pos + 8) {
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let body = bytes
+            .get(pos + 8..pos + 8 + len)
+            .ok_or_else(|| invalid_wav("truncated chunk"))?;
+        match &header[..4] {
+            b"fmt " if body.len() >= 16 => {
+                format = Some(WavFormat {
+                    tag: u16::from_le_bytes([body[0], body[1]]),
+                    channels: u16::from_le_bytes([body[2], body[3]]),
+                    sample_rate: u32::from_le_bytes([body[4], body[5], body[6], body[7]]),
+                    byte_rate: u32::from_le_bytes([body[8], body[9], body[10], body[11]]),
+                    bits: u16::from_le_bytes([body[14], body[15]]),
+                });
+            }
+            b"data" => {
+                let format = format.ok_or_else(|| invalid_wav("data before format"))?;
+                return Ok((format, body));
+            }
+            _ => {}
+        }
+        pos += 8 + len + len % 2;
+    }
+    Err(invalid_wav("no data chunk"))
+}
+
+/// Read a 16-bit PCM WAV file into a single chunk
+pub fn read_wav(bytes: &[u8]) -> Result<AudioChunk> {
+    let (format, data) = parse_wav(bytes)?;
+    // 1 is plain PCM, 0xFFFE the extensible format that wraps it
+    if !matches!(format.tag, 1 | 0xFFFE) || format.bits != 16 {
+        return Err(invalid_wav("only 16-bit PCM is supported"));
+    }
+    Ok(AudioChunk {
+        data: data.to_vec(),
+        sample_rate: format.sample_rate,
+        channels: format.channels,
+    })
+}
+
+/// Length of the audio in a WAV file of any encoding, from its byte rate
+pub fn wav_duration(bytes: &[u8]) -> Result<std::time::Duration> {
+    let (format, data) = parse_wav(bytes)?;
+    if format.byte_rate == 0 {
+        return Err(invalid_wav("zero byte rate"));
+    }
+    Ok(std::time::Duration::from_secs_f64(
+        data.len() as f64 / format.byte_rate as f64,
+    ))
+}
+
+impl GenerateContentResponse {
+    /// Decoded audio parts of the first candidate, in order
+    pub fn audio_chunks(&self) -> Result<Vec<AudioChunk>> {
+        let mut chunks = Vec::new();
+        for part in self
+            .candidates
+            .first()
+            .iter()
+            .flat_map(|c| &c.content.parts)
+        {
+            if let Part::InlineData { inline_data, .. } = part {
+                chunks.extend(AudioChunk::from_inline_data(inline_data)?);
+            }
+        }
+        Ok(chunks)
+    }
+}
+
+/// Write `chunks` to `writer` as a single 16-bit PCM WAV file
+///
+/// Every chunk must have the same sample rate and channel count.
+pub fn write_wav<W: Write>(mut writer: W, chunks: &[AudioChunk]) -> Result<()> {
+    let (sample_rate, channels) = chunks
+        .first()
+        .map(|chunk| (chunk.sample_rate, chunk.channels))
+        .unwrap_or((DEFAULT_SAMPLE_RATE, 1));
+    if let Some(chunk) = chunks
+        .iter()
+        .find(|chunk| (chunk.sample_rate, chunk.channels) != (sample_rate, channels))
+    {
+        return Err(Error::InvalidResponse(format!(
+            "Cannot join audio at {} Hz x {} with {} Hz x {}",
+            chunk.sample_rate, chunk.channels, sample_rate, channels
+        )));
+    }
+
+    let data_len: usize = chunks.iter().map(|chunk| chunk.data.len()).sum();
+    let data_len = u32::try_from(data_len)
+        .map_err(|_| Error::InvalidResponse("Audio too long for a WAV file".to_string()))?;
+    let block_align = channels * 2;
+    let byte_rate = sample_rate * block_align as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_len).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&byte_rate.to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+    for chunk in chunks {
+        writer.write_all(&chunk.data)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write `chunks` to a WAV file at `path`
+pub fn save_wav(path: impl AsRef<Path>, chunks: &[AudioChunk]) -> Result<()> {
+    let file = std::fs::File::create(path)?;
+    write_wav(std::io::BufWriter::new(file), chunks)
+}
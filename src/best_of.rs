@@ -0,0 +1,123 @@
+//! Best-of-n sampling
+
+use crate::{
+    client::GeminiClient,
+    error::{Error, Result},
+    models::{Candidate, GenerateContentRequest},
+};
+use std::future::Future;
+use tracing::debug;
+
+/// How the candidates of a best-of-n run are generated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sampling {
+    /// One request with `candidate_count` set to n
+    #[default]
+    CandidateCount,
+    /// n concurrent requests, for models that do not support several candidates
+    ParallelCalls,
+}
+
+/// A candidate and the score it was given
+#[derive(Debug, Clone)]
+pub struct ScoredCandidate {
+    /// The generated candidate
+    pub candidate: Candidate,
+    /// Score returned by the scorer; higher is better
+    pub score: f64,
+}
+
+/// The outcome of [`GeminiClient::generate_best_of`]
+#[derive(Debug, Clone)]
+pub struct BestOf {
+    /// The highest-scoring candidate
+    pub winner: ScoredCandidate,
+    /// Every candidate in generation order, including the winner
+    pub candidates: Vec<ScoredCandidate>,
+}
+
+impl GeminiClient {
+    /// Generate `n` candidates, score each one and return the best
+    ///
+    /// The scorer runs concurrently for all candidates and may itself call a model, e.g. to
+    /// have a judge rate the answers. A scorer error fails the whole call. Ties go to the
+    /// earlier candidate; NaN scores rank lowest.
+    pub async fn generate_best_of<F, Fut>(
+        &self,
+        model: Option<&str>,
+        request: GenerateContentRequest,
+        n: u32,
+        sampling: Sampling,
+        scorer: F,
+    ) -> Result<BestOf>
+    where
+        F: Fn(Candidate) -> Fut,
+        Fut: Future<Output = Result<f64>>,
+    {
+        if n == 0 {
+            return Err(Error::Config(
+                "best-of sampling needs at least one candidate".to_string(),
+            ));
+        }
+
+        let candidates = match sampling {
+            Sampling::CandidateCount => {
+                let mut request = request;
+                request
+                    .generation_config
+                    .get_or_insert_with(Default::default)
+                    .candidate_count = Some(n as i32);
+                self.generate_content(model, request)
+                    .await?
+                    .require_content()?
+                    .candidates
+            }
+            Sampling::ParallelCalls => {
+                let responses = futures::future::try_join_all(
+                    (0..n).map(|_| self.generate_content(model, request.clone())),
+                )
+                .await?;
+                responses
+                    .into_iter()
+                    .filter_map(|response| response.candidates.into_iter().next())
+                    .enumerate()
+                    .map(|(index, mut candidate)| {
+                        candidate.index = Some(index as i32);
+                        candidate
+                    })
+                    .collect()
+            }
+        };
+        debug!("Scoring {} of {} requested candidates", candidates.len(), n);
+
+        let scores = futures::future::try_join_all(
+            candidates.iter().map(|candidate| scorer(candidate.clone())),
+        )
+        .await?;
+        let candidates: Vec<ScoredCandidate> = candidates
+            .into_iter()
+            .zip(scores)
+            .map(|(candidate, score)| ScoredCandidate { candidate, score })
+            .collect();
+
+        let winner = candidates
+            .iter()
+            .rev()
+            .max_by(|a, b| rank(a.score).total_cmp(&rank(b.score)))
+            .cloned()
+            .ok_or(Error::EmptyResponse {
+                finish_reason: None,
+                block_reason: None,
+            })?;
+        Ok(BestOf { winner, candidates })
+    }
+}
+
+/// Order NaN below every other score
+fn rank(score: f64) -> f64 {
+    if score.is_nan() {
+        f64::NEG_INFINITY
+    } else {
+        score
+    }
+}
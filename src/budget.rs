@@ -0,0 +1,151 @@
+//! Token budgets across a conversation
+//!
+//! A [`TokenBudget`] adds up the [`UsageMetadata`] of each response against a ceiling and,
+//! before a request, compares the locally estimated prompt size with what is left. Attached to
+//! a [`ChatSession`](crate::ChatSession) with
+//! [`with_token_budget`](crate::ChatSession::with_token_budget), it warns as the ceiling nears
+//! and then warns, leaves old exchanges out of the request, or refuses to send, as set by
+//! [`OverBudget`].
+
+use crate::{
+    error::{Error, Result},
+    memory::trim_to_fit,
+    models::{estimate_tokens, Content, UsageMetadata},
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Default fraction of the ceiling at which requests start logging warnings
+const DEFAULT_WARN_RATIO: f64 = 0.8;
+
+/// What to do with a request whose estimated prompt does not fit the remaining budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverBudget {
+    /// Log a warning and send it anyway
+    #[default]
+    Warn,
+    /// Leave the oldest exchanges out of the request until it fits
+    ///
+    /// The remembered history is unchanged. Fails with `Error::TokenBudgetExceeded` if the
+    /// latest exchange alone does not fit.
+    Truncate,
+    /// Fail with `Error::TokenBudgetExceeded`
+    Reject,
+}
+
+/// Cumulative token usage against a ceiling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBudget {
+    /// Maximum total tokens, including prompts, answers and thinking
+    pub ceiling: u64,
+
+    /// Fraction of the ceiling past which requests log a warning
+    #[serde(default = "default_warn_ratio")]
+    pub warn_ratio: f64,
+
+    /// What to do with requests that do not fit
+    #[serde(default)]
+    pub over_budget: OverBudget,
+
+    /// Prompt tokens used so far
+    #[serde(default)]
+    pub prompt_tokens: u64,
+
+    /// Answer tokens used so far
+    #[serde(default)]
+    pub output_tokens: u64,
+
+    /// Total tokens used so far
+    #[serde(default)]
+    pub total_tokens: u64,
+}
+
+fn default_warn_ratio() -> f64 {
+    DEFAULT_WARN_RATIO
+}
+
+impl TokenBudget {
+    /// A budget of `ceiling` total tokens that warns at 80% and when exceeded
+    pub fn new(ceiling: u64) -> Self {
+        Self {
+            ceiling,
+            warn_ratio: DEFAULT_WARN_RATIO,
+            over_budget: OverBudget::default(),
+            prompt_tokens: 0,
+            output_tokens: 0,
+            total_tokens: 0,
+        }
+    }
+
+    /// Warn once usage passes this fraction of the ceiling
+    pub fn with_warn_ratio(mut self, ratio: f64) -> Self {
+        self.warn_ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Handle requests that do not fit the remaining budget this way
+    pub fn with_over_budget(mut self, over_budget: OverBudget) -> Self {
+        self.over_budget = over_budget;
+        self
+    }
+
+    /// Add a response's usage
+    pub fn record(&mut self, usage: &UsageMetadata) {
+        self.prompt_tokens += usage.prompt_token_count.max(0) as u64;
+        self.output_tokens += usage.candidates_token_count.max(0) as u64;
+        self.total_tokens += usage.total_token_count.max(0) as u64;
+    }
+
+    /// Tokens left before the ceiling
+    pub fn remaining(&self) -> u64 {
+        self.ceiling.saturating_sub(self.total_tokens)
+    }
+
+    /// Whether usage has reached the ceiling
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Forget the usage so far, keeping the ceiling and settings
+    pub fn reset(&mut self) {
+        self.prompt_tokens = 0;
+        self.output_tokens = 0;
+        self.total_tokens = 0;
+    }
+
+    /// Check the contents of the next request, returning the contents to send
+    ///
+    /// `fixed_tokens` covers what is sent besides `contents` and cannot be left out, such as
+    /// the system prompt.
+    pub fn fit(&self, contents: Vec<Content>, fixed_tokens: u64) -> Result<Vec<Content>> {
+        let remaining = self.remaining();
+        let needed = fixed_tokens + estimate_tokens(&contents) as u64;
+        let warn_at = (self.ceiling as f64 * self.warn_ratio) as u64;
+        if needed <= remaining {
+            if self.total_tokens + needed > warn_at {
+                warn!(
+                    "Token budget nearly used: {} of {} tokens, next request about {}",
+                    self.total_tokens, self.ceiling, needed
+                );
+            }
+            return Ok(contents);
+        }
+
+        match self.over_budget {
+            OverBudget::Warn => {
+                warn!(
+                    "Request of about {} tokens exceeds the {} left in the token budget",
+                    needed, remaining
+                );
+                Ok(contents)
+            }
+            OverBudget::Truncate => trim_to_fit(contents, |contents| {
+                fixed_tokens + estimate_tokens(contents) as u64 <= remaining
+            })
+            .ok_or(Error::TokenBudgetExceeded { needed, remaining }),
+            OverBudget::Reject => Err(Error::TokenBudgetExceeded { needed, remaining }),
+        }
+    }
+}
@@ -1,19 +1,27 @@
 //! Context caching support for Gemini API
 
 use crate::{
-    client::GeminiClient,
+    client::{GeminiClient, API_KEY_HEADER},
     error::{Error, Result},
-    models::Content,
+    models::{
+        estimate_tokens, Content, GenerateContentRequest, GenerateContentResponse, Part, Tool,
+        ToolConfig, UsageMetadata,
+    },
 };
 use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{debug, info};
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, info, warn};
+
+pub mod store;
+pub use store::{CacheStore, JsonFileStore};
 
 /// Cache configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CacheConfig {
     /// Time to live for cached content (in seconds)
     pub ttl: Option<u64>,
@@ -21,6 +29,71 @@ pub struct CacheConfig {
     /// Display name for the cache
     #[serde(skip_serializing_if = "Option::is_none")]
     pub display_name: Option<String>,
+
+    /// Tools to cache alongside the contents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+
+    /// Tool configuration to cache alongside the contents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_config: Option<ToolConfig>,
+
+    /// Absolute expiration time (alternative to `ttl`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expire_time: Option<DateTime<Utc>>,
+
+    /// Maximum number of automatically created caches to keep; the oldest are deleted first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_entries: Option<usize>,
+}
+
+/// Policy for automatically caching large request prefixes
+///
+/// When installed with [`GeminiClient::with_auto_cache`], every request without explicit
+/// cached content whose prefix (system instruction, tools, and all but the last content) is
+/// estimated above `min_tokens` is rewritten to use a cache of that prefix. Caches are keyed
+/// by a hash of the prefix, so later requests sharing it reuse the same cache.
+#[derive(Debug, Clone)]
+pub struct AutoCachePolicy {
+    /// Minimum estimated prefix size in tokens before caching
+    pub min_tokens: usize,
+
+    /// TTL and eviction settings for created caches
+    pub config: CacheConfig,
+}
+
+impl Default for AutoCachePolicy {
+    fn default() -> Self {
+        Self {
+            min_tokens: 4096,
+            config: CacheConfig {
+                ttl: Some(3600),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl AutoCachePolicy {
+    /// Create a policy caching prefixes of at least `min_tokens` estimated tokens
+    pub fn new(min_tokens: usize) -> Self {
+        Self {
+            min_tokens,
+            ..Default::default()
+        }
+    }
+
+    /// Set the TTL of created caches
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.config.ttl = Some(ttl.as_secs());
+        self
+    }
+
+    /// Keep at most `max_entries` automatic caches, deleting the oldest
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.config.max_entries = Some(max_entries);
+        self
+    }
 }
 
 /// Cached content reference
@@ -58,13 +131,131 @@ struct CreateCacheRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     system_instruction: Option<Content>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<Tool>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_config: Option<ToolConfig>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     ttl: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expire_time: Option<DateTime<Utc>>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     display_name: Option<String>,
 }
 
+/// Changes to apply to existing cached content
+///
+/// Only the expiration can be updated; set either a TTL or an absolute expire time.
+#[derive(Debug, Clone, Default)]
+pub struct CacheUpdate {
+    ttl: Option<Duration>,
+    expire_time: Option<DateTime<Utc>>,
+}
+
+impl CacheUpdate {
+    /// Create an empty update
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expire the cache this long from now
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Expire the cache at an absolute time
+    pub fn expire_time(mut self, expire_time: DateTime<Utc>) -> Self {
+        self.expire_time = Some(expire_time);
+        self
+    }
+
+    /// Request body and update mask for the API
+    fn into_patch(self) -> Result<(serde_json::Value, String)> {
+        let mut body = serde_json::Map::new();
+        let mut mask = Vec::new();
+
+        if let Some(ttl) = self.ttl {
+            body.insert("ttl".to_string(), format!("{}s", ttl.as_secs()).into());
+            mask.push("ttl");
+        }
+        if let Some(expire_time) = self.expire_time {
+            body.insert("expireTime".to_string(), expire_time.to_rfc3339().into());
+            mask.push("expireTime");
+        }
+
+        match mask.len() {
+            0 => Err(Error::Config("Cache update has no fields set".to_string())),
+            1 => Ok((serde_json::Value::Object(body), mask.join(","))),
+            _ => Err(Error::Config(
+                "Cache update cannot set both ttl and expire_time".to_string(),
+            )),
+        }
+    }
+}
+
+/// How `create_cache` rewrites model names before sending them to the API
+#[derive(Clone, Default)]
+pub enum ModelNormalization {
+    /// Use the model name unchanged
+    #[default]
+    AsIs,
+
+    /// Append a version suffix (e.g. "-001") to names that do not already end in a version
+    ///
+    /// Only needed for older models that reject unversioned names for caching.
+    PinVersion(String),
+
+    /// Rewrite names with a custom function
+    Custom(Arc<dyn Fn(&str) -> String + Send + Sync>),
+}
+
+impl ModelNormalization {
+    /// Rewrite a model name according to this strategy
+    pub fn apply(&self, model: &str) -> String {
+        match self {
+            ModelNormalization::AsIs => model.to_string(),
+            ModelNormalization::PinVersion(suffix) => {
+                let versioned = model.rsplit('-').next().is_some_and(|last| {
+                    last.len() == 3 && last.bytes().all(|b| b.is_ascii_digit())
+                });
+                if versioned {
+                    model.to_string()
+                } else {
+                    format!("{}{}", model, suffix)
+                }
+            }
+            ModelNormalization::Custom(f) => f(model),
+        }
+    }
+}
+
+/// Reject models that cannot back a context cache
+fn check_cache_support(model: &str) -> Result<()> {
+    let name = model.strip_prefix("models/").unwrap_or(model);
+
+    if name.ends_with("-latest") {
+        return Err(Error::Config(format!(
+            "Context caching requires a fixed model version, not the alias {}",
+            name
+        )));
+    }
+
+    let unsupported = ["embedding", "imagen", "veo", "aqa", "gemma", "gemini-1.0"];
+    if let Some(kind) = unsupported.iter().find(|kind| name.contains(*kind)) {
+        return Err(Error::Config(format!(
+            "Model {} does not support context caching ({} models cannot be cached)",
+            name, kind
+        )));
+    }
+
+    Ok(())
+}
+
 /// Cache manager for handling context caching
 pub struct CacheManager {
     /// In-memory cache tracking
@@ -72,6 +263,27 @@ pub struct CacheManager {
 
     /// Cache by display name for easy lookup
     name_index: Arc<RwLock<HashMap<String, String>>>,
+
+    /// Automatically created caches by prefix hash
+    prefix_index: Arc<RwLock<HashMap<String, CachedContent>>>,
+
+    /// Per-key locks serializing `get_or_create` calls
+    in_flight: Arc<InFlightLocks>,
+
+    /// Base URL each cache was created on, by resource name
+    endpoints: Arc<RwLock<HashMap<String, String>>>,
+
+    /// Persistent backing store for the registry
+    store: Option<Arc<dyn CacheStore>>,
+
+    /// How model names are rewritten before creating a cache
+    model_normalization: ModelNormalization,
+
+    /// Token usage served from each cache
+    usage: Arc<std::sync::Mutex<HashMap<String, CacheUsage>>>,
+
+    /// Prices used to estimate savings
+    pricing: CachePricing,
 }
 
 impl Default for CacheManager {
@@ -86,10 +298,172 @@ impl CacheManager {
         Self {
             cache_registry: Arc::new(RwLock::new(HashMap::new())),
             name_index: Arc::new(RwLock::new(HashMap::new())),
+            prefix_index: Arc::new(RwLock::new(HashMap::new())),
+            in_flight: Arc::new(InFlightLocks::default()),
+            endpoints: Arc::new(RwLock::new(HashMap::new())),
+            store: None,
+            model_normalization: ModelNormalization::default(),
+            usage: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            pricing: CachePricing::default(),
+        }
+    }
+
+    /// Set the prices used by [`savings_report`](Self::savings_report)
+    pub fn with_pricing(mut self, pricing: CachePricing) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Record the usage of a response generated with cached content
+    pub fn record_usage(&self, cache_name: &str, usage: &UsageMetadata) {
+        let mut stats = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = stats.entry(cache_name.to_string()).or_default();
+        entry.requests += 1;
+        entry.prompt_tokens += usage.prompt_token_count.max(0) as u64;
+        entry.cached_tokens += usage.cached_content_token_count.unwrap_or(0).max(0) as u64;
+    }
+
+    /// Summarize tokens served from cache and the estimated input cost saved
+    pub fn savings_report(&self) -> SavingsReport {
+        let per_cache = self.usage.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let cached_tokens: u64 = per_cache.values().map(|u| u.cached_tokens).sum();
+        let discount = (self.pricing.input_per_million - self.pricing.cached_per_million).max(0.0);
+
+        SavingsReport {
+            requests: per_cache.values().map(|u| u.requests).sum(),
+            prompt_tokens: per_cache.values().map(|u| u.prompt_tokens).sum(),
+            cached_tokens,
+            estimated_savings_usd: cached_tokens as f64 / 1_000_000.0 * discount,
+            per_cache,
+        }
+    }
+
+    /// Set how model names are rewritten before creating a cache
+    pub fn with_model_normalization(mut self, normalization: ModelNormalization) -> Self {
+        self.model_normalization = normalization;
+        self
+    }
+
+    /// Create a cache manager whose registry is persisted to `store`
+    ///
+    /// Live entries already in the store are loaded immediately.
+    pub fn with_store(store: Arc<dyn CacheStore>) -> Result<Self> {
+        let mut registry = HashMap::new();
+        let mut index = HashMap::new();
+        for cached in store.load()?.into_iter().filter(CachedContent::is_live) {
+            if let Some(display_name) = &cached.display_name {
+                index.insert(display_name.clone(), cached.name.clone());
+            }
+            registry.insert(cached.name.clone(), cached);
+        }
+
+        Ok(Self {
+            cache_registry: Arc::new(RwLock::new(registry)),
+            name_index: Arc::new(RwLock::new(index)),
+            store: Some(store),
+            ..Self::new()
+        })
+    }
+
+    /// Reload entries from the backing store, picking up caches created by other workers
+    pub async fn reload(&self) -> Result<()> {
+        let Some(entries) = self.with_store_blocking(|store| store.load()).await else {
+            return Ok(());
+        };
+        let entries = entries?;
+        let mut registry = self.cache_registry.write().await;
+        let mut index = self.name_index.write().await;
+        for cached in entries.into_iter().filter(CachedContent::is_live) {
+            if let Some(display_name) = &cached.display_name {
+                index.insert(display_name.clone(), cached.name.clone());
+            }
+            registry.insert(cached.name.clone(), cached);
+        }
+        Ok(())
+    }
+
+    /// Run a blocking store operation on a blocking thread, or `None` without a store
+    async fn with_store_blocking<T: Send + 'static>(
+        &self,
+        op: impl FnOnce(&dyn CacheStore) -> Result<T> + Send + 'static,
+    ) -> Option<Result<T>> {
+        let store = self.store.clone()?;
+        Some(
+            tokio::task::spawn_blocking(move || op(store.as_ref()))
+                .await
+                .unwrap_or_else(|e| Err(Error::Cache(format!("Cache store task failed: {}", e)))),
+        )
+    }
+
+    /// Record a cache in the registry and the backing store
+    async fn register(&self, cached: &CachedContent) {
+        self.register_all(vec![cached.clone()]).await;
+    }
+
+    /// Record several caches, writing them to the backing store at once
+    async fn register_all(&self, entries: Vec<CachedContent>) {
+        if entries.is_empty() {
+            return;
+        }
+        {
+            let mut registry = self.cache_registry.write().await;
+            let mut index = self.name_index.write().await;
+            for cached in &entries {
+                registry.insert(cached.name.clone(), cached.clone());
+                if let Some(display_name) = &cached.display_name {
+                    index.insert(display_name.clone(), cached.name.clone());
+                }
+            }
+        }
+
+        let count = entries.len();
+        if let Some(Err(e)) = self
+            .with_store_blocking(move |store| store.put_all(&entries))
+            .await
+        {
+            warn!("Failed to persist {} cache entries: {}", count, e);
+        }
+    }
+
+    /// Base URL serving a cache: the endpoint it was created on, else its model's endpoint
+    async fn cache_base_url(&self, client: &GeminiClient, name: &str) -> String {
+        if let Some(url) = self.endpoints.read().await.get(name) {
+            return url.clone();
+        }
+        let registry = self.cache_registry.read().await;
+        match registry.get(name) {
+            Some(cached) => client.config().base_url_for(cached.model_name()),
+            None => &client.config().base_url,
+        }
+        .to_string()
+    }
+
+    /// Forget a cache in the registry and the backing store
+    async fn unregister(&self, name: &str) {
+        self.endpoints.write().await.remove(name);
+        if let Some(cached) = self.cache_registry.write().await.remove(name) {
+            if let Some(display_name) = cached.display_name {
+                let mut index = self.name_index.write().await;
+                if index.get(&display_name).map(String::as_str) == Some(name) {
+                    index.remove(&display_name);
+                }
+            }
+        }
+
+        let owned = name.to_string();
+        if let Some(Err(e)) = self
+            .with_store_blocking(move |store| store.remove(&owned))
+            .await
+        {
+            warn!("Failed to remove cache {} from store: {}", name, e);
         }
     }
 
     /// Create a new cached content
+    ///
+    /// Contents may include file parts (see [`Part::file`](crate::models::Part::file)) for
+    /// large PDFs or videos uploaded through the Files API. A rejected request is returned
+    /// as [`Error::Api`] or [`Error::RateLimit`].
     pub async fn create_cache(
         &self,
         client: &GeminiClient,
@@ -100,30 +474,30 @@ impl CacheManager {
     ) -> Result<CachedContent> {
         let model_name = client.config().get_model_name(model);
 
-        // Ensure we use a stable model version for caching
-        let cache_model = if model_name.contains("-latest") {
+        let cache_model = self.model_normalization.apply(&model_name);
+        check_cache_support(&cache_model)?;
+
+        if config.ttl.is_some() && config.expire_time.is_some() {
             return Err(Error::Config(
-                "Context caching requires a stable model version (e.g., gemini-1.5-pro-001)"
-                    .to_string(),
+                "Cache config cannot set both ttl and expire_time".to_string(),
             ));
-        } else if !model_name.contains("-00") {
-            // Add version suffix if not present
-            format!("{}-001", model_name)
-        } else {
-            model_name.clone()
-        };
+        }
 
         let request = CreateCacheRequest {
             model: cache_model,
             contents,
             system_instruction,
+            tools: config.tools,
+            tool_config: config.tool_config,
             ttl: config.ttl.map(|seconds| format!("{}s", seconds)),
+            expire_time: config.expire_time,
             display_name: config.display_name.clone(),
         };
 
+        let base_url = client.config().base_url_for(&model_name);
         let endpoint = format!(
             "{}/{}/cachedContents",
-            client.config().base_url,
+            base_url,
             client.config().api_version.as_str()
         );
 
@@ -135,30 +509,27 @@ impl CacheManager {
         let response = client
             .http_client()
             .post(&endpoint)
-            .query(&[("key", &client.config().api_key)])
+            .header(API_KEY_HEADER, client.config().api_key.expose())
             .json(&request)
             .send()
-            .await?;
+            .await
+            .map_err(|e| Error::from_reqwest(e, &client.config().http_config))?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(Error::Cache(format!(
-                "Failed to create cache (status {}): {}",
-                status, error_body
-            )));
+            return Err(client.error_from_response(response).await);
         }
 
-        let cached: CachedContent = response.json().await?;
+        let cached: CachedContent = response
+            .json()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &client.config().http_config))?;
 
         // Store in registry
-        let mut registry = self.cache_registry.write().await;
-        registry.insert(cached.name.clone(), cached.clone());
-
-        if let Some(display_name) = &cached.display_name {
-            let mut index = self.name_index.write().await;
-            index.insert(display_name.clone(), cached.name.clone());
-        }
+        self.endpoints
+            .write()
+            .await
+            .insert(cached.name.clone(), base_url.to_string());
+        self.register(&cached).await;
 
         info!("Created cached content: {}", cached.name);
 
@@ -183,7 +554,7 @@ impl CacheManager {
         // Fetch from API
         let endpoint = format!(
             "{}/{}/{}",
-            client.config().base_url,
+            self.cache_base_url(client, name).await,
             client.config().api_version.as_str(),
             name
         );
@@ -191,24 +562,22 @@ impl CacheManager {
         let response = client
             .http_client()
             .get(&endpoint)
-            .query(&[("key", &client.config().api_key)])
+            .header(API_KEY_HEADER, client.config().api_key.expose())
             .send()
-            .await?;
+            .await
+            .map_err(|e| Error::from_reqwest(e, &client.config().http_config))?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(Error::Cache(format!(
-                "Failed to get cache (status {}): {}",
-                status, error_body
-            )));
+            return Err(client.error_from_response(response).await);
         }
 
-        let cached: CachedContent = response.json().await?;
+        let cached: CachedContent = response
+            .json()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &client.config().http_config))?;
 
         // Update registry
-        let mut registry = self.cache_registry.write().await;
-        registry.insert(cached.name.clone(), cached.clone());
+        self.register(&cached).await;
 
         Ok(cached)
     }
@@ -219,11 +588,12 @@ impl CacheManager {
         client: &GeminiClient,
         display_name: &str,
     ) -> Result<CachedContent> {
-        // Look up resource name from index
-        let resource_name = {
-            let index = self.name_index.read().await;
-            index.get(display_name).cloned()
-        };
+        // Look up resource name from index, falling back to entries other workers stored
+        let mut resource_name = self.name_index.read().await.get(display_name).cloned();
+        if resource_name.is_none() && self.store.is_some() {
+            self.reload().await?;
+            resource_name = self.name_index.read().await.get(display_name).cloned();
+        }
 
         match resource_name {
             Some(name) => self.get_cache(client, &name).await,
@@ -234,66 +604,134 @@ impl CacheManager {
         }
     }
 
-    /// List all cached contents
+    /// List all cached contents on the default endpoint
     pub async fn list_caches(
         &self,
         client: &GeminiClient,
         page_size: Option<i32>,
         page_token: Option<&str>,
+    ) -> Result<ListCachesResponse> {
+        self.list_page(client, &client.config().base_url, page_size, page_token)
+            .await
+    }
+
+    /// List cached contents on the endpoint serving `model`
+    pub async fn list_caches_for_model(
+        &self,
+        client: &GeminiClient,
+        model: &str,
+        page_size: Option<i32>,
+        page_token: Option<&str>,
+    ) -> Result<ListCachesResponse> {
+        let base_url = client.config().base_url_for(model);
+        self.list_page(client, base_url, page_size, page_token)
+            .await
+    }
+
+    async fn list_page(
+        &self,
+        client: &GeminiClient,
+        base_url: &str,
+        page_size: Option<i32>,
+        page_token: Option<&str>,
     ) -> Result<ListCachesResponse> {
         let endpoint = format!(
             "{}/{}/cachedContents",
-            client.config().base_url,
+            base_url,
             client.config().api_version.as_str()
         );
 
-        let mut query = vec![("key", client.config().api_key.as_str())];
-
-        let page_size_str;
+        let mut query = Vec::new();
         if let Some(size) = page_size {
-            page_size_str = size.to_string();
-            query.push(("pageSize", &page_size_str));
+            query.push(("pageSize", size.to_string()));
         }
-
         if let Some(token) = page_token {
-            query.push(("pageToken", token));
+            query.push(("pageToken", token.to_string()));
         }
 
         let response = client
             .http_client()
             .get(&endpoint)
+            .header(API_KEY_HEADER, client.config().api_key.expose())
             .query(&query)
             .send()
-            .await?;
+            .await
+            .map_err(|e| Error::from_reqwest(e, &client.config().http_config))?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(Error::Cache(format!(
-                "Failed to list caches (status {}): {}",
-                status, error_body
-            )));
+            return Err(client.error_from_response(response).await);
         }
 
-        let list_response: ListCachesResponse = response.json().await?;
+        let list_response: ListCachesResponse = response
+            .json()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &client.config().http_config))?;
 
         // Update registry with all caches
-        if let Some(caches) = &list_response.cached_contents {
-            let mut registry = self.cache_registry.write().await;
-            let mut index = self.name_index.write().await;
-
-            for cached in caches {
-                registry.insert(cached.name.clone(), cached.clone());
-
-                if let Some(display_name) = &cached.display_name {
-                    index.insert(display_name.clone(), cached.name.clone());
-                }
+        let listed = list_response.cached_contents.clone().unwrap_or_default();
+        {
+            let mut endpoints = self.endpoints.write().await;
+            for cached in &listed {
+                endpoints.insert(cached.name.clone(), base_url.to_string());
             }
         }
+        self.register_all(listed).await;
 
         Ok(list_response)
     }
 
+    /// Stream every cached content on the default endpoint, following page tokens as needed
+    pub fn list_all<'a>(
+        &'a self,
+        client: &'a GeminiClient,
+    ) -> impl Stream<Item = Result<CachedContent>> + 'a {
+        self.list_all_on(client, &client.config().base_url)
+    }
+
+    /// Stream every cached content on the endpoint serving `model`
+    pub fn list_all_for_model<'a>(
+        &'a self,
+        client: &'a GeminiClient,
+        model: &str,
+    ) -> impl Stream<Item = Result<CachedContent>> + 'a {
+        self.list_all_on(client, client.config().base_url_for(model))
+    }
+
+    fn list_all_on<'a>(
+        &'a self,
+        client: &'a GeminiClient,
+        base_url: &'a str,
+    ) -> impl Stream<Item = Result<CachedContent>> + 'a {
+        let state = (VecDeque::new(), None::<String>, false);
+        futures::stream::unfold(
+            state,
+            move |(mut pending, mut page_token, mut done)| async move {
+                loop {
+                    if let Some(cached) = pending.pop_front() {
+                        return Some((Ok(cached), (pending, page_token, done)));
+                    }
+                    if done {
+                        return None;
+                    }
+
+                    match self
+                        .list_page(client, base_url, None, page_token.as_deref())
+                        .await
+                    {
+                        Ok(page) => {
+                            pending.extend(page.cached_contents.into_iter().flatten());
+                            match page.next_page_token {
+                                Some(token) if !token.is_empty() => page_token = Some(token),
+                                _ => done = true,
+                            }
+                        }
+                        Err(e) => return Some((Err(e), (pending, page_token, true))),
+                    }
+                }
+            },
+        )
+    }
+
     /// Update cache TTL
     pub async fn update_cache_ttl(
         &self,
@@ -301,49 +739,88 @@ impl CacheManager {
         name: &str,
         ttl_seconds: u64,
     ) -> Result<CachedContent> {
+        self.update_cache(
+            client,
+            name,
+            CacheUpdate::new().ttl(Duration::from_secs(ttl_seconds)),
+        )
+        .await
+    }
+
+    /// Update the expiration of cached content
+    pub async fn update_cache(
+        &self,
+        client: &GeminiClient,
+        name: &str,
+        update: CacheUpdate,
+    ) -> Result<CachedContent> {
+        let (update_request, update_mask) = update.into_patch()?;
+
         let endpoint = format!(
             "{}/{}/{}",
-            client.config().base_url,
+            self.cache_base_url(client, name).await,
             client.config().api_version.as_str(),
             name
         );
 
-        let update_request = serde_json::json!({
-            "ttl": format!("{}s", ttl_seconds)
-        });
-
         let response = client
             .http_client()
             .patch(&endpoint)
-            .query(&[("key", &client.config().api_key)])
-            .query(&[("updateMask", "ttl")])
+            .header(API_KEY_HEADER, client.config().api_key.expose())
+            .query(&[("updateMask", &update_mask)])
             .json(&update_request)
             .send()
-            .await?;
+            .await
+            .map_err(|e| Error::from_reqwest(e, &client.config().http_config))?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(Error::Cache(format!(
-                "Failed to update cache (status {}): {}",
-                status, error_body
-            )));
+            return Err(client.error_from_response(response).await);
         }
 
-        let cached: CachedContent = response.json().await?;
+        let cached: CachedContent = response
+            .json()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &client.config().http_config))?;
 
         // Update registry
-        let mut registry = self.cache_registry.write().await;
-        registry.insert(cached.name.clone(), cached.clone());
+        self.register(&cached).await;
 
         Ok(cached)
     }
 
+    /// Keep a cache alive by extending its TTL every `interval` until the handle is dropped
+    ///
+    /// Each refresh sets the TTL to twice the interval, so a single failed refresh does not
+    /// let the cache expire.
+    pub fn keepalive(
+        self: &Arc<Self>,
+        client: &GeminiClient,
+        cache: &CachedContent,
+        interval: Duration,
+    ) -> CacheKeepalive {
+        let manager = Arc::clone(self);
+        let client = client.clone();
+        let name = cache.name.clone();
+        let ttl_seconds = (interval.as_secs() * 2).max(60);
+
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match manager.update_cache_ttl(&client, &name, ttl_seconds).await {
+                    Ok(_) => debug!("Extended TTL of {} by {}s", name, ttl_seconds),
+                    Err(e) => warn!("Failed to extend TTL of {}: {}", name, e),
+                }
+            }
+        });
+
+        CacheKeepalive { task }
+    }
+
     /// Delete cached content
     pub async fn delete_cache(&self, client: &GeminiClient, name: &str) -> Result<()> {
         let endpoint = format!(
             "{}/{}/{}",
-            client.config().base_url,
+            self.cache_base_url(client, name).await,
             client.config().api_version.as_str(),
             name
         );
@@ -351,33 +828,72 @@ impl CacheManager {
         let response = client
             .http_client()
             .delete(&endpoint)
-            .query(&[("key", &client.config().api_key)])
+            .header(API_KEY_HEADER, client.config().api_key.expose())
             .send()
-            .await?;
+            .await
+            .map_err(|e| Error::from_reqwest(e, &client.config().http_config))?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(Error::Cache(format!(
-                "Failed to delete cache (status {}): {}",
-                status, error_body
-            )));
+            return Err(client.error_from_response(response).await);
         }
 
         // Remove from registry
-        let mut registry = self.cache_registry.write().await;
-        if let Some(cached) = registry.remove(name) {
-            if let Some(display_name) = cached.display_name {
-                let mut index = self.name_index.write().await;
-                index.remove(&display_name);
-            }
-        }
+        self.unregister(name).await;
 
         info!("Deleted cached content: {}", name);
 
         Ok(())
     }
 
+    /// Create caches for many documents concurrently
+    ///
+    /// At most `concurrency` caches are created at once. Each cache's display name is its
+    /// document's key, so it can be found later with [`get_cache_by_name`](Self::get_cache_by_name);
+    /// creations that fail with a retryable error are retried per the client's retry
+    /// configuration.
+    /// Returns each document's cache, or the error from its last attempt.
+    pub async fn warm(
+        &self,
+        client: &GeminiClient,
+        model: Option<&str>,
+        docs: Vec<(String, Vec<Content>)>,
+        config: CacheConfig,
+        concurrency: usize,
+    ) -> HashMap<String, Result<CachedContent>> {
+        let max_attempts = client.config().retry_config.max_attempts.max(1);
+
+        futures::stream::iter(docs)
+            .map(|(doc, contents)| {
+                let config = CacheConfig {
+                    display_name: Some(doc.clone()),
+                    ..config.clone()
+                };
+                async move {
+                    let mut attempt = 1;
+                    loop {
+                        let result = self
+                            .create_cache(client, model, contents.clone(), None, config.clone())
+                            .await;
+                        match result {
+                            Err(e) if attempt < max_attempts && e.is_retryable() => {
+                                let delay = client.calculate_retry_delay(attempt);
+                                warn!(
+                                    "Failed to warm cache for {} (attempt {}), retrying in {:?}: {}",
+                                    doc, attempt, delay, e
+                                );
+                                tokio::time::sleep(delay).await;
+                                attempt += 1;
+                            }
+                            result => return (doc, result),
+                        }
+                    }
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
     /// Clean up expired caches from local registry
     pub async fn cleanup_expired(&self) {
         let now = Utc::now();
@@ -394,16 +910,575 @@ impl CacheManager {
             })
             .collect();
 
+        let mut names = Vec::with_capacity(expired.len());
         for (name, display_name) in expired {
             registry.remove(&name);
             if let Some(display_name) = display_name {
                 index.remove(&display_name);
             }
             debug!("Removed expired cache: {}", name);
+            names.push(name);
+        }
+        drop((registry, index));
+
+        if names.is_empty() {
+            return;
+        }
+        let count = names.len();
+        if let Some(Err(e)) = self
+            .with_store_blocking(move |store| store.remove_all(&names))
+            .await
+        {
+            warn!(
+                "Failed to remove {} expired caches from store: {}",
+                count, e
+            );
+        }
+    }
+}
+
+impl CachedContent {
+    /// Model name without the "models/" prefix
+    pub fn model_name(&self) -> &str {
+        self.model.strip_prefix("models/").unwrap_or(&self.model)
+    }
+
+    /// Point a request at this cache
+    ///
+    /// Sets `cached_content` and drops the system instruction, tools, and tool config, which
+    /// the API rejects alongside cached content (they must be part of the cache instead).
+    pub fn apply_to(&self, request: GenerateContentRequest) -> GenerateContentRequest {
+        self.apply_to_request(request, true)
+    }
+
+    /// Point a request at this cache, warning about dropped fields only if `warn` is set
+    ///
+    /// Automatic prefix caches already hold those fields, so dropping them is expected there.
+    fn apply_to_request(
+        &self,
+        mut request: GenerateContentRequest,
+        warn: bool,
+    ) -> GenerateContentRequest {
+        request.normalize();
+
+        if request.system_instruction.take().is_some() && warn {
+            warn!("Dropping system instruction; it must be set on the cached content");
+        }
+        if request.tools.take().is_some() && warn {
+            warn!("Dropping tools; they must be set on the cached content");
+        }
+        if request.tool_config.take().is_some() && warn {
+            warn!("Dropping tool config; it must be set on the cached content");
+        }
+
+        request.cached_content = Some(self.name.clone());
+        request
+    }
+}
+
+impl From<CachedContent> for String {
+    fn from(cached: CachedContent) -> Self {
+        cached.name
+    }
+}
+
+impl From<&CachedContent> for String {
+    fn from(cached: &CachedContent) -> Self {
+        cached.name.clone()
+    }
+}
+
+/// Fluent builder for creating cached content
+///
+/// ```rust,no_run
+/// # async fn example(client: gemini_rust::GeminiClient) -> gemini_rust::Result<()> {
+/// use gemini_rust::GenerateContentRequest;
+/// use std::time::Duration;
+///
+/// let cached = client
+///     .cache_builder()
+///     .model("gemini-2.0-flash-001")
+///     .system("You answer questions about the attached contract.")
+///     .add_file("application/pdf", "https://generativelanguage.googleapis.com/v1beta/files/abc")
+///     .ttl(Duration::from_secs(3600))
+///     .create()
+///     .await?;
+///
+/// let request = GenerateContentRequest {
+///     cached_content: Some(cached.into()),
+///     ..GenerateContentRequest::new("Who are the parties?")
+/// };
+/// # Ok(())
+/// # }
+/// ```
+pub struct CachedContentBuilder<'a> {
+    client: &'a GeminiClient,
+    model: Option<String>,
+    contents: Vec<Content>,
+    system_instruction: Option<Content>,
+    config: CacheConfig,
+}
+
+impl<'a> CachedContentBuilder<'a> {
+    /// Create a builder that creates the cache through the client's cache manager
+    pub fn new(client: &'a GeminiClient) -> Self {
+        Self {
+            client,
+            model: None,
+            contents: Vec::new(),
+            system_instruction: None,
+            config: CacheConfig::default(),
+        }
+    }
+
+    /// Set the model (defaults to the client's default model)
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Set the system instruction text
+    pub fn system(mut self, text: impl Into<String>) -> Self {
+        self.system_instruction = Some(Content::system(text));
+        self
+    }
+
+    /// Add content to the cache
+    pub fn add_content(mut self, content: impl Into<Content>) -> Self {
+        self.contents.push(content.into());
+        self
+    }
+
+    /// Add an uploaded file to the cache as user content
+    pub fn add_file(self, mime_type: impl Into<String>, file_uri: impl Into<String>) -> Self {
+        self.add_content(Part::file(mime_type, file_uri))
+    }
+
+    /// Set the time to live
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.config.ttl = Some(ttl.as_secs());
+        self
+    }
+
+    /// Set an absolute expiration time instead of a TTL
+    pub fn expire_time(mut self, expire_time: DateTime<Utc>) -> Self {
+        self.config.expire_time = Some(expire_time);
+        self
+    }
+
+    /// Set the display name
+    pub fn display_name(mut self, name: impl Into<String>) -> Self {
+        self.config.display_name = Some(name.into());
+        self
+    }
+
+    /// Set the tools to cache
+    pub fn tools(mut self, tools: Vec<Tool>) -> Self {
+        self.config.tools = Some(tools);
+        self
+    }
+
+    /// Set the tool configuration to cache
+    pub fn tool_config(mut self, tool_config: ToolConfig) -> Self {
+        self.config.tool_config = Some(tool_config);
+        self
+    }
+
+    /// Create the cached content
+    pub async fn create(self) -> Result<CachedContent> {
+        self.client
+            .cache_manager()
+            .create_cache(
+                self.client,
+                self.model.as_deref(),
+                self.contents,
+                self.system_instruction,
+                self.config,
+            )
+            .await
+    }
+}
+
+impl GeminiClient {
+    /// Start building cached content
+    pub fn cache_builder(&self) -> CachedContentBuilder<'_> {
+        CachedContentBuilder::new(self)
+    }
+
+    /// Generate content using cached content, with the model the cache was created for
+    pub async fn generate_with_cache(
+        &self,
+        cached: &CachedContent,
+        request: GenerateContentRequest,
+    ) -> Result<GenerateContentResponse> {
+        self.generate_content(Some(cached.model_name()), cached.apply_to(request))
+            .await
+    }
+}
+
+/// Token usage attributed to one cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheUsage {
+    /// Number of responses generated with the cache
+    pub requests: u64,
+
+    /// Total prompt tokens, including cached ones
+    pub prompt_tokens: u64,
+
+    /// Prompt tokens served from the cache
+    pub cached_tokens: u64,
+}
+
+/// Input token prices (USD per million tokens) used to estimate savings
+#[derive(Debug, Clone, Copy)]
+pub struct CachePricing {
+    /// Price of regular input tokens
+    pub input_per_million: f64,
+
+    /// Price of cached input tokens
+    pub cached_per_million: f64,
+}
+
+impl Default for CachePricing {
+    /// Gemini 2.5 Flash text pricing
+    fn default() -> Self {
+        Self {
+            input_per_million: 0.30,
+            cached_per_million: 0.075,
         }
     }
 }
 
+/// Summary of tokens served from caches
+///
+/// Savings cover input tokens only; cache storage charges are not included.
+#[derive(Debug, Clone, Default)]
+pub struct SavingsReport {
+    /// Responses generated with a cache
+    pub requests: u64,
+
+    /// Total prompt tokens of those responses
+    pub prompt_tokens: u64,
+
+    /// Prompt tokens served from caches
+    pub cached_tokens: u64,
+
+    /// Estimated input cost saved, in USD
+    pub estimated_savings_usd: f64,
+
+    /// Usage per cache resource name
+    pub per_cache: HashMap<String, CacheUsage>,
+}
+
+/// Minimum remaining lifetime for an existing cache to be reused
+const MIN_REMAINING_SECS: i64 = 60;
+
+/// Contents and settings for a cache created by [`CacheManager::get_or_create`]
+#[derive(Debug, Clone, Default)]
+pub struct CacheContents {
+    /// Contents to cache
+    pub contents: Vec<Content>,
+    /// System instruction to cache
+    pub system_instruction: Option<Content>,
+    /// TTL, tools, and tool config (the display name is set from the key)
+    pub config: CacheConfig,
+}
+
+impl CacheContents {
+    /// Stable hash identifying these contents for the given model
+    pub fn hash(&self, model: &str) -> String {
+        hash_key(
+            model,
+            &self.contents,
+            self.system_instruction.as_ref(),
+            self.config.tools.as_ref(),
+            self.config.tool_config.as_ref(),
+        )
+    }
+}
+
+impl CachedContent {
+    /// Whether the cache has enough lifetime left to be reused
+    fn is_live(&self) -> bool {
+        self.expire_time.is_some_and(|expire| {
+            expire > Utc::now() + chrono::Duration::seconds(MIN_REMAINING_SECS)
+        })
+    }
+}
+
+/// Locks for keys with a `get_or_create` call in progress
+type InFlightLocks = std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>;
+
+/// A caller's share of a key's in-flight lock
+///
+/// The lock is removed from the map when its last caller is done, however the call ends:
+/// with a cache, an error or cancellation.
+struct InFlightEntry<'a> {
+    locks: &'a InFlightLocks,
+    key: String,
+    lock: Arc<Mutex<()>>,
+}
+
+impl<'a> InFlightEntry<'a> {
+    fn acquire(locks: &'a InFlightLocks, key: &str) -> Self {
+        let lock = locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+        Self {
+            locks,
+            key: key.to_string(),
+            lock,
+        }
+    }
+}
+
+impl Drop for InFlightEntry<'_> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.lock().unwrap_or_else(|e| e.into_inner());
+        // One reference is held by the map and one by this entry
+        if Arc::strong_count(&self.lock) == 2 {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+impl CacheManager {
+    /// Get a live cache for a key, creating it from `build` if none exists
+    ///
+    /// Without a key, `build` is called up front and its contents are identified by a
+    /// stable content hash. The key (or hash) is stored as the cache's display name, so
+    /// concurrent callers sharing this manager wait for a single creation, and other
+    /// processes find the cache through the API instead of paying for a duplicate.
+    pub async fn get_or_create<F>(
+        &self,
+        client: &GeminiClient,
+        model: Option<&str>,
+        key: Option<&str>,
+        build: F,
+    ) -> Result<CachedContent>
+    where
+        F: FnOnce() -> CacheContents,
+    {
+        let model_name = client.config().get_model_name(model);
+        let (key, mut build, mut built) = match key {
+            Some(key) => (key.to_string(), Some(build), None),
+            None => {
+                let contents = build();
+                let key = format!("sha256-{}", &contents.hash(&model_name)[..32]);
+                (key, None, Some(contents))
+            }
+        };
+
+        let entry = InFlightEntry::acquire(&self.in_flight, &key);
+        let _guard = entry.lock.lock().await;
+
+        if let Some(cached) = self.find_live(client, &model_name, &key).await? {
+            debug!("Reusing cache {} for key {}", cached.name, key);
+            return Ok(cached);
+        }
+
+        let contents = match built.take() {
+            Some(contents) => contents,
+            None => build.take().map(|build| build()).unwrap_or_default(),
+        };
+        let config = CacheConfig {
+            display_name: Some(key.clone()),
+            ..contents.config
+        };
+        self.create_cache(
+            client,
+            Some(&model_name),
+            contents.contents,
+            contents.system_instruction,
+            config,
+        )
+        .await
+    }
+
+    /// Find a live cache by display name, locally first and then on the model's endpoint
+    async fn find_live(
+        &self,
+        client: &GeminiClient,
+        model: &str,
+        display_name: &str,
+    ) -> Result<Option<CachedContent>> {
+        let local = {
+            let index = self.name_index.read().await;
+            let registry = self.cache_registry.read().await;
+            index
+                .get(display_name)
+                .and_then(|name| registry.get(name))
+                .cloned()
+        };
+        if let Some(cached) = local.filter(CachedContent::is_live) {
+            return Ok(Some(cached));
+        }
+
+        let caches = self.list_all_for_model(client, model);
+        futures::pin_mut!(caches);
+        while let Some(cached) = caches.try_next().await? {
+            if cached.display_name.as_deref() == Some(display_name) && cached.is_live() {
+                return Ok(Some(cached));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Look up a live automatic cache for a prefix hash
+    async fn cached_prefix(&self, hash: &str) -> Option<CachedContent> {
+        let index = self.prefix_index.read().await;
+        index.get(hash).filter(|cached| cached.is_live()).cloned()
+    }
+
+    /// Record an automatic cache, returning caches evicted beyond `max_entries`
+    async fn insert_prefix(
+        &self,
+        hash: String,
+        cached: CachedContent,
+        max_entries: Option<usize>,
+    ) -> Vec<CachedContent> {
+        let mut index = self.prefix_index.write().await;
+        index.insert(hash, cached);
+
+        let Some(max_entries) = max_entries else {
+            return Vec::new();
+        };
+        let mut evicted = Vec::new();
+        while index.len() > max_entries {
+            let oldest = index
+                .iter()
+                .min_by_key(|(_, cached)| cached.create_time)
+                .map(|(hash, _)| hash.clone());
+            match oldest.and_then(|hash| index.remove(&hash)) {
+                Some(cached) => evicted.push(cached),
+                None => break,
+            }
+        }
+        evicted
+    }
+}
+
+impl GeminiClient {
+    /// Rewrite a request to use an automatic prefix cache if the policy applies
+    ///
+    /// Returns the model to use (the cache's model when a cache is used). Cache failures
+    /// fall back to sending the request uncached.
+    pub(crate) async fn apply_auto_cache(
+        &self,
+        policy: &AutoCachePolicy,
+        model_name: String,
+        request: GenerateContentRequest,
+    ) -> (String, GenerateContentRequest) {
+        if request.cached_content.is_some() || request.contents.len() < 2 {
+            return (model_name, request);
+        }
+
+        let prefix_len = request.contents.len() - 1;
+        let prefix = &request.contents[..prefix_len];
+        let estimated = estimate_tokens(prefix)
+            + request
+                .system_instruction
+                .as_ref()
+                .map_or(0, |c| estimate_tokens(std::slice::from_ref(c)));
+        if estimated < policy.min_tokens {
+            return (model_name, request);
+        }
+
+        let hash = hash_key(
+            &model_name,
+            prefix,
+            request.system_instruction.as_ref(),
+            request.tools.as_ref(),
+            request.tool_config.as_ref(),
+        );
+        let manager = self.cache_manager();
+
+        let cached = match manager.cached_prefix(&hash).await {
+            Some(cached) => cached,
+            None => {
+                let key = format!("auto-{}", &hash[..32]);
+                let created = manager
+                    .get_or_create(self, Some(&model_name), Some(&key), || CacheContents {
+                        contents: prefix.to_vec(),
+                        system_instruction: request.system_instruction.clone(),
+                        config: CacheConfig {
+                            tools: request.tools.clone(),
+                            tool_config: request.tool_config.clone(),
+                            ..policy.config.clone()
+                        },
+                    })
+                    .await;
+                let cached = match created {
+                    Ok(cached) => cached,
+                    Err(e) => {
+                        warn!("Automatic prefix caching failed, sending uncached: {}", e);
+                        return (model_name, request);
+                    }
+                };
+
+                let evicted = manager
+                    .insert_prefix(hash, cached.clone(), policy.config.max_entries)
+                    .await;
+                for old in evicted {
+                    if let Err(e) = manager.delete_cache(self, &old.name).await {
+                        warn!("Failed to evict cache {}: {}", old.name, e);
+                    }
+                }
+                cached
+            }
+        };
+
+        debug!("Using automatic prefix cache {}", cached.name);
+        let mut request = request;
+        request.contents.drain(..prefix_len);
+        (
+            cached.model_name().to_string(),
+            cached.apply_to_request(request, false),
+        )
+    }
+}
+
+/// Stable SHA-256 hex digest of everything that goes into a cache
+fn hash_key(
+    model: &str,
+    contents: &[Content],
+    system_instruction: Option<&Content>,
+    tools: Option<&Vec<Tool>>,
+    tool_config: Option<&ToolConfig>,
+) -> String {
+    use sha2::{Digest, Sha256};
+
+    let key = serde_json::json!({
+        "model": model,
+        "contents": contents,
+        "systemInstruction": system_instruction,
+        "tools": tools,
+        "toolConfig": tool_config,
+    });
+    let digest = Sha256::digest(key.to_string().as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Handle to a background TTL refresh task; the task stops when the handle is dropped
+#[derive(Debug)]
+pub struct CacheKeepalive {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl CacheKeepalive {
+    /// Stop refreshing the TTL
+    pub fn stop(self) {}
+}
+
+impl Drop for CacheKeepalive {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
 /// Response from list caches API
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -429,3 +1504,40 @@ pub fn calculate_optimal_ttl(token_count: i32) -> u64 {
         _ => DAY,                     // 24 hours for very large content
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn in_flight_lock_is_removed_by_its_last_caller() {
+        let locks = InFlightLocks::default();
+        let first = InFlightEntry::acquire(&locks, "doc");
+        let second = InFlightEntry::acquire(&locks, "doc");
+        assert!(Arc::ptr_eq(&first.lock, &second.lock));
+
+        let guard = first.lock.lock().await;
+        assert!(second.lock.try_lock().is_err());
+        drop(guard);
+        drop(first);
+        assert!(locks.lock().unwrap().contains_key("doc"));
+
+        drop(second);
+        assert!(locks.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn in_flight_lock_is_removed_when_the_call_is_cancelled() {
+        let locks = InFlightLocks::default();
+        let holder = InFlightEntry::acquire(&locks, "doc");
+        let _guard = holder.lock.lock().await;
+
+        let waiting = async {
+            let entry = InFlightEntry::acquire(&locks, "doc");
+            let _guard = entry.lock.lock().await;
+        };
+        let timed_out = tokio::time::timeout(std::time::Duration::from_millis(10), waiting).await;
+        assert!(timed_out.is_err());
+        assert_eq!(Arc::strong_count(&holder.lock), 2);
+    }
+}
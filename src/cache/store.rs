@@ -0,0 +1,205 @@
+//! Persistent backing stores for the cache registry
+
+use super::CachedContent;
+use crate::error::{Error, Result};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// Storage for cache registry entries that outlives the process
+///
+/// Entries are keyed by resource name; display-name lookups are rebuilt from the
+/// stored entries when a [`CacheManager`](super::CacheManager) loads them. Methods block,
+/// and the manager calls them from a blocking thread.
+pub trait CacheStore: Send + Sync {
+    /// Load all stored entries
+    fn load(&self) -> Result<Vec<CachedContent>>;
+
+    /// Insert or replace an entry
+    fn put(&self, cached: &CachedContent) -> Result<()>;
+
+    /// Remove an entry by resource name
+    fn remove(&self, name: &str) -> Result<()>;
+
+    /// Insert or replace several entries
+    fn put_all(&self, entries: &[CachedContent]) -> Result<()> {
+        entries.iter().try_for_each(|cached| self.put(cached))
+    }
+
+    /// Remove several entries by resource name
+    fn remove_all(&self, names: &[String]) -> Result<()> {
+        names.iter().try_for_each(|name| self.remove(name))
+    }
+}
+
+/// Cache store backed by a JSON file
+///
+/// Each write takes an exclusive lock on a `.lock` file next to it, re-reads the file and
+/// replaces it through a uniquely named temporary file, so several processes sharing the
+/// file see each other's entries.
+#[derive(Debug)]
+pub struct JsonFileStore {
+    path: PathBuf,
+}
+
+impl JsonFileStore {
+    /// Use the JSON file at `path`, which is created on first write
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn io_error(&self, action: &str, e: std::io::Error) -> Error {
+        Error::Cache(format!(
+            "Failed to {} {}: {}",
+            action,
+            self.path.display(),
+            e
+        ))
+    }
+
+    /// Lock the sidecar lock file, shared for reads and exclusive for writes
+    ///
+    /// The data file itself is replaced on every write, so it cannot carry the lock.
+    fn lock(&self, exclusive: bool) -> Result<File> {
+        let mut lock_path = self.path.clone().into_os_string();
+        lock_path.push(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| self.io_error("lock", e))?;
+        let locked = if exclusive {
+            file.lock()
+        } else {
+            file.lock_shared()
+        };
+        locked.map_err(|e| self.io_error("lock", e))?;
+        Ok(file)
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, CachedContent>> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) if bytes.is_empty() => Ok(BTreeMap::new()),
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(self.io_error("read", e)),
+        }
+    }
+
+    fn write(&self, entries: &BTreeMap<String, CachedContent>) -> Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        let tmp = PathBuf::from(tmp);
+        let written = std::fs::write(&tmp, serde_json::to_vec_pretty(entries)?)
+            .and_then(|_| std::fs::rename(&tmp, &self.path));
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(self.io_error("write", e));
+        }
+        Ok(())
+    }
+
+    fn modify(&self, f: impl FnOnce(&mut BTreeMap<String, CachedContent>)) -> Result<()> {
+        let _lock = self.lock(true)?;
+        let mut entries = self.read()?;
+        f(&mut entries);
+        self.write(&entries)
+    }
+}
+
+impl CacheStore for JsonFileStore {
+    fn load(&self) -> Result<Vec<CachedContent>> {
+        let _lock = self.lock(false)?;
+        Ok(self.read()?.into_values().collect())
+    }
+
+    fn put(&self, cached: &CachedContent) -> Result<()> {
+        self.put_all(std::slice::from_ref(cached))
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        self.remove_all(&[name.to_string()])
+    }
+
+    fn put_all(&self, entries: &[CachedContent]) -> Result<()> {
+        self.modify(|stored| {
+            for cached in entries {
+                stored.insert(cached.name.clone(), cached.clone());
+            }
+        })
+    }
+
+    fn remove_all(&self, names: &[String]) -> Result<()> {
+        self.modify(|stored| {
+            for name in names {
+                stored.remove(name);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn entry(name: String) -> CachedContent {
+        CachedContent {
+            name,
+            display_name: None,
+            model: "models/gemini-2.0-flash".to_string(),
+            create_time: Utc::now(),
+            update_time: Utc::now(),
+            expire_time: None,
+        }
+    }
+
+    #[test]
+    fn concurrent_writers_keep_every_entry() {
+        let dir = std::env::temp_dir().join(format!("gemini-store-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("caches.json");
+
+        let writers: Vec<_> = (0..8)
+            .map(|worker| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    // A store per thread, as separate processes would have
+                    let store = JsonFileStore::new(path);
+                    for i in 0..5 {
+                        store
+                            .put(&entry(format!("cachedContents/{}-{}", worker, i)))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let store = JsonFileStore::new(&path);
+        assert_eq!(store.load().unwrap().len(), 40);
+        store
+            .remove_all(&[
+                "cachedContents/0-0".to_string(),
+                "cachedContents/1-1".to_string(),
+            ])
+            .unwrap();
+        assert_eq!(store.load().unwrap().len(), 38);
+
+        let leftovers: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,266 @@
+//! Multi-turn chat sessions
+//!
+//! A [`ChatSession`] serializes as a [`ChatState`], which
+//! [`ChatSession::restore`] turns back into a session, so conversations can be stored between
+//! requests.
+
+use crate::{
+    budget::TokenBudget,
+    client::GeminiClient,
+    error::Result,
+    memory::{FullHistory, Memory},
+    models::{
+        estimate_tokens, Content, GenerateContentRequest, GenerateContentResponse, Tool,
+        ToolConfig, UsageMetadata,
+    },
+};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// A conversation that remembers its turns between requests
+///
+/// Which turns are sent is up to the session's [`Memory`] strategy, [`FullHistory`] by
+/// default. A message and its answer are only remembered once the request succeeds, so a
+/// failed [`send`](Self::send) can simply be retried.
+pub struct ChatSession {
+    client: GeminiClient,
+    model: Option<String>,
+    system_prompt: Option<String>,
+    cached_content: Option<String>,
+    tools: Option<Vec<Tool>>,
+    tool_config: Option<ToolConfig>,
+    usage: ChatUsage,
+    token_budget: Option<TokenBudget>,
+    memory: Box<dyn Memory>,
+}
+
+/// Token usage accumulated over a chat session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatUsage {
+    /// Number of successful requests
+    pub requests: u64,
+
+    /// Total prompt tokens, including history and cached tokens
+    pub prompt_tokens: u64,
+
+    /// Total tokens in the answers
+    pub output_tokens: u64,
+
+    /// Total tokens, including thinking
+    pub total_tokens: u64,
+}
+
+impl ChatUsage {
+    pub(crate) fn record(&mut self, usage: Option<&UsageMetadata>) {
+        self.requests += 1;
+        if let Some(usage) = usage {
+            self.prompt_tokens += usage.prompt_token_count.max(0) as u64;
+            self.output_tokens += usage.candidates_token_count.max(0) as u64;
+            self.total_tokens += usage.total_token_count.max(0) as u64;
+        }
+    }
+}
+
+/// Everything needed to resume a [`ChatSession`]
+///
+/// Memory strategies are not part of the state: a restored session starts with
+/// [`FullHistory`] holding the saved turns, and [`ChatSession::with_memory`] moves them into
+/// another strategy.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatState {
+    /// Model the session talks to, if not the client's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// System prompt sent with every request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+
+    /// Remembered turns, oldest first
+    #[serde(default)]
+    pub history: Vec<Content>,
+
+    /// Name of the cached content requests refer to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_content: Option<String>,
+
+    /// Tools available to the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+
+    /// Tool usage configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_config: Option<ToolConfig>,
+
+    /// Token usage so far
+    #[serde(default)]
+    pub usage: ChatUsage,
+
+    /// Token budget and the usage counted against it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<TokenBudget>,
+}
+
+impl ChatSession {
+    /// Start a session on a client, using its default model
+    pub fn new(client: GeminiClient) -> Self {
+        Self {
+            client,
+            model: None,
+            system_prompt: None,
+            cached_content: None,
+            tools: None,
+            tool_config: None,
+            usage: ChatUsage::default(),
+            token_budget: None,
+            memory: Box::new(FullHistory::new()),
+        }
+    }
+
+    /// Resume a session from a saved state
+    pub fn restore(client: GeminiClient, state: ChatState) -> Self {
+        let mut memory = FullHistory::new();
+        for content in state.history {
+            memory.push(content);
+        }
+        Self {
+            client,
+            model: state.model,
+            system_prompt: state.system_prompt,
+            cached_content: state.cached_content,
+            tools: state.tools,
+            tool_config: state.tool_config,
+            usage: state.usage,
+            token_budget: state.token_budget,
+            memory: Box::new(memory),
+        }
+    }
+
+    /// Snapshot the session for storage
+    pub fn state(&self) -> ChatState {
+        ChatState {
+            model: self.model.clone(),
+            system_prompt: self.system_prompt.clone(),
+            history: self.memory.history(),
+            cached_content: self.cached_content.clone(),
+            tools: self.tools.clone(),
+            tool_config: self.tool_config.clone(),
+            usage: self.usage,
+            token_budget: self.token_budget.clone(),
+        }
+    }
+
+    /// Use a model other than the client's default
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Send a system prompt with every request
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Refer to cached content by name, e.g. `cachedContents/abc`
+    pub fn with_cached_content(mut self, name: impl Into<String>) -> Self {
+        self.cached_content = Some(name.into());
+        self
+    }
+
+    /// Make tools available to the model
+    pub fn with_tools(mut self, tools: Vec<Tool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Configure how the model uses tools
+    pub fn with_tool_config(mut self, tool_config: ToolConfig) -> Self {
+        self.tool_config = Some(tool_config);
+        self
+    }
+
+    /// Count usage against a token budget, checking each request before it is sent
+    pub fn with_token_budget(mut self, budget: TokenBudget) -> Self {
+        self.token_budget = Some(budget);
+        self
+    }
+
+    /// Manage history with a different strategy, moving the current turns into it
+    pub fn with_memory(mut self, mut memory: impl Memory + 'static) -> Self {
+        for content in self.memory.history() {
+            memory.push(content);
+        }
+        self.memory = Box::new(memory);
+        self
+    }
+
+    /// Send a message and remember it along with the answer
+    pub async fn send(&mut self, message: impl Into<Content>) -> Result<GenerateContentResponse> {
+        let message = message.into();
+        self.memory
+            .prepare(&self.client, self.model.as_deref())
+            .await?;
+
+        let mut contents = self.memory.history();
+        contents.push(message.clone());
+        let system_instruction = self.system_prompt.clone().map(Content::system);
+        if let Some(budget) = &self.token_budget {
+            let fixed = estimate_tokens(system_instruction.as_slice()) as u64;
+            contents = budget.fit(contents, fixed)?;
+        }
+        let mut request = GenerateContentRequest::new(contents);
+        request.system_instruction = system_instruction;
+        request.cached_content = self.cached_content.clone();
+        request.tools = self.tools.clone();
+        request.tool_config = self.tool_config.clone();
+
+        let response = self
+            .client
+            .generate_content(self.model.as_deref(), request)
+            .await?;
+        self.usage.record(response.usage_metadata.as_ref());
+        if let (Some(budget), Some(usage)) = (&mut self.token_budget, &response.usage_metadata) {
+            budget.record(usage);
+        }
+        self.memory.push(message);
+        if let Some(candidate) = response.candidates.first() {
+            self.memory.push(candidate.content.clone());
+        }
+        Ok(response)
+    }
+
+    /// The turns the next request will include
+    pub fn history(&self) -> Vec<Content> {
+        self.memory.history()
+    }
+
+    /// Token usage accumulated over the session
+    pub fn usage(&self) -> ChatUsage {
+        self.usage
+    }
+
+    /// The session's token budget, if it has one
+    pub fn token_budget(&self) -> Option<&TokenBudget> {
+        self.token_budget.as_ref()
+    }
+
+    /// The session's memory, e.g. to pin a turn
+    pub fn memory_mut(&mut self) -> &mut dyn Memory {
+        self.memory.as_mut()
+    }
+}
+
+impl Serialize for ChatSession {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.state().serialize(serializer)
+    }
+}
+
+impl GeminiClient {
+    /// Start a chat session on this client
+    pub fn chat(&self) -> ChatSession {
+        ChatSession::new(self.clone())
+    }
+}
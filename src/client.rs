@@ -1,32 +1,61 @@
 //! Main Gemini API client implementation
 
 use crate::{
-    config::{ApiVersion, GeminiConfig},
-    error::{Error, Result},
+    config::{ApiVersion, GeminiConfig, HttpConfig, LoggingConfig, SecretString, TlsVersion},
+    error::{ApiErrorCode, ApiErrorResponse, Error, QuotaViolation, Result},
+    metrics::MetricsObserver,
     models::*,
+    retry::{DefaultRetryPolicy, RetryPolicy},
 };
 
 #[cfg(feature = "caching")]
-use crate::cache::CacheManager;
-use reqwest::{Client as HttpClient, RequestBuilder, StatusCode};
+use crate::cache::{AutoCachePolicy, CacheManager};
+#[cfg(feature = "testing")]
+use crate::testing::FaultInjector;
+use reqwest::{Client as HttpClient, RequestBuilder, Response, StatusCode};
 use serde::de::DeserializeOwned;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{debug, instrument, warn};
 
+/// Header carrying the API key
+///
+/// A `key` query parameter would end up in the URL that transport errors and logs print.
+pub(crate) const API_KEY_HEADER: &str = "x-goog-api-key";
+
+/// Model used for embeddings when none is given
+const DEFAULT_EMBEDDING_MODEL: &str = "gemini-embedding-001";
+
+/// Maximum number of contents per batch embedding request
+const MAX_EMBEDDING_BATCH: usize = 100;
+
 /// Main Gemini API client
 #[derive(Clone)]
 pub struct GeminiClient {
     config: Arc<GeminiConfig>,
     http_client: HttpClient,
+    retry_policy: Arc<dyn RetryPolicy>,
+    metrics: Option<Arc<dyn MetricsObserver>>,
     #[cfg(feature = "caching")]
     cache_manager: Arc<CacheManager>,
+    #[cfg(feature = "caching")]
+    auto_cache: Option<Arc<AutoCachePolicy>>,
+    #[cfg(feature = "testing")]
+    fault_injector: Option<Arc<FaultInjector>>,
 }
 
 impl GeminiClient {
     /// Create a new client with the given configuration
+    ///
+    /// The configuration is checked with [`GeminiConfig::validate`] first.
     pub fn new(config: GeminiConfig) -> Result<Self> {
+        config.validate()?;
+        Self::new_unchecked(config)
+    }
+
+    /// Create a new client without validating the configuration
+    pub fn new_unchecked(config: GeminiConfig) -> Result<Self> {
         let http_client = Self::build_http_client(&config)?;
         #[cfg(feature = "caching")]
         let cache_manager = Arc::new(CacheManager::new());
@@ -34,11 +63,50 @@ impl GeminiClient {
         Ok(Self {
             config: Arc::new(config),
             http_client,
+            retry_policy: Arc::new(DefaultRetryPolicy),
+            metrics: None,
             #[cfg(feature = "caching")]
             cache_manager,
+            #[cfg(feature = "caching")]
+            auto_cache: None,
+            #[cfg(feature = "testing")]
+            fault_injector: None,
         })
     }
 
+    /// Decide which failures are retried with a custom policy
+    pub fn with_retry_policy(mut self, policy: impl RetryPolicy + 'static) -> Self {
+        self.retry_policy = Arc::new(policy);
+        self
+    }
+
+    /// Report request events, e.g. to [`PrometheusMetrics`](crate::metrics::PrometheusMetrics)
+    pub fn with_metrics_observer(mut self, observer: impl MetricsObserver + 'static) -> Self {
+        self.metrics = Some(Arc::new(observer));
+        self
+    }
+
+    /// Automatically cache large request prefixes according to the policy
+    #[cfg(feature = "caching")]
+    pub fn with_auto_cache(mut self, policy: AutoCachePolicy) -> Self {
+        self.auto_cache = Some(Arc::new(policy));
+        self
+    }
+
+    /// Use a custom cache manager, e.g. one backed by a persistent store
+    #[cfg(feature = "caching")]
+    pub fn with_cache_manager(mut self, manager: CacheManager) -> Self {
+        self.cache_manager = Arc::new(manager);
+        self
+    }
+
+    /// Install a fault injector for resilience testing
+    #[cfg(feature = "testing")]
+    pub fn with_fault_injector(mut self, injector: FaultInjector) -> Self {
+        self.fault_injector = Some(Arc::new(injector));
+        self
+    }
+
     /// Create a new client from environment variables
     pub fn from_env() -> Result<Self> {
         let config = GeminiConfig::from_env()?;
@@ -58,26 +126,219 @@ impl GeminiClient {
         request: GenerateContentRequest,
     ) -> Result<GenerateContentResponse> {
         let model_name = self.config.get_model_name(model);
-        let endpoint = format!(
-            "{}/{}/models/{}:generateContent",
-            self.config.base_url,
-            self.config.api_version.as_str(),
-            model_name
-        );
+        let started = Instant::now();
+        if let Some(metrics) = &self.metrics {
+            metrics.request_started("generate_content", &model_name);
+        }
+
+        #[cfg(feature = "otel")]
+        let result = {
+            use tracing::Instrument;
+
+            let span = crate::otel::operation_span("generate_content", &model_name);
+            let result = self
+                .generate_content_inner(model, request)
+                .instrument(span.clone())
+                .await;
+            crate::otel::record_result(&span, &result, started);
+            result
+        };
+        #[cfg(not(feature = "otel"))]
+        let result = self.generate_content_inner(model, request).await;
+
+        if let Some(metrics) = &self.metrics {
+            let latency = started.elapsed();
+            match &result {
+                Ok(response) => metrics.request_succeeded(
+                    "generate_content",
+                    &model_name,
+                    latency,
+                    response.usage_metadata.as_ref(),
+                ),
+                Err(error) => {
+                    metrics.request_failed("generate_content", &model_name, latency, error)
+                }
+            }
+        }
+        result
+    }
+
+    async fn generate_content_inner(
+        &self,
+        model: Option<&str>,
+        mut request: GenerateContentRequest,
+    ) -> Result<GenerateContentResponse> {
+        request.normalize();
+        let model_name = self.config.get_model_name(model);
+        self.config.apply_request_defaults(&mut request);
+        self.config.apply_profiles(&model_name, &mut request)?;
+        request.validate()?;
+        request.validate_tools(&model_name)?;
+        request.validate_modalities(&model_name)?;
+        #[cfg(feature = "thinking")]
+        request.validate_thinking(&model_name)?;
+
+        let offloaded = if self.config.inline_offload.enabled {
+            self.offload_inline_data(&model_name, &mut request).await?
+        } else {
+            Vec::new()
+        };
+        let offload_model = model_name.clone();
+
+        #[cfg(feature = "caching")]
+        let (model_name, request) = match &self.auto_cache {
+            Some(policy) => self.apply_auto_cache(policy, model_name, request).await,
+            None => (model_name, request),
+        };
+
+        #[cfg(feature = "otel")]
+        crate::otel::record_request(&tracing::Span::current(), &model_name, &request);
+
+        let endpoint = self.model_url(&model_name, "generateContent");
 
         debug!("Generating content with model: {}", model_name);
 
-        self.execute_with_retry(|client| {
-            client
-                .http_client
-                .post(&endpoint)
-                .query(&[("key", &client.config.api_key)])
-                .json(&request)
-        })
-        .await
+        let response = self.post_generate_content(&endpoint, &request).await;
+        if !offloaded.is_empty() {
+            self.cleanup_offloaded(&offload_model, offloaded).await;
+        }
+        let mut response = response?;
+        response.index_candidates();
+
+        #[cfg(feature = "caching")]
+        if let (Some(cache), Some(usage)) = (&request.cached_content, &response.usage_metadata) {
+            self.cache_manager.record_usage(cache, usage);
+        }
+
+        #[cfg(feature = "thinking")]
+        if response.thinking_exhausted_output() {
+            if self.config.model_config.strict_thinking {
+                return Err(Error::ThinkingBudgetExceeded);
+            }
+            warn!(
+                "Thinking used the entire output token limit of {}; the answer is empty",
+                model_name
+            );
+        }
+
+        if self.config.model_config.strict_responses {
+            return response.require_content();
+        }
+        Ok(response)
+    }
+
+    /// Send a generate request, retrying errors and responses the retry policy rejects
+    ///
+    /// Both kinds of retry count against the same `max_attempts`.
+    pub(crate) async fn post_generate_content(
+        &self,
+        endpoint: &str,
+        request: &GenerateContentRequest,
+    ) -> Result<GenerateContentResponse> {
+        let max_attempts = self.config.retry_config.max_attempts;
+        let mut attempts = 0;
+        loop {
+            let response = self
+                .send_counting_attempts(
+                    |client| {
+                        client
+                            .http_client
+                            .post(endpoint)
+                            .header(API_KEY_HEADER, client.config.api_key.expose())
+                            .json(request)
+                    },
+                    &mut attempts,
+                    max_attempts,
+                )
+                .await?;
+            let response: GenerateContentResponse = self.read_json(response).await?;
+
+            if attempts >= max_attempts || !self.retry_policy.should_retry_response(&response) {
+                return Ok(response);
+            }
+            let delay = self.calculate_retry_delay(attempts);
+            warn!(
+                "Retry policy rejected response (attempt {}), retrying in {:?}",
+                attempts, delay
+            );
+            sleep(delay).await;
+        }
+    }
+
+    /// Generate content for a prompt with the default model
+    ///
+    /// Accepts anything convertible into contents, e.g. `client.generate("Hello")`.
+    pub async fn generate(&self, prompt: impl IntoContents) -> Result<GenerateContentResponse> {
+        self.generate_content(None, GenerateContentRequest::new(prompt))
+            .await
+    }
+
+    /// Generate content, executing function calls with the registry until the model answers
+    ///
+    /// The registry's declarations are added to the request's tools. Each turn, all function
+    /// calls in the response are executed concurrently and their results sent back; the first
+    /// response without function calls is returned. Fails after `max_turns` model calls.
+    #[cfg(feature = "functions")]
+    #[instrument(skip(self, request, registry))]
+    pub async fn generate_with_tools(
+        &self,
+        model: Option<&str>,
+        mut request: GenerateContentRequest,
+        registry: &crate::functions::ToolRegistry,
+        max_turns: usize,
+    ) -> Result<GenerateContentResponse> {
+        request
+            .tools
+            .get_or_insert_with(Vec::new)
+            .push(registry.tool());
+
+        for turn in 1..=max_turns {
+            let response = self.generate_content(model, request.clone()).await?;
+
+            let Some(candidate) = response.candidates.first() else {
+                return Ok(response);
+            };
+            let calls: Vec<crate::functions::FunctionCall> = candidate
+                .content
+                .parts
+                .iter()
+                .filter_map(|part| match part {
+                    Part::FunctionCall { function_call, .. } => Some(function_call.clone()),
+                    _ => None,
+                })
+                .collect();
+
+            if calls.is_empty() {
+                return Ok(response);
+            }
+
+            debug!(
+                "Tool turn {}: executing {} function calls",
+                turn,
+                calls.len()
+            );
+
+            request.contents.push(candidate.content.clone());
+            let results = registry.execute_all(&calls).await?;
+            request.contents.push(Content {
+                role: Role::User,
+                parts: results
+                    .into_iter()
+                    .map(|function_response| Part::FunctionResponse { function_response })
+                    .collect(),
+            });
+        }
+
+        Err(Error::FunctionCall(format!(
+            "Model did not produce a final answer within {} turns",
+            max_turns
+        )))
     }
 
     /// Stream content generation
+    ///
+    /// Failures after the first chunk end the stream with `Error::StreamInterrupted`, which
+    /// carries the text received so far.
     #[cfg(feature = "streaming")]
     #[instrument(skip(self, request))]
     pub async fn stream_generate_content(
@@ -85,38 +346,168 @@ impl GeminiClient {
         model: Option<&str>,
         request: GenerateContentRequest,
     ) -> Result<impl futures::Stream<Item = Result<GenerateContentResponse>>> {
+        use futures::future::Either;
+
         let model_name = self.config.get_model_name(model);
-        let endpoint = format!(
-            "{}/{}/models/{}:streamGenerateContent",
-            self.config.base_url,
-            self.config.api_version.as_str(),
-            model_name
-        );
+        let started = Instant::now();
+        if let Some(metrics) = &self.metrics {
+            metrics.request_started("stream_generate_content", &model_name);
+        }
+
+        #[cfg(feature = "otel")]
+        let result = {
+            use tracing::Instrument;
+
+            let span = crate::otel::operation_span("generate_content", &model_name);
+            let result = self
+                .stream_generate_content_inner(model, request)
+                .instrument(span.clone())
+                .await;
+            crate::otel::record_stream(span, result, started)
+        };
+        #[cfg(not(feature = "otel"))]
+        let result = self.stream_generate_content_inner(model, request).await;
+
+        let Some(metrics) = &self.metrics else {
+            return result.map(Either::Right);
+        };
+        match result {
+            Ok(stream) => Ok(Either::Left(crate::metrics::ObservedStream::new(
+                stream,
+                metrics.clone(),
+                model_name,
+                started,
+            ))),
+            Err(error) => {
+                metrics.request_failed(
+                    "stream_generate_content",
+                    &model_name,
+                    started.elapsed(),
+                    &error,
+                );
+                Err(error)
+            }
+        }
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn stream_generate_content_inner(
+        &self,
+        model: Option<&str>,
+        mut request: GenerateContentRequest,
+    ) -> Result<impl futures::Stream<Item = Result<GenerateContentResponse>>> {
+        request.normalize();
+        let model_name = self.config.get_model_name(model);
+        self.config.apply_request_defaults(&mut request);
+        self.config.apply_profiles(&model_name, &mut request)?;
+        request.validate()?;
+        request.validate_tools(&model_name)?;
+        request.validate_modalities(&model_name)?;
+        #[cfg(feature = "thinking")]
+        request.validate_thinking(&model_name)?;
+
+        // Offloaded files outlive the stream and are left to expire
+        if self.config.inline_offload.enabled {
+            self.offload_inline_data(&model_name, &mut request).await?;
+        }
+
+        #[cfg(feature = "caching")]
+        let (model_name, request) = match &self.auto_cache {
+            Some(policy) => self.apply_auto_cache(policy, model_name, request).await,
+            None => (model_name, request),
+        };
+
+        #[cfg(feature = "otel")]
+        crate::otel::record_request(&tracing::Span::current(), &model_name, &request);
+
+        let endpoint = self.model_url(&model_name, "streamGenerateContent");
 
         debug!("Streaming content with model: {}", model_name);
 
+        // Failures before the first byte are retried; mid-stream failures are not
+        let max_attempts = if self.config.retry_config.retry_stream_connect {
+            self.config.retry_config.max_attempts
+        } else {
+            1
+        };
         let response = self
-            .http_client
-            .post(&endpoint)
-            .query(&[("key", &self.config.api_key)])
-            .json(&request)
-            .send()
+            .send_with_retry(
+                |client| {
+                    client
+                        .http_client
+                        .post(&endpoint)
+                        .header(API_KEY_HEADER, client.config.api_key.expose())
+                        .query(&[("alt", "sse")])
+                        .json(&request)
+                },
+                max_attempts,
+            )
             .await?;
+        Ok(self.parse_response_stream(response))
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(self.handle_api_error(status, error_body));
-        }
+    /// Turn a streaming response body into parsed chunks
+    ///
+    /// Bodies sent as `text/event-stream` are parsed as server-sent events; anything else,
+    /// such as a proxy ignoring `alt=sse`, as a streamed JSON array. With the `testing`
+    /// feature, configured stream faults are injected into the raw bytes.
+    #[cfg(feature = "streaming")]
+    fn parse_response_stream(
+        &self,
+        response: Response,
+    ) -> impl futures::Stream<Item = Result<GenerateContentResponse>> {
+        use futures::future::Either;
+        use futures::StreamExt;
 
-        #[cfg(feature = "streaming")]
-        {
-            Ok(crate::streaming::parse_stream(response))
-        }
-        #[cfg(not(feature = "streaming"))]
-        {
-            Err(Error::Config("Streaming feature not enabled".to_string()))
-        }
+        let sse = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.trim_start().starts_with("text/event-stream"));
+        let http = self.config.http_config.clone();
+        let bytes = response.bytes_stream().map(move |chunk| {
+            chunk
+                .map(|chunk| chunk.to_vec())
+                .map_err(|e| Error::from_reqwest(e, &http))
+        });
+        #[cfg(feature = "testing")]
+        let bytes = {
+            let injector = self.fault_injector.clone();
+            bytes.map(move |chunk| match (chunk, &injector) {
+                (Ok(chunk), Some(injector)) => injector.apply_to_chunk(&chunk),
+                (chunk, _) => chunk,
+            })
+        };
+
+        let bytes =
+            crate::streaming::with_idle_timeout(bytes, self.config.http_config.stream_idle_timeout);
+        let bytes = Box::pin(bytes);
+        let chunks = if sse {
+            Either::Left(crate::streaming::parse_sse_byte_stream_bounded(
+                bytes,
+                self.config.http_config.stream_buffer_limit,
+            ))
+        } else {
+            debug!("Stream response is not server-sent events; parsing a JSON array");
+            Either::Right(crate::streaming::parse_byte_stream_bounded(
+                bytes,
+                self.config.http_config.stream_buffer_limit,
+            ))
+        };
+        crate::streaming::with_partial_on_error(chunks)
+    }
+
+    /// Stream typed events (text and thought deltas, tool calls, usage, finish)
+    #[cfg(feature = "streaming")]
+    pub async fn stream_events(
+        &self,
+        model: Option<&str>,
+        request: GenerateContentRequest,
+    ) -> Result<impl futures::Stream<Item = Result<crate::streaming::StreamEvent>>> {
+        use crate::streaming::GeminiStreamExt;
+
+        let stream = self.stream_generate_content(model, request).await?;
+        Ok(stream.events())
     }
 
     /// Count tokens for the given content
@@ -127,12 +518,7 @@ impl GeminiClient {
         contents: Vec<Content>,
     ) -> Result<CountTokensResponse> {
         let model_name = self.config.get_model_name(model);
-        let endpoint = format!(
-            "{}/{}/models/{}:countTokens",
-            self.config.base_url,
-            self.config.api_version.as_str(),
-            model_name
-        );
+        let endpoint = self.model_url(&model_name, "countTokens");
 
         let request = CountTokensRequest { contents };
 
@@ -140,12 +526,216 @@ impl GeminiClient {
             client
                 .http_client
                 .post(&endpoint)
-                .query(&[("key", &client.config.api_key)])
+                .header(API_KEY_HEADER, client.config.api_key.expose())
                 .json(&request)
         })
         .await
     }
 
+    /// Embed a content, with `gemini-embedding-001` unless another model is given
+    #[instrument(skip(self, content))]
+    pub async fn embed_content(
+        &self,
+        model: Option<&str>,
+        content: impl Into<Content>,
+        task_type: Option<EmbeddingTaskType>,
+    ) -> Result<ContentEmbedding> {
+        let model_name = self
+            .config
+            .get_model_name(Some(model.unwrap_or(DEFAULT_EMBEDDING_MODEL)));
+        let endpoint = self.model_url(&model_name, "embedContent");
+
+        let request = EmbedContentRequest {
+            model: format!("models/{}", model_name),
+            content: content.into(),
+            task_type,
+            title: None,
+            output_dimensionality: None,
+        };
+
+        let response: EmbedContentResponse = self
+            .execute_with_retry(|client| {
+                client
+                    .http_client
+                    .post(&endpoint)
+                    .header(API_KEY_HEADER, client.config.api_key.expose())
+                    .json(&request)
+            })
+            .await?;
+        Ok(response.embedding)
+    }
+
+    /// Embed several contents, in batches of up to 100 per request
+    ///
+    /// Embeddings are returned in the order of `contents`.
+    #[instrument(skip(self, contents), fields(count = contents.len()))]
+    pub async fn batch_embed_contents(
+        &self,
+        model: Option<&str>,
+        contents: Vec<Content>,
+        task_type: Option<EmbeddingTaskType>,
+    ) -> Result<Vec<ContentEmbedding>> {
+        let model_name = self
+            .config
+            .get_model_name(Some(model.unwrap_or(DEFAULT_EMBEDDING_MODEL)));
+        let endpoint = self.model_url(&model_name, "batchEmbedContents");
+
+        let mut embeddings = Vec::with_capacity(contents.len());
+        for batch in contents.chunks(MAX_EMBEDDING_BATCH) {
+            let request = BatchEmbedContentsRequest {
+                requests: batch
+                    .iter()
+                    .map(|content| EmbedContentRequest {
+                        model: format!("models/{}", model_name),
+                        content: content.clone(),
+                        task_type,
+                        title: None,
+                        output_dimensionality: None,
+                    })
+                    .collect(),
+            };
+            let response: BatchEmbedContentsResponse = self
+                .execute_with_retry(|client| {
+                    client
+                        .http_client
+                        .post(&endpoint)
+                        .header(API_KEY_HEADER, client.config.api_key.expose())
+                        .json(&request)
+                })
+                .await?;
+            if response.embeddings.len() != batch.len() {
+                return Err(Error::InvalidResponse(format!(
+                    "Expected {} embeddings, got {}",
+                    batch.len(),
+                    response.embeddings.len()
+                )));
+            }
+            embeddings.extend(response.embeddings);
+        }
+        Ok(embeddings)
+    }
+
+    /// List the models available to this API key
+    #[instrument(skip(self))]
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let endpoint = format!(
+            "{}/{}/models",
+            self.config.base_url,
+            self.config.api_version.as_str()
+        );
+
+        let mut models = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let page: ListModelsResponse = self
+                .execute_with_retry(|client| {
+                    let mut request = client
+                        .http_client
+                        .get(&endpoint)
+                        .header(API_KEY_HEADER, client.config.api_key.expose())
+                        .query(&[("pageSize", "1000")]);
+                    if let Some(token) = &page_token {
+                        request = request.query(&[("pageToken", token)]);
+                    }
+                    request
+                })
+                .await?;
+            models.extend(page.models);
+            match page.next_page_token.filter(|token| !token.is_empty()) {
+                Some(token) => page_token = Some(token),
+                None => return Ok(models),
+            }
+        }
+    }
+
+    /// Drop model aliases whose target is no longer served, checked against
+    /// [`list_models`](Self::list_models)
+    ///
+    /// Returns the updated client; removed aliases are logged.
+    pub async fn refresh_model_aliases(mut self) -> Result<Self> {
+        let available: std::collections::HashSet<String> = self
+            .list_models()
+            .await?
+            .iter()
+            .map(|model| model.id().to_string())
+            .collect();
+
+        let config = Arc::make_mut(&mut self.config);
+        config.model_config.model_aliases.retain(|alias, target| {
+            let keep = available.contains(target);
+            if !keep {
+                warn!(
+                    "Dropping model alias {} -> {}: model not available",
+                    alias, target
+                );
+            }
+            keep
+        });
+        Ok(self)
+    }
+
+    /// Generate JSON and deserialize it into `T`
+    ///
+    /// Asks for `application/json` output unless the request sets another MIME type. Code
+    /// fences around the answer are removed; with
+    /// [`repair_json`](crate::ModelConfig::repair_json) set, trailing commas and unclosed
+    /// strings and brackets are fixed as well.
+    #[instrument(skip(self, request))]
+    pub async fn generate_json<T: DeserializeOwned>(
+        &self,
+        model: Option<&str>,
+        mut request: GenerateContentRequest,
+    ) -> Result<T> {
+        let generation_config = request
+            .generation_config
+            .get_or_insert_with(Default::default);
+        if generation_config.response_mime_type.is_none() {
+            generation_config.response_mime_type = Some("application/json".to_string());
+        }
+
+        let response = self.generate_content(model, request).await?;
+        let text = response
+            .text()
+            .ok_or_else(|| Error::InvalidResponse("No text in response".to_string()))?;
+        crate::json::parse(&text, self.config.model_config.repair_json)
+    }
+
+    /// Classify a prompt into one of the variants of a unit enum
+    ///
+    /// The enum's variants are sent as a `text/x.enum` response schema and the model's
+    /// answer is deserialized back into `E`.
+    #[cfg(feature = "schemars")]
+    #[instrument(skip(self, prompt))]
+    pub async fn classify<E>(&self, model: Option<&str>, prompt: impl IntoContents) -> Result<E>
+    where
+        E: schemars::JsonSchema + DeserializeOwned,
+    {
+        let schema = ResponseSchema::from_type::<E>()?;
+        if schema.enum_values.is_none() {
+            return Err(Error::SchemaValidation(
+                "classify requires a unit-variant enum type".to_string(),
+            ));
+        }
+
+        let request = GenerateContentRequest {
+            generation_config: Some(GenerationConfig {
+                response_mime_type: Some("text/x.enum".to_string()),
+                response_schema: Some(schema),
+                ..Default::default()
+            }),
+            ..GenerateContentRequest::new(prompt)
+        };
+
+        let response = self.generate_content(model, request).await?;
+        let label = response.text().ok_or_else(|| {
+            Error::InvalidResponse("No text in classification response".to_string())
+        })?;
+
+        serde_json::from_value(serde_json::Value::String(label.trim().to_string())).map_err(|_| {
+            Error::InvalidResponse(format!("Unexpected classification label: {:?}", label))
+        })
+    }
+
     /// Get the cache manager
     #[cfg(feature = "caching")]
     pub fn cache_manager(&self) -> &Arc<CacheManager> {
@@ -162,39 +752,200 @@ impl GeminiClient {
         &self.http_client
     }
 
+    /// Build the URL for a model method, honoring per-model endpoint overrides
+    fn model_url(&self, model_name: &str, method: &str) -> String {
+        format!(
+            "{}/{}/models/{}:{}",
+            self.config.base_url_for(model_name),
+            self.config.api_version.as_str(),
+            model_name,
+            method
+        )
+    }
+
     /// Build the HTTP client with configuration
     fn build_http_client(config: &GeminiConfig) -> Result<HttpClient> {
+        let http = &config.http_config;
         let mut builder = HttpClient::builder()
-            .timeout(config.http_config.timeout)
-            .connect_timeout(config.http_config.connect_timeout);
+            .timeout(http.timeout)
+            .connect_timeout(http.connect_timeout)
+            .gzip(http.gzip)
+            .brotli(http.brotli)
+            .http2_adaptive_window(http.http2_adaptive_window)
+            .local_address(http.local_address);
 
-        if config.http_config.pool_connections {
+        let mut client_id = concat!("gemini-rust/", env!("CARGO_PKG_VERSION")).to_string();
+        if let Some(application) = &config.user_agent {
+            client_id = format!("{} {}", client_id, application);
+        }
+        let mut headers = reqwest::header::HeaderMap::new();
+        let header_value = reqwest::header::HeaderValue::from_str(&client_id)
+            .map_err(|e| Error::Config(format!("Invalid user agent '{}': {}", client_id, e)))?;
+        headers.insert("x-goog-api-client", header_value);
+        builder = builder.user_agent(client_id).default_headers(headers);
+
+        if http.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        builder = Self::trust_roots(builder, http)?;
+        if let Some(path) = &http.tls.client_identity {
+            let pem = read_pem(path, "client identity")?;
+            let identity = reqwest::Identity::from_pem(&pem).map_err(|e| {
+                Error::Config(format!("Invalid client identity {}: {}", path.display(), e))
+            })?;
+            // PEM identities are only supported by the rustls backend
+            builder = builder.use_rustls_tls().identity(identity);
+        }
+
+        if http.pool_connections {
             builder = builder
                 .pool_idle_timeout(Duration::from_secs(90))
-                .pool_max_idle_per_host(config.http_config.pool_max_idle_per_host);
+                .pool_max_idle_per_host(http.pool_max_idle_per_host);
         }
 
-        builder.build().map_err(Error::from)
+        builder.build().map_err(Error::Http)
+    }
+
+    /// Build a client for hosts other than the API, without its identity or default headers
+    ///
+    /// Only the timeouts and the trusted roots are carried over from the configuration.
+    pub(crate) fn foreign_http_client(&self) -> Result<HttpClient> {
+        let http = &self.config.http_config;
+        let builder = HttpClient::builder()
+            .timeout(http.timeout)
+            .connect_timeout(http.connect_timeout);
+        Self::trust_roots(builder, http)?
+            .build()
+            .map_err(Error::Http)
+    }
+
+    /// Add the configured root certificates and minimum TLS version
+    fn trust_roots(
+        mut builder: reqwest::ClientBuilder,
+        http: &HttpConfig,
+    ) -> Result<reqwest::ClientBuilder> {
+        for path in &http.tls.root_certificates {
+            let pem = read_pem(path, "root certificate")?;
+            let certificates = reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| {
+                Error::Config(format!(
+                    "Invalid root certificate {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if let Some(version) = http.tls.min_version {
+            builder = builder.min_tls_version(match version {
+                TlsVersion::Tls12 => reqwest::tls::Version::TLS_1_2,
+                TlsVersion::Tls13 => reqwest::tls::Version::TLS_1_3,
+            });
+        }
+        Ok(builder)
     }
 
     /// Execute a request with retry logic
-    async fn execute_with_retry<T, F>(&self, build_request: F) -> Result<T>
+    pub(crate) async fn execute_with_retry<T, F>(&self, build_request: F) -> Result<T>
     where
         T: DeserializeOwned,
         F: Fn(&Self) -> RequestBuilder,
     {
-        let mut attempts = 0;
+        let response = self
+            .send_with_retry(build_request, self.config.retry_config.max_attempts)
+            .await?;
+        self.read_json(response).await
+    }
+
+    /// Decode a successful response body, logging it if body logging is on
+    async fn read_json<T: DeserializeOwned>(&self, response: Response) -> Result<T> {
+        let logging = &self.config.http_config.logging;
+        if logging.log_bodies {
+            let status = response.status();
+            let body = response
+                .bytes()
+                .await
+                .map_err(|e| Error::from_reqwest(e, &self.config.http_config))?;
+            crate::logging::log_response(status, &body, logging.max_text_length);
+            return serde_json::from_slice(&body).map_err(Error::from);
+        }
+        response
+            .json::<T>()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &self.config.http_config))
+    }
+
+    /// Send a request until it gets a successful status, retrying retryable failures
+    pub(crate) async fn send_with_retry<F>(
+        &self,
+        build_request: F,
+        max_attempts: u32,
+    ) -> Result<Response>
+    where
+        F: Fn(&Self) -> RequestBuilder,
+    {
+        self.send_counting_attempts(build_request, &mut 0, max_attempts)
+            .await
+    }
+
+    /// Like [`send_with_retry`](Self::send_with_retry), continuing from `attempts` already made
+    ///
+    /// `attempts` is advanced for every request sent, so callers that retry on their own can
+    /// share one attempt budget with the error retries here.
+    async fn send_counting_attempts<F>(
+        &self,
+        build_request: F,
+        attempts: &mut u32,
+        max_attempts: u32,
+    ) -> Result<Response>
+    where
+        F: Fn(&Self) -> RequestBuilder,
+    {
         let mut last_error = None;
+        let logging = &self.config.http_config.logging;
 
-        while attempts < self.config.retry_config.max_attempts {
-            attempts += 1;
+        while *attempts < max_attempts {
+            *attempts += 1;
+            let attempts = *attempts;
 
             let request = build_request(self);
+            if logging.log_bodies {
+                crate::logging::log_request(&request, logging.max_text_length);
+            }
+
+            #[cfg(feature = "testing")]
+            if let Some(injector) = &self.fault_injector {
+                if let Some(error) = injector.before_request().await {
+                    if !self.retry_policy.should_retry(&error, attempts) || attempts >= max_attempts
+                    {
+                        return Err(error);
+                    }
+
+                    let delay = error
+                        .retry_delay()
+                        .unwrap_or_else(|| self.calculate_retry_delay(attempts))
+                        .min(self.config.retry_config.max_delay);
+                    warn!(
+                        "Injected fault (attempt {}), retrying in {:?}",
+                        attempts, delay
+                    );
+                    self.note_retry(attempts, &error);
+                    last_error = Some(error);
+                    sleep(delay).await;
+                    continue;
+                }
+            }
+
             let response = match request.send().await {
                 Ok(resp) => resp,
                 Err(e) => {
-                    last_error = Some(Error::from(e));
-                    if attempts < self.config.retry_config.max_attempts {
+                    let error = Error::from_reqwest(e, &self.config.http_config);
+                    let retry = self.retry_policy.should_retry(&error, attempts);
+                    if retry && attempts < max_attempts {
+                        self.note_retry(attempts, &error);
+                        last_error = Some(error);
                         let delay = self.calculate_retry_delay(attempts);
                         warn!(
                             "Request failed (attempt {}), retrying in {:?}",
@@ -203,6 +954,7 @@ impl GeminiClient {
                         sleep(delay).await;
                         continue;
                     }
+                    last_error = Some(error);
                     break;
                 }
             };
@@ -210,23 +962,34 @@ impl GeminiClient {
             let status = response.status();
 
             if status.is_success() {
-                return response.json::<T>().await.map_err(Error::from);
+                return Ok(response);
             }
 
+            let retry_after = parse_retry_after(response.headers());
+            let ids = ResponseIds::from_headers(response.headers());
             let error_body = response.text().await.unwrap_or_default();
-            let error = self.handle_api_error(status, error_body);
+            if logging.log_bodies {
+                crate::logging::log_response(
+                    status,
+                    error_body.as_bytes(),
+                    logging.max_text_length,
+                );
+            }
+            let server_delay = retry_after.or_else(|| retry_info_delay(&error_body));
+            let error = self.handle_api_error(status, error_body, server_delay, ids);
 
-            if !error.is_retryable() || attempts >= self.config.retry_config.max_attempts {
+            if !self.retry_policy.should_retry(&error, attempts) || attempts >= max_attempts {
                 return Err(error);
             }
 
+            // Server-provided hints take precedence, but never exceed max_delay
+            let delay = server_delay
+                .or_else(|| error.retry_delay())
+                .unwrap_or_else(|| self.calculate_retry_delay(attempts))
+                .min(self.config.retry_config.max_delay);
+            self.note_retry(attempts, &error);
             last_error = Some(error);
 
-            let delay = last_error
-                .as_ref()
-                .and_then(|e| e.retry_delay())
-                .unwrap_or_else(|| self.calculate_retry_delay(attempts));
-
             warn!("API error (attempt {}), retrying in {:?}", attempts, delay);
             sleep(delay).await;
         }
@@ -234,8 +997,14 @@ impl GeminiClient {
         Err(last_error.unwrap_or_else(|| Error::Config("Max retry attempts exceeded".to_string())))
     }
 
+    fn note_retry(&self, attempt: u32, error: &Error) {
+        if let Some(metrics) = &self.metrics {
+            metrics.request_retried(attempt, error);
+        }
+    }
+
     /// Calculate retry delay with exponential backoff
-    fn calculate_retry_delay(&self, attempt: u32) -> Duration {
+    pub(crate) fn calculate_retry_delay(&self, attempt: u32) -> Duration {
         let base_delay = self.config.retry_config.initial_delay.as_secs_f64();
         let multiplier = self.config.retry_config.backoff_multiplier;
         let max_delay = self.config.retry_config.max_delay;
@@ -255,22 +1024,54 @@ impl GeminiClient {
         }
     }
 
+    /// Turn an unsuccessful response into an API error
+    #[cfg(feature = "caching")]
+    pub(crate) async fn error_from_response(&self, response: Response) -> Error {
+        let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
+        let ids = ResponseIds::from_headers(response.headers());
+        let body = response.text().await.unwrap_or_default();
+        let server_delay = retry_after.or_else(|| retry_info_delay(&body));
+        self.handle_api_error(status, body, server_delay, ids)
+    }
+
     /// Handle API errors
-    fn handle_api_error(&self, status: StatusCode, body: String) -> Error {
+    fn handle_api_error(
+        &self,
+        status: StatusCode,
+        body: String,
+        server_delay: Option<Duration>,
+        ids: ResponseIds,
+    ) -> Error {
         let details = serde_json::from_str::<serde_json::Value>(&body).ok();
 
         match status {
             StatusCode::TOO_MANY_REQUESTS => {
-                let retry_after = details
-                    .as_ref()
-                    .and_then(|d| d.get("retryAfter"))
-                    .and_then(|v| v.as_u64())
-                    .map(Duration::from_secs);
+                let retry_after = server_delay.or_else(|| {
+                    details
+                        .as_ref()
+                        .and_then(|d| d.get("retryAfter"))
+                        .and_then(|v| v.as_u64())
+                        .map(Duration::from_secs)
+                });
 
-                Error::RateLimit { retry_after }
+                Error::RateLimit {
+                    retry_after,
+                    quota_violations: details
+                        .as_ref()
+                        .map(QuotaViolation::from_error_body)
+                        .unwrap_or_default(),
+                }
             }
             _ => Error::Api {
                 status: status.as_u16(),
+                code: details
+                    .as_ref()
+                    .and_then(|d| d.get("error"))
+                    .and_then(|e| e.get("status"))
+                    .and_then(|s| s.as_str())
+                    .map(ApiErrorCode::parse)
+                    .or_else(|| ApiErrorCode::from_http_status(status.as_u16())),
                 message: details
                     .as_ref()
                     .and_then(|d| d.get("error"))
@@ -279,22 +1080,95 @@ impl GeminiClient {
                     .unwrap_or(&body)
                     .to_string(),
                 details,
+                response: Box::new(ApiErrorResponse {
+                    raw_body: body,
+                    request_id: ids.request_id,
+                    trace_id: ids.trace_id,
+                }),
             },
         }
     }
 }
 
+/// Request and trace identifiers from response headers, for support tickets
+struct ResponseIds {
+    request_id: Option<String>,
+    trace_id: Option<String>,
+}
+
+impl ResponseIds {
+    fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let first = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                headers
+                    .get(*name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            })
+        };
+        Self {
+            request_id: first(&["x-request-id", "x-goog-request-id"]),
+            trace_id: first(&["x-cloud-trace-context", "traceparent"]),
+        }
+    }
+}
+
+/// Read a PEM file referenced by the TLS configuration
+fn read_pem(path: &std::path::Path, what: &str) -> Result<Vec<u8>> {
+    std::fs::read(path)
+        .map_err(|e| Error::Config(format!("Failed to read {} {}: {}", what, path.display(), e)))
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Extract the delay from a `google.rpc.RetryInfo` entry in an error body's details
+fn retry_info_delay(body: &str) -> Option<Duration> {
+    let body: serde_json::Value = serde_json::from_str(body).ok()?;
+    body.get("error")?
+        .get("details")?
+        .as_array()?
+        .iter()
+        .filter(|detail| {
+            detail
+                .get("@type")
+                .and_then(|t| t.as_str())
+                .is_some_and(|t| t.ends_with("google.rpc.RetryInfo"))
+        })
+        .find_map(|detail| {
+            let delay = detail.get("retryDelay")?.as_str()?;
+            let seconds: f64 = delay.strip_suffix('s')?.parse().ok()?;
+            Duration::try_from_secs_f64(seconds).ok()
+        })
+}
+
 /// Builder for creating a customized GeminiClient
 #[derive(Default)]
 pub struct GeminiClientBuilder {
     config: Option<GeminiConfig>,
+    skip_validation: bool,
 }
 
 impl GeminiClientBuilder {
     /// Set the API key
     pub fn api_key(mut self, key: impl Into<String>) -> Self {
         let mut config = self.config.unwrap_or_default();
-        config.api_key = key.into();
+        config.api_key = SecretString::new(key);
         self.config = Some(config);
         self
     }
@@ -307,6 +1181,14 @@ impl GeminiClientBuilder {
         self
     }
 
+    /// Route requests for a specific model to a different base URL
+    pub fn model_endpoint(mut self, model: impl Into<String>, url: impl Into<String>) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.model_endpoints.insert(model.into(), url.into());
+        self.config = Some(config);
+        self
+    }
+
     /// Set the API version
     pub fn api_version(mut self, version: ApiVersion) -> Self {
         let mut config = self.config.unwrap_or_default();
@@ -323,6 +1205,63 @@ impl GeminiClientBuilder {
         self
     }
 
+    /// Resolve `alias` to `model` on every request
+    pub fn model_alias(mut self, alias: impl Into<String>, model: impl Into<String>) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config
+            .model_config
+            .model_aliases
+            .insert(alias.into(), model.into());
+        self.config = Some(config);
+        self
+    }
+
+    /// Add a named generation profile; name it after a model to make it that model's default
+    pub fn profile(mut self, name: impl Into<String>, profile: GenerationConfig) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.model_config.profiles.insert(name.into(), profile);
+        self.config = Some(config);
+        self
+    }
+
+    /// Apply a profile to every request
+    pub fn default_profile(mut self, name: impl Into<String>) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.model_config.default_profile = Some(name.into());
+        self.config = Some(config);
+        self
+    }
+
+    /// Add safety settings to every request that does not set the same category
+    pub fn default_safety_settings(mut self, settings: Vec<SafetySetting>) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.default_safety_settings = settings;
+        self.config = Some(config);
+        self
+    }
+
+    /// Use this system instruction for requests that do not set their own
+    pub fn default_system_instruction(mut self, instruction: impl Into<String>) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.default_system_instruction = Some(Content::system(instruction));
+        self.config = Some(config);
+        self
+    }
+
+    /// Identify the application in the `User-Agent` and `x-goog-api-client` headers
+    pub fn user_agent(mut self, application: impl Into<String>) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.user_agent = Some(application.into());
+        self.config = Some(config);
+        self
+    }
+
+    /// Build without validating the rest of the configuration
+    pub fn skip_validation(mut self) -> Self {
+        self.skip_validation = true;
+        self
+    }
+
     /// Set request timeout
     pub fn timeout(mut self, timeout: Duration) -> Self {
         let mut config = self.config.unwrap_or_default();
@@ -331,6 +1270,68 @@ impl GeminiClientBuilder {
         self
     }
 
+    /// Fail instead of warning when thinking leaves no room for an answer
+    pub fn strict_thinking(mut self, strict: bool) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.model_config.strict_thinking = strict;
+        self.config = Some(config);
+        self
+    }
+
+    /// Fail with `Error::EmptyResponse` instead of returning responses without content
+    pub fn strict_responses(mut self, strict: bool) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.model_config.strict_responses = strict;
+        self.config = Some(config);
+        self
+    }
+
+    /// Repair almost-valid JSON before deserializing it in `generate_json`
+    pub fn repair_json(mut self, repair: bool) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.model_config.repair_json = repair;
+        self.config = Some(config);
+        self
+    }
+
+    /// Abort streams that receive no bytes for this long
+    ///
+    /// Slow events that keep arriving byte by byte do not trip it.
+    pub fn stream_idle_timeout(mut self, timeout: Duration) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.http_config.stream_idle_timeout = Some(timeout);
+        self.config = Some(config);
+        self
+    }
+
+    /// Fail streams that buffer more than this many bytes without a complete event
+    pub fn stream_buffer_limit(mut self, bytes: usize) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.http_config.stream_buffer_limit = bytes;
+        self.config = Some(config);
+        self
+    }
+
+    /// Upload inline data through the Files API when a request would exceed `threshold_bytes`
+    pub fn offload_inline_data(mut self, threshold_bytes: usize) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.inline_offload.enabled = true;
+        config.inline_offload.threshold_bytes = threshold_bytes;
+        self.config = Some(config);
+        self
+    }
+
+    /// Log redacted request and response bodies at debug level
+    pub fn log_bodies(mut self, max_text_length: usize) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.http_config.logging = LoggingConfig {
+            log_bodies: true,
+            max_text_length,
+        };
+        self.config = Some(config);
+        self
+    }
+
     /// Set retry configuration
     pub fn max_retries(mut self, retries: u32) -> Self {
         let mut config = self.config.unwrap_or_default();
@@ -349,6 +1350,10 @@ impl GeminiClientBuilder {
             return Err(Error::Config("API key is required".to_string()));
         }
 
-        GeminiClient::new(config)
+        if self.skip_validation {
+            GeminiClient::new_unchecked(config)
+        } else {
+            GeminiClient::new(config)
+        }
     }
 }
@@ -1,22 +1,35 @@
 //! Configuration for the Gemini API client
 
+use crate::models::{Content, GenerateContentRequest, GenerationConfig, SafetySetting};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::Duration;
 
 /// Configuration for the Gemini API client
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeminiConfig {
-    /// API key for authentication
-    pub api_key: String,
+    /// API key for authentication, redacted in `Debug` and serialized output
+    #[serde(default)]
+    pub api_key: SecretString,
 
     /// Base URL for the API (can be overridden for testing)
     #[serde(default = "default_base_url")]
     pub base_url: String,
 
+    /// Base URL overrides keyed by model name (e.g. regional endpoints for data residency)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub model_endpoints: HashMap<String, String>,
+
     /// API version to use
     #[serde(default)]
     pub api_version: ApiVersion,
 
+    /// Application identifier (e.g. `my-app/1.2`) appended to the `User-Agent` and
+    /// `x-goog-api-client` headers, for attributing usage per application
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+
     /// HTTP client configuration
     #[serde(default)]
     pub http_config: HttpConfig,
@@ -28,6 +41,88 @@ pub struct GeminiConfig {
     /// Default model configuration
     #[serde(default)]
     pub model_config: ModelConfig,
+
+    /// Safety settings added to every request; a request's own setting for a category wins
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_safety_settings: Vec<SafetySetting>,
+
+    /// System instruction for requests that do not set their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_system_instruction: Option<Content>,
+
+    /// Uploading oversized inline data through the Files API
+    #[serde(default)]
+    pub inline_offload: OffloadConfig,
+}
+
+/// A secret such as an API key that is redacted when printed or serialized
+///
+/// `Debug`, `Display` and `Serialize` all produce `[REDACTED]`; use
+/// [`expose`](Self::expose) to read the value. Because serialization redacts, configs written
+/// out and read back need the key supplied again (e.g. through `GEMINI_API_KEY`): the
+/// placeholder deserializes as an empty secret rather than as a key.
+#[derive(Clone, Default)]
+pub struct SecretString(String);
+
+/// What a secret prints and serializes as
+const REDACTED: &str = "[REDACTED]";
+
+impl SecretString {
+    /// Wrap a secret value
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    /// The secret value
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the secret is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+impl std::fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl std::fmt::Display for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let secret = String::deserialize(deserializer)?;
+        Ok(if secret == REDACTED {
+            Self::default()
+        } else {
+            Self(secret)
+        })
+    }
 }
 
 /// API version to use for requests
@@ -54,6 +149,7 @@ impl ApiVersion {
 
 /// HTTP client configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct HttpConfig {
     /// Request timeout
     #[serde(with = "humantime_serde")]
@@ -68,6 +164,129 @@ pub struct HttpConfig {
 
     /// Maximum idle connections per host
     pub pool_max_idle_per_host: usize,
+
+    /// Abort a stream with `Error::Timeout` if no bytes arrive for this long; off by default
+    #[serde(with = "humantime_serde")]
+    pub stream_idle_timeout: Option<Duration>,
+
+    /// High-water mark for bytes buffered while waiting for a complete stream event
+    pub stream_buffer_limit: usize,
+
+    /// Accept gzip-compressed responses
+    pub gzip: bool,
+
+    /// Accept brotli-compressed responses
+    pub brotli: bool,
+
+    /// Speak HTTP/2 without negotiation (only for endpoints known to support it)
+    pub http2_prior_knowledge: bool,
+
+    /// Let HTTP/2 flow-control windows adapt to the measured bandwidth-delay product
+    pub http2_adaptive_window: bool,
+
+    /// Local address to bind outgoing connections to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_address: Option<std::net::IpAddr>,
+
+    /// TLS settings
+    pub tls: TlsConfig,
+
+    /// Debug logging of request and response bodies
+    pub logging: LoggingConfig,
+}
+
+/// TLS settings for gateways and private API proxies
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// PEM files with extra root certificates to trust, in addition to the built-in roots
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub root_certificates: Vec<PathBuf>,
+
+    /// PEM file holding the client certificate chain and private key, for mTLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_identity: Option<PathBuf>,
+
+    /// Minimum TLS version to negotiate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<TlsVersion>,
+}
+
+/// Debug logging of request and response JSON
+///
+/// Bodies are logged under the `gemini_rust::logging` target after
+/// [`redact`](crate::logging::redact): API keys are hidden, inline data is replaced by its size
+/// and MIME type, and long strings are truncated. Streamed responses are not logged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Log request and response bodies at debug level
+    #[serde(default)]
+    pub log_bodies: bool,
+
+    /// Strings longer than this many characters are truncated in logs
+    #[serde(default = "default_max_logged_text")]
+    pub max_text_length: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            log_bodies: false,
+            max_text_length: default_max_logged_text(),
+        }
+    }
+}
+
+/// Automatic upload of inline data that would push a request over the inline size limit
+///
+/// When enabled, `generate_content` and `stream_generate_content` upload the largest
+/// `InlineData` parts through the Files API until the serialized request fits under
+/// `threshold_bytes`, and reference them as `FileData` instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OffloadConfig {
+    /// Upload oversized inline data instead of sending it
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Largest serialized request sent as is
+    #[serde(default = "default_offload_threshold")]
+    pub threshold_bytes: usize,
+
+    /// What happens to uploaded files once the request is done
+    #[serde(default)]
+    pub cleanup: OffloadCleanup,
+}
+
+impl Default for OffloadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_bytes: default_offload_threshold(),
+            cleanup: OffloadCleanup::default(),
+        }
+    }
+}
+
+/// Cleanup policy for files uploaded by inline data offloading
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OffloadCleanup {
+    /// Delete the files once a `generate_content` call completes; streamed requests leave
+    /// them to expire
+    #[default]
+    AfterRequest,
+    /// Leave the files to expire on their own (after 48 hours)
+    Expire,
+}
+
+/// TLS protocol version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TlsVersion {
+    /// TLS 1.2
+    #[serde(rename = "1.2")]
+    Tls12,
+    /// TLS 1.3
+    #[serde(rename = "1.3")]
+    Tls13,
 }
 
 impl Default for HttpConfig {
@@ -77,12 +296,22 @@ impl Default for HttpConfig {
             connect_timeout: Duration::from_secs(30),
             pool_connections: true,
             pool_max_idle_per_host: 10,
+            stream_idle_timeout: None,
+            stream_buffer_limit: default_stream_buffer_limit(),
+            gzip: default_compression(),
+            brotli: default_compression(),
+            http2_prior_knowledge: false,
+            http2_adaptive_window: false,
+            local_address: None,
+            tls: TlsConfig::default(),
+            logging: LoggingConfig::default(),
         }
     }
 }
 
 /// Retry configuration for failed requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RetryConfig {
     /// Maximum number of retry attempts
     pub max_attempts: u32,
@@ -100,6 +329,9 @@ pub struct RetryConfig {
 
     /// Add jitter to retry delays
     pub jitter: bool,
+
+    /// Retry failures that happen before a stream's first byte
+    pub retry_stream_connect: bool,
 }
 
 impl Default for RetryConfig {
@@ -110,6 +342,7 @@ impl Default for RetryConfig {
             max_delay: Duration::from_secs(60),
             backoff_multiplier: 2.0,
             jitter: true,
+            retry_stream_connect: default_retry_stream_connect(),
         }
     }
 }
@@ -121,9 +354,35 @@ pub struct ModelConfig {
     #[serde(default = "default_model")]
     pub model: String,
 
-    /// Whether to use the latest version suffix
-    #[serde(default = "default_use_latest")]
-    pub use_latest: bool,
+    /// Model name aliases applied before every request (e.g. `"pro"` to `"gemini-2.5-pro"`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub model_aliases: HashMap<String, String>,
+
+    /// Fail with `Error::ThinkingBudgetExceeded` instead of warning when thinking uses up
+    /// the output token limit and leaves no answer
+    #[serde(default)]
+    pub strict_thinking: bool,
+
+    /// Fail with `Error::EmptyResponse` when `generate_content` gets no candidates or a
+    /// candidate without parts
+    #[serde(default)]
+    pub strict_responses: bool,
+
+    /// Repair almost-valid JSON (code fences, trailing commas, unclosed brackets) before
+    /// deserializing in `generate_json`
+    #[serde(default)]
+    pub repair_json: bool,
+
+    /// Named generation profiles (e.g. "creative", "deterministic")
+    ///
+    /// A profile named after a model is applied to that model's requests automatically.
+    /// Settings a request sets explicitly always win.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, GenerationConfig>,
+
+    /// Profile applied to every request, after the request's own and the model's profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
 
     /// Model-specific parameters
     #[serde(flatten)]
@@ -134,7 +393,12 @@ impl Default for ModelConfig {
     fn default() -> Self {
         Self {
             model: default_model(),
-            use_latest: default_use_latest(),
+            model_aliases: HashMap::new(),
+            profiles: HashMap::new(),
+            default_profile: None,
+            strict_thinking: false,
+            strict_responses: false,
+            repair_json: false,
             params: serde_json::Value::Object(Default::default()),
         }
     }
@@ -148,7 +412,23 @@ fn default_model() -> String {
     "gemini-2.5-flash".to_string()
 }
 
-fn default_use_latest() -> bool {
+fn default_retry_stream_connect() -> bool {
+    true
+}
+
+fn default_offload_threshold() -> usize {
+    20 * 1024 * 1024
+}
+
+fn default_max_logged_text() -> usize {
+    256
+}
+
+fn default_stream_buffer_limit() -> usize {
+    8 * 1024 * 1024
+}
+
+fn default_compression() -> bool {
     true
 }
 
@@ -156,47 +436,276 @@ impl GeminiConfig {
     /// Create a new configuration with an API key
     pub fn new(api_key: impl Into<String>) -> Self {
         Self {
-            api_key: api_key.into(),
+            api_key: SecretString::new(api_key),
             ..Default::default()
         }
     }
 
     /// Load configuration from environment variables
+    ///
+    /// Requires `GEMINI_API_KEY`; see [`apply_env_overrides`](Self::apply_env_overrides) for
+    /// the other variables read.
     pub fn from_env() -> crate::error::Result<Self> {
         let api_key = std::env::var("GEMINI_API_KEY").map_err(|_| {
             crate::error::Error::Config("GEMINI_API_KEY environment variable not set".to_string())
         })?;
 
-        Ok(Self::new(api_key))
+        let mut config = Self::new(api_key);
+        config.apply_env_overrides()?;
+        Ok(config)
     }
 
-    /// Get the full model name with version suffix if needed
+    /// Load configuration from a JSON, TOML (`toml` feature) or YAML (`yaml` feature) file,
+    /// chosen by extension, then apply environment overrides
+    ///
+    /// The API key may be left out of the file and supplied through `GEMINI_API_KEY`.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> crate::error::Result<Self> {
+        use crate::error::Error;
+
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("Failed to read {}: {}", path.display(), e)))?;
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let invalid = |e: &dyn std::fmt::Display| {
+            Error::Config(format!("Invalid config file {}: {}", path.display(), e))
+        };
+
+        let mut config: Self = match extension.as_str() {
+            "json" => serde_json::from_str(&contents).map_err(|e| invalid(&e))?,
+            #[cfg(feature = "toml")]
+            "toml" => toml::from_str(&contents).map_err(|e| invalid(&e))?,
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => serde_yaml::from_str(&contents).map_err(|e| invalid(&e))?,
+            other => {
+                return Err(Error::Config(format!(
+                    "Unsupported config file format '{}' (enable the `toml` or `yaml` feature for those formats)",
+                    other
+                )))
+            }
+        };
+
+        config.apply_env_overrides()?;
+        if config.api_key.is_empty() {
+            return Err(Error::Config(format!(
+                "No api_key in {} and GEMINI_API_KEY not set",
+                path.display()
+            )));
+        }
+        Ok(config)
+    }
+
+    /// Override settings from environment variables, where set
+    ///
+    /// Reads `GEMINI_API_KEY`, `GEMINI_BASE_URL`, `GEMINI_API_VERSION` (`v1` or `v1beta`),
+    /// `GEMINI_MODEL`, `GEMINI_TIMEOUT` and `GEMINI_CONNECT_TIMEOUT` (human-readable durations
+    /// such as `90s`), and `GEMINI_MAX_RETRIES`.
+    pub fn apply_env_overrides(&mut self) -> crate::error::Result<()> {
+        use crate::error::Error;
+
+        fn var(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|value| !value.is_empty())
+        }
+        fn duration(name: &str, value: &str) -> crate::error::Result<Duration> {
+            humantime_serde::re::humantime::parse_duration(value)
+                .map_err(|e| Error::Config(format!("Invalid {} '{}': {}", name, value, e)))
+        }
+
+        if let Some(api_key) = var("GEMINI_API_KEY") {
+            self.api_key = SecretString::new(api_key);
+        }
+        if let Some(base_url) = var("GEMINI_BASE_URL") {
+            self.base_url = base_url;
+        }
+        if let Some(version) = var("GEMINI_API_VERSION") {
+            self.api_version = match version.as_str() {
+                "v1" => ApiVersion::V1,
+                "v1beta" => ApiVersion::V1Beta,
+                other => {
+                    return Err(Error::Config(format!(
+                        "Invalid GEMINI_API_VERSION '{}': expected v1 or v1beta",
+                        other
+                    )))
+                }
+            };
+        }
+        if let Some(model) = var("GEMINI_MODEL") {
+            self.model_config.model = model;
+        }
+        if let Some(timeout) = var("GEMINI_TIMEOUT") {
+            self.http_config.timeout = duration("GEMINI_TIMEOUT", &timeout)?;
+        }
+        if let Some(timeout) = var("GEMINI_CONNECT_TIMEOUT") {
+            self.http_config.connect_timeout = duration("GEMINI_CONNECT_TIMEOUT", &timeout)?;
+        }
+        if let Some(retries) = var("GEMINI_MAX_RETRIES") {
+            self.retry_config.max_attempts = retries.parse().map_err(|e| {
+                Error::Config(format!("Invalid GEMINI_MAX_RETRIES '{}': {}", retries, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Resolve the model to use, applying any configured alias
     pub fn get_model_name(&self, model: Option<&str>) -> String {
         let base_model = model.unwrap_or(&self.model_config.model);
+        self.model_config
+            .model_aliases
+            .get(base_model)
+            .unwrap_or(&base_model.to_string())
+            .clone()
+    }
 
-        if self.model_config.use_latest && !base_model.contains("-preview") {
-            // Add appropriate suffix based on model type
-            match base_model {
-                "gemini-2.5-pro" => "gemini-2.5-pro-preview-05-06".to_string(),
-                "gemini-2.5-flash" => "gemini-2.5-flash".to_string(),
-                "gemini-2.0-flash" => "gemini-2.0-flash-001".to_string(),
-                _ => base_model.to_string(),
+    /// Every problem with the configuration, or an empty list if it is usable
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.api_key.is_empty() {
+            problems.push("api_key is empty".to_string());
+        }
+        let endpoints = std::iter::once(("base_url".to_string(), &self.base_url)).chain(
+            self.model_endpoints
+                .iter()
+                .map(|(model, url)| (format!("model_endpoints[{}]", model), url)),
+        );
+        for (field, url) in endpoints {
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                Ok(parsed) => problems.push(format!(
+                    "{} '{}' must use http or https, not {}",
+                    field,
+                    url,
+                    parsed.scheme()
+                )),
+                Err(e) => problems.push(format!("{} '{}' is not a valid URL: {}", field, url, e)),
             }
+        }
+
+        let http = &self.http_config;
+        if http.timeout.is_zero() {
+            problems.push("http_config.timeout is zero".to_string());
+        }
+        if http.connect_timeout.is_zero() {
+            problems.push("http_config.connect_timeout is zero".to_string());
+        }
+        if http
+            .stream_idle_timeout
+            .is_some_and(|timeout| timeout.is_zero())
+        {
+            problems.push("http_config.stream_idle_timeout is zero".to_string());
+        }
+        if http.stream_buffer_limit == 0 {
+            problems.push("http_config.stream_buffer_limit is zero".to_string());
+        }
+
+        let retry = &self.retry_config;
+        if retry.max_attempts == 0 {
+            problems.push("retry_config.max_attempts is zero".to_string());
+        }
+        if retry.backoff_multiplier < 1.0 {
+            problems.push(format!(
+                "retry_config.backoff_multiplier {} is less than 1",
+                retry.backoff_multiplier
+            ));
+        }
+        if retry.max_delay < retry.initial_delay {
+            problems.push(format!(
+                "retry_config.max_delay {:?} is less than initial_delay {:?}",
+                retry.max_delay, retry.initial_delay
+            ));
+        }
+
+        problems
+    }
+
+    /// Check the configuration, reporting every problem at once in an `Error::Config`
+    pub fn validate(&self) -> crate::error::Result<()> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
         } else {
-            base_model.to_string()
+            Err(crate::error::Error::Config(problems.join("; ")))
+        }
+    }
+
+    /// Merge the default safety settings and system instruction into a request
+    pub fn apply_request_defaults(&self, request: &mut GenerateContentRequest) {
+        if !self.default_safety_settings.is_empty() {
+            let settings = request.safety_settings.get_or_insert_with(Vec::new);
+            for default in &self.default_safety_settings {
+                if !settings.iter().any(|s| s.category == default.category) {
+                    settings.push(default.clone());
+                }
+            }
         }
+        if request.system_instruction.is_none() {
+            request
+                .system_instruction
+                .clone_from(&self.default_system_instruction);
+        }
+    }
+
+    /// Merge generation profiles into a request's unset settings
+    ///
+    /// Precedence, highest first: the request itself, the profile it names, the profile named
+    /// after `model`, then the default profile.
+    pub fn apply_profiles(
+        &self,
+        model: &str,
+        request: &mut GenerateContentRequest,
+    ) -> crate::error::Result<()> {
+        let profiles = &self.model_config.profiles;
+        let lookup = |name: &Option<String>| match name {
+            Some(name) => profiles.get(name).map(Some).ok_or_else(|| {
+                crate::error::Error::Config(format!("Unknown generation profile '{}'", name))
+            }),
+            None => Ok(None),
+        };
+        let named = lookup(&request.profile)?;
+        let default = lookup(&self.model_config.default_profile)?;
+        let layers: Vec<&GenerationConfig> = [named, profiles.get(model), default]
+            .into_iter()
+            .flatten()
+            .collect();
+        if layers.is_empty() {
+            return Ok(());
+        }
+
+        let config = request
+            .generation_config
+            .get_or_insert_with(Default::default);
+        for layer in layers {
+            config.merge_defaults(layer);
+        }
+        Ok(())
+    }
+
+    /// Get the base URL to use for a model, honoring per-model endpoint overrides
+    pub fn base_url_for(&self, model: &str) -> &str {
+        self.model_endpoints
+            .get(model)
+            .map(String::as_str)
+            .unwrap_or(&self.base_url)
     }
 }
 
 impl Default for GeminiConfig {
     fn default() -> Self {
         Self {
-            api_key: String::new(),
+            api_key: SecretString::default(),
             base_url: default_base_url(),
+            model_endpoints: HashMap::new(),
             api_version: ApiVersion::default(),
+            user_agent: None,
             http_config: HttpConfig::default(),
             retry_config: RetryConfig::default(),
             model_config: ModelConfig::default(),
+            default_safety_settings: Vec::new(),
+            default_system_instruction: None,
+            inline_offload: OffloadConfig::default(),
         }
     }
 }
@@ -1,31 +1,72 @@
 //! Error handling for the Gemini API integration
 
+use crate::config::HttpConfig;
+use crate::models::{BlockReason, FinishReason};
+use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 
 /// Result type alias for library operations
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The response behind an [`Error::Api`], kept for support tickets
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiErrorResponse {
+    /// Response body exactly as received
+    pub raw_body: String,
+    /// Request ID from the response headers, if any
+    pub request_id: Option<String>,
+    /// Trace ID from the response headers, if any
+    pub trace_id: Option<String>,
+}
+
 /// Error types for the Gemini API client
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
-    /// HTTP request error
+    /// HTTP request error, e.g. the connection dropped while reading the response
     #[error("HTTP request failed: {0}")]
-    Http(#[from] reqwest::Error),
+    Http(#[source] reqwest::Error),
+
+    /// The connection to the server could not be established
+    #[error("Connection failed: {0}")]
+    Connect(#[source] reqwest::Error),
+
+    /// The response body could not be decoded
+    #[error("Failed to decode response: {0}")]
+    Decode(#[source] reqwest::Error),
 
     /// JSON serialization/deserialization error
     #[error("JSON serialization/deserialization failed: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// Reading or writing a local file failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// An input image could not be decoded or re-encoded
+    #[cfg(feature = "image")]
+    #[error("Image processing failed: {0}")]
+    Image(#[from] image::ImageError),
+
+    /// A PDF could not be parsed or written
+    #[cfg(feature = "pdf")]
+    #[error("PDF processing failed: {0}")]
+    Pdf(#[from] lopdf::Error),
+
     /// API error response
     #[error("API error (status: {status}): {message}")]
     Api {
         /// HTTP status code
         status: u16,
+        /// Semantic error code from the body's `error.status`, or derived from the HTTP status
+        code: Option<ApiErrorCode>,
         /// Error message
         message: String,
         /// Additional error details
         details: Option<serde_json::Value>,
+        /// The raw response and its identifiers, for support tickets
+        response: Box<ApiErrorResponse>,
     },
 
     /// Rate limit exceeded
@@ -33,6 +74,8 @@ pub enum Error {
     RateLimit {
         /// Suggested retry delay
         retry_after: Option<Duration>,
+        /// Quotas the server reported as exceeded
+        quota_violations: Vec<QuotaViolation>,
     },
 
     /// Configuration error
@@ -59,6 +102,22 @@ pub enum Error {
     #[error("Streaming error: {0}")]
     Streaming(String),
 
+    /// A stream failed after producing part of the response
+    #[error("Stream interrupted after {chunks_received} chunks ({} bytes of text): {source}", partial_text.len())]
+    StreamInterrupted {
+        /// Text received before the failure
+        partial_text: String,
+        /// Number of chunks received before the failure
+        chunks_received: usize,
+        /// The error that ended the stream
+        source: Box<Error>,
+    },
+
+    /// An error delivered to several consumers of one stream, such as every
+    /// [`CandidateStream`](crate::streaming::CandidateStream) split from a response
+    #[error(transparent)]
+    Shared(Arc<Error>),
+
     /// Operation timeout
     #[error("Timeout after {0:?}")]
     Timeout(Duration),
@@ -67,18 +126,333 @@ pub enum Error {
     #[error("Invalid response format: {0}")]
     InvalidResponse(String),
 
+    /// The response had no candidates, or a first candidate without any parts
+    #[error("Empty response (finish reason: {finish_reason:?}, block reason: {block_reason:?})")]
+    EmptyResponse {
+        /// Finish reason of the first candidate, if there was one
+        finish_reason: Option<FinishReason>,
+        /// Why the prompt was blocked, if it was
+        block_reason: Option<BlockReason>,
+    },
+
+    /// The response still failed its guardrails after every repair attempt
+    #[error("Response failed guardrails after {attempts} attempts: {}", violations.join("; "))]
+    GuardrailFailed {
+        /// Problems found in the last response
+        violations: Vec<String>,
+        /// Number of responses generated
+        attempts: u32,
+    },
+
+    /// The next request would exceed a session's token budget
+    #[error("Token budget exceeded: request needs about {needed} tokens, {remaining} remain")]
+    TokenBudgetExceeded {
+        /// Estimated prompt tokens of the request
+        needed: u64,
+        /// Tokens left in the budget
+        remaining: u64,
+    },
+
     /// Thinking budget exceeded
     #[error("Thinking budget exceeded")]
     ThinkingBudgetExceeded,
 }
 
+/// An exceeded quota, from a 429 body's `QuotaFailure` or `ErrorInfo` details
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaViolation {
+    /// Quota metric, e.g. `generativelanguage.googleapis.com/generate_content_free_tier_requests`
+    pub metric: String,
+    /// Quota identifier, e.g. `GenerateRequestsPerMinutePerProjectPerModel-FreeTier`
+    pub quota_id: Option<String>,
+    /// Model the quota applies to, when it is per-model
+    pub model: Option<String>,
+    /// Quota limit, when reported
+    pub limit: Option<u64>,
+    /// What the quota limits, derived from the metric and identifier
+    pub kind: QuotaKind,
+}
+
+/// What a quota limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuotaKind {
+    /// Requests per minute (RPM)
+    RequestsPerMinute,
+    /// Requests per day (RPD)
+    RequestsPerDay,
+    /// Tokens per minute (TPM)
+    TokensPerMinute,
+    /// Tokens per day (TPD)
+    TokensPerDay,
+    /// A quota this library cannot classify
+    Other,
+}
+
+impl QuotaKind {
+    /// Classify a quota from its metric and identifier names
+    pub fn classify(metric: &str, quota_id: Option<&str>) -> Self {
+        let name = format!("{} {}", metric, quota_id.unwrap_or_default()).to_ascii_lowercase();
+        let tokens = name.contains("token");
+        let requests = name.contains("request");
+        let per_day = name.contains("perday") || name.contains("per_day");
+        let per_minute = name.contains("perminute") || name.contains("per_minute");
+        match (tokens, requests, per_minute, per_day) {
+            (true, _, true, _) => Self::TokensPerMinute,
+            (true, _, _, true) => Self::TokensPerDay,
+            (false, true, true, _) => Self::RequestsPerMinute,
+            (false, true, _, true) => Self::RequestsPerDay,
+            _ => Self::Other,
+        }
+    }
+
+    /// Whether the quota resets daily, so retrying within minutes will not help
+    pub fn is_daily(self) -> bool {
+        matches!(self, Self::RequestsPerDay | Self::TokensPerDay)
+    }
+}
+
+impl QuotaViolation {
+    /// Extract quota violations from an error body
+    ///
+    /// `QuotaFailure` violations are preferred; an `ErrorInfo` carrying `quota_metric`
+    /// metadata is used when there are none.
+    pub fn from_error_body(body: &serde_json::Value) -> Vec<Self> {
+        let Some(details) = body
+            .get("error")
+            .and_then(|e| e.get("details"))
+            .and_then(|d| d.as_array())
+        else {
+            return Vec::new();
+        };
+        let of_type = |suffix: &'static str| {
+            details.iter().filter(move |detail| {
+                detail
+                    .get("@type")
+                    .and_then(|t| t.as_str())
+                    .is_some_and(|t| t.ends_with(suffix))
+            })
+        };
+        let str_field = |value: &serde_json::Value, key: &str| {
+            value.get(key).and_then(|v| v.as_str()).map(str::to_string)
+        };
+        let limit = |value: Option<&serde_json::Value>| {
+            value.and_then(|v| v.as_u64().or_else(|| v.as_str()?.parse().ok()))
+        };
+
+        let violations: Vec<Self> = of_type("google.rpc.QuotaFailure")
+            .filter_map(|failure| failure.get("violations")?.as_array())
+            .flatten()
+            .filter_map(|violation| {
+                let metric = str_field(violation, "quotaMetric")?;
+                let quota_id = str_field(violation, "quotaId");
+                let model = violation
+                    .get("quotaDimensions")
+                    .and_then(|dimensions| str_field(dimensions, "model"));
+                let kind = QuotaKind::classify(&metric, quota_id.as_deref());
+                Some(Self {
+                    limit: limit(violation.get("quotaValue")),
+                    metric,
+                    quota_id,
+                    model,
+                    kind,
+                })
+            })
+            .collect();
+        if !violations.is_empty() {
+            return violations;
+        }
+
+        of_type("google.rpc.ErrorInfo")
+            .filter_map(|info| {
+                let metadata = info.get("metadata")?;
+                let metric = str_field(metadata, "quota_metric")?;
+                let quota_id = str_field(metadata, "quota_limit");
+                let kind = QuotaKind::classify(&metric, quota_id.as_deref());
+                Some(Self {
+                    limit: limit(metadata.get("quota_limit_value")),
+                    model: str_field(metadata, "model"),
+                    metric,
+                    quota_id,
+                    kind,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Canonical error codes reported in an API error body's `error.status`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ApiErrorCode {
+    /// The operation was cancelled
+    Cancelled,
+    /// Unknown server error
+    Unknown,
+    /// The request is malformed or has invalid arguments
+    InvalidArgument,
+    /// The deadline expired before the operation completed
+    DeadlineExceeded,
+    /// A requested resource was not found
+    NotFound,
+    /// The resource already exists
+    AlreadyExists,
+    /// The caller lacks permission (e.g. invalid API key or region)
+    PermissionDenied,
+    /// A quota or rate limit was exhausted
+    ResourceExhausted,
+    /// The system is not in a state required for the operation (e.g. billing not enabled)
+    FailedPrecondition,
+    /// The operation was aborted
+    Aborted,
+    /// A value is out of range
+    OutOfRange,
+    /// The operation is not supported
+    Unimplemented,
+    /// Internal server error
+    Internal,
+    /// The service is temporarily unavailable
+    Unavailable,
+    /// Unrecoverable data loss
+    DataLoss,
+    /// The request lacks valid credentials
+    Unauthenticated,
+    /// A code this library does not know
+    Other(String),
+}
+
+impl ApiErrorCode {
+    /// Parse an `error.status` value such as `INVALID_ARGUMENT`
+    pub fn parse(status: &str) -> Self {
+        match status {
+            "CANCELLED" => Self::Cancelled,
+            "UNKNOWN" => Self::Unknown,
+            "INVALID_ARGUMENT" => Self::InvalidArgument,
+            "DEADLINE_EXCEEDED" => Self::DeadlineExceeded,
+            "NOT_FOUND" => Self::NotFound,
+            "ALREADY_EXISTS" => Self::AlreadyExists,
+            "PERMISSION_DENIED" => Self::PermissionDenied,
+            "RESOURCE_EXHAUSTED" => Self::ResourceExhausted,
+            "FAILED_PRECONDITION" => Self::FailedPrecondition,
+            "ABORTED" => Self::Aborted,
+            "OUT_OF_RANGE" => Self::OutOfRange,
+            "UNIMPLEMENTED" => Self::Unimplemented,
+            "INTERNAL" => Self::Internal,
+            "UNAVAILABLE" => Self::Unavailable,
+            "DATA_LOSS" => Self::DataLoss,
+            "UNAUTHENTICATED" => Self::Unauthenticated,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    /// The code usually paired with an HTTP status, for bodies that carry no `error.status`
+    pub fn from_http_status(status: u16) -> Option<Self> {
+        Some(match status {
+            400 => Self::InvalidArgument,
+            401 => Self::Unauthenticated,
+            403 => Self::PermissionDenied,
+            404 => Self::NotFound,
+            409 => Self::Aborted,
+            429 => Self::ResourceExhausted,
+            499 => Self::Cancelled,
+            500 => Self::Internal,
+            501 => Self::Unimplemented,
+            503 => Self::Unavailable,
+            504 => Self::DeadlineExceeded,
+            _ => return None,
+        })
+    }
+
+    /// The code as it appears in `error.status`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Cancelled => "CANCELLED",
+            Self::Unknown => "UNKNOWN",
+            Self::InvalidArgument => "INVALID_ARGUMENT",
+            Self::DeadlineExceeded => "DEADLINE_EXCEEDED",
+            Self::NotFound => "NOT_FOUND",
+            Self::AlreadyExists => "ALREADY_EXISTS",
+            Self::PermissionDenied => "PERMISSION_DENIED",
+            Self::ResourceExhausted => "RESOURCE_EXHAUSTED",
+            Self::FailedPrecondition => "FAILED_PRECONDITION",
+            Self::Aborted => "ABORTED",
+            Self::OutOfRange => "OUT_OF_RANGE",
+            Self::Unimplemented => "UNIMPLEMENTED",
+            Self::Internal => "INTERNAL",
+            Self::Unavailable => "UNAVAILABLE",
+            Self::DataLoss => "DATA_LOSS",
+            Self::Unauthenticated => "UNAUTHENTICATED",
+            Self::Other(code) => code,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl Error {
+    /// Classify a transport error, reporting timeouts with the configured duration
+    ///
+    /// The request URL is dropped from the error, so query parameters never reach messages.
+    pub(crate) fn from_reqwest(error: reqwest::Error, http: &HttpConfig) -> Self {
+        let error = error.without_url();
+        if error.is_timeout() {
+            Error::Timeout(if error.is_connect() {
+                http.connect_timeout
+            } else {
+                http.timeout
+            })
+        } else if error.is_connect() {
+            Error::Connect(error)
+        } else if error.is_decode() && !Self::body_interrupted(&error) {
+            Error::Decode(error)
+        } else {
+            Error::Http(error)
+        }
+    }
+
+    /// Whether a body stream error wraps a dropped connection rather than a bad payload
+    ///
+    /// reqwest reports both as decode errors on a streamed body.
+    fn body_interrupted(error: &reqwest::Error) -> bool {
+        std::error::Error::source(error)
+            .and_then(|source| source.downcast_ref::<reqwest::Error>())
+            .is_some_and(reqwest::Error::is_body)
+    }
+
+    /// Semantic code of an API error
+    pub fn api_code(&self) -> Option<&ApiErrorCode> {
+        match self {
+            Error::Api { code, .. } => code.as_ref(),
+            Error::Shared(error) => error.api_code(),
+            _ => None,
+        }
+    }
+
     /// Check if the error is retryable
+    ///
+    /// Rate limits are not retryable when every exceeded quota is a daily one. Decode
+    /// failures are not retried, since the same body would fail again. An interrupted stream
+    /// is retryable when the error that ended it is, and a shared error when its cause is.
     pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::StreamInterrupted { source, .. } => return source.is_retryable(),
+            Error::Shared(error) => return error.is_retryable(),
+            _ => {}
+        }
+        if let Error::RateLimit {
+            quota_violations, ..
+        } = self
+        {
+            return quota_violations.is_empty()
+                || !quota_violations.iter().all(|quota| quota.kind.is_daily());
+        }
+
         matches!(
             self,
             Error::Http(_)
-                | Error::RateLimit { .. }
+                | Error::Connect(_)
                 | Error::Timeout(_)
                 | Error::Api {
                     status: 500..=599,
@@ -90,7 +464,8 @@ impl Error {
     /// Get retry delay if applicable
     pub fn retry_delay(&self) -> Option<Duration> {
         match self {
-            Error::RateLimit { retry_after } => *retry_after,
+            Error::Shared(error) => error.retry_delay(),
+            Error::RateLimit { retry_after, .. } => *retry_after,
             Error::Api { status: 429, .. } => Some(Duration::from_secs(60)),
             Error::Api {
                 status: 500..=599, ..
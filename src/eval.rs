@@ -0,0 +1,555 @@
+//! Evaluating prompts and models
+//!
+//! An [`Eval`] runs every [`EvalCase`] through every [`Variant`] — a prompt template, model and
+//! generation settings — concurrently, grades each output with its [`Grader`]s and collects an
+//! [`EvalReport`] with pass rates, scores, latency and token usage per variant.
+
+use crate::{
+    chat::ChatUsage,
+    client::GeminiClient,
+    error::{Error, Result},
+    models::{Content, GenerateContentRequest, GenerationConfig, ResponseSchema, UsageMetadata},
+};
+use futures::{future::BoxFuture, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::debug;
+
+/// Default number of requests in flight
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Default judge score needed to pass a rubric
+const DEFAULT_PASS_SCORE: f64 = 0.7;
+
+/// An input to evaluate, with the expected output if there is one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalCase {
+    /// Name shown in the report
+    pub name: String,
+    /// Text substituted for `{input}` in each variant's template
+    pub input: String,
+    /// Reference output for graders that compare against one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+}
+
+impl EvalCase {
+    /// A case without an expected output
+    pub fn new(name: impl Into<String>, input: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            input: input.into(),
+            expected: None,
+        }
+    }
+
+    /// Compare outputs against `expected`
+    pub fn with_expected(mut self, expected: impl Into<String>) -> Self {
+        self.expected = Some(expected.into());
+        self
+    }
+}
+
+/// A prompt and model setup to evaluate
+#[derive(Debug, Clone)]
+pub struct Variant {
+    /// Name shown in the report
+    pub name: String,
+    /// Model, or the client's default
+    pub model: Option<String>,
+    /// Prompt with `{input}` standing for the case input
+    pub template: String,
+    /// System prompt
+    pub system_prompt: Option<String>,
+    /// Generation settings
+    pub generation_config: Option<GenerationConfig>,
+}
+
+impl Variant {
+    /// Send each case input as is to the client's default model
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            model: None,
+            template: "{input}".to_string(),
+            system_prompt: None,
+            generation_config: None,
+        }
+    }
+
+    /// Use another model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Wrap case inputs in a template containing `{input}`
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    /// Send a system prompt
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Use these generation settings
+    pub fn with_generation_config(mut self, config: GenerationConfig) -> Self {
+        self.generation_config = Some(config);
+        self
+    }
+
+    fn request(&self, case: &EvalCase) -> GenerateContentRequest {
+        let mut request =
+            GenerateContentRequest::new(self.template.replace("{input}", &case.input));
+        request.system_instruction = self.system_prompt.clone().map(Content::system);
+        request.generation_config = self.generation_config.clone();
+        request
+    }
+}
+
+/// A grader's verdict on one output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Grade {
+    /// Score from 0.0 to 1.0
+    pub score: f64,
+    /// Whether the output is acceptable
+    pub passed: bool,
+    /// Why, for failures and judged grades
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Grade {
+    /// A passing grade with full score
+    pub fn pass() -> Self {
+        Self {
+            score: 1.0,
+            passed: true,
+            reason: None,
+        }
+    }
+
+    /// A failing grade with zero score
+    pub fn fail(reason: impl Into<String>) -> Self {
+        Self {
+            score: 0.0,
+            passed: false,
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+/// Grades a variant's output for a case
+pub trait Grader: Send + Sync {
+    /// Name shown in the report
+    fn name(&self) -> &str;
+
+    /// Grade `output`, the text generated for `case`
+    fn grade<'a>(
+        &'a self,
+        client: &'a GeminiClient,
+        case: &'a EvalCase,
+        output: &'a str,
+    ) -> BoxFuture<'a, Result<Grade>>;
+}
+
+/// Passes outputs equal to the case's expected output, ignoring surrounding whitespace
+#[derive(Debug, Clone, Default)]
+pub struct ExactMatch {
+    ignore_case: bool,
+}
+
+impl ExactMatch {
+    /// Compare exactly, apart from surrounding whitespace
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare ignoring case
+    pub fn ignore_case(mut self) -> Self {
+        self.ignore_case = true;
+        self
+    }
+}
+
+impl Grader for ExactMatch {
+    fn name(&self) -> &str {
+        "exact_match"
+    }
+
+    fn grade<'a>(
+        &'a self,
+        _client: &'a GeminiClient,
+        case: &'a EvalCase,
+        output: &'a str,
+    ) -> BoxFuture<'a, Result<Grade>> {
+        Box::pin(async move {
+            let expected = case.expected.as_deref().ok_or_else(|| {
+                Error::Config(format!("Case {} has no expected output", case.name))
+            })?;
+            let (output, expected) = (output.trim(), expected.trim());
+            let matches = if self.ignore_case {
+                output.to_lowercase() == expected.to_lowercase()
+            } else {
+                output == expected
+            };
+            Ok(if matches {
+                Grade::pass()
+            } else {
+                Grade::fail(format!("expected {:?} but got {:?}", expected, output))
+            })
+        })
+    }
+}
+
+/// Passes outputs that are JSON matching a schema
+///
+/// Code fences and trailing commas are tolerated.
+#[derive(Debug, Clone)]
+pub struct JsonSchemaGrader {
+    schema: ResponseSchema,
+}
+
+impl JsonSchemaGrader {
+    /// Check outputs against `schema`
+    pub fn new(schema: ResponseSchema) -> Self {
+        Self { schema }
+    }
+}
+
+impl Grader for JsonSchemaGrader {
+    fn name(&self) -> &str {
+        "json_schema"
+    }
+
+    fn grade<'a>(
+        &'a self,
+        _client: &'a GeminiClient,
+        _case: &'a EvalCase,
+        output: &'a str,
+    ) -> BoxFuture<'a, Result<Grade>> {
+        Box::pin(async move {
+            let value: serde_json::Value = match crate::json::parse(output, true) {
+                Ok(value) => value,
+                Err(e) => return Ok(Grade::fail(e.to_string())),
+            };
+            let violations = self.schema.validate(&value);
+            Ok(if violations.is_empty() {
+                Grade::pass()
+            } else {
+                Grade::fail(violations.join("; "))
+            })
+        })
+    }
+}
+
+/// Has a judge model score outputs against a rubric
+///
+/// The judge answers with a score from 0 to 10, scaled to 0.0–1.0; outputs pass at 0.7 by
+/// default. The case's expected output, if any, is shown to the judge as a reference.
+#[derive(Debug, Clone)]
+pub struct RubricJudge {
+    model: String,
+    rubric: String,
+    pass_score: f64,
+}
+
+#[derive(Deserialize)]
+struct JudgeVerdict {
+    score: f64,
+    reason: String,
+}
+
+impl RubricJudge {
+    /// Judge with `model` against `rubric`
+    pub fn new(model: impl Into<String>, rubric: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            rubric: rubric.into(),
+            pass_score: DEFAULT_PASS_SCORE,
+        }
+    }
+
+    /// Score from 0.0 to 1.0 needed to pass
+    pub fn with_pass_score(mut self, score: f64) -> Self {
+        self.pass_score = score;
+        self
+    }
+}
+
+impl Grader for RubricJudge {
+    fn name(&self) -> &str {
+        "rubric"
+    }
+
+    fn grade<'a>(
+        &'a self,
+        client: &'a GeminiClient,
+        case: &'a EvalCase,
+        output: &'a str,
+    ) -> BoxFuture<'a, Result<Grade>> {
+        Box::pin(async move {
+            let mut prompt = format!(
+                "Grade the answer below against the rubric. Reply with a JSON object \
+                 {{\"score\": <0 to 10>, \"reason\": \"<one sentence>\"}}.\n\nRubric:\n{}\n\n\
+                 Question:\n{}\n\n",
+                self.rubric, case.input
+            );
+            if let Some(expected) = &case.expected {
+                prompt.push_str(&format!("Reference answer:\n{}\n\n", expected));
+            }
+            prompt.push_str(&format!("Answer:\n{}", output));
+
+            let verdict: JudgeVerdict = client
+                .generate_json(Some(&self.model), GenerateContentRequest::new(prompt))
+                .await?;
+            let score = (verdict.score / 10.0).clamp(0.0, 1.0);
+            Ok(Grade {
+                score,
+                passed: score >= self.pass_score,
+                reason: Some(verdict.reason),
+            })
+        })
+    }
+}
+
+/// The outcome of one case for one variant
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaseResult {
+    /// Case name
+    pub case: String,
+    /// Generated text, if the request succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Request error, if it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Grades by grader name; grader errors and failed requests count as failing grades
+    pub grades: Vec<(String, Grade)>,
+    /// Request latency in milliseconds
+    pub latency_ms: u64,
+}
+
+impl CaseResult {
+    /// Whether the request succeeded and every grade passed
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.grades.iter().all(|(_, grade)| grade.passed)
+    }
+}
+
+/// Results of one variant over every case
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariantReport {
+    /// Variant name
+    pub variant: String,
+    /// Results in case order
+    pub results: Vec<CaseResult>,
+    /// Token usage of the variant's requests, not counting judges
+    pub usage: ChatUsage,
+}
+
+impl VariantReport {
+    /// Fraction of cases that passed
+    pub fn pass_rate(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        let passed = self.results.iter().filter(|result| result.passed()).count();
+        passed as f64 / self.results.len() as f64
+    }
+
+    /// Mean score over every grade
+    pub fn mean_score(&self) -> f64 {
+        let scores: Vec<f64> = self
+            .results
+            .iter()
+            .flat_map(|result| &result.grades)
+            .map(|(_, grade)| grade.score)
+            .collect();
+        if scores.is_empty() {
+            0.0
+        } else {
+            scores.iter().sum::<f64>() / scores.len() as f64
+        }
+    }
+
+    /// Mean request latency in milliseconds
+    pub fn mean_latency_ms(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        let total: u64 = self.results.iter().map(|result| result.latency_ms).sum();
+        total as f64 / self.results.len() as f64
+    }
+}
+
+/// Results of an evaluation run
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalReport {
+    /// One report per variant, in the order added
+    pub variants: Vec<VariantReport>,
+}
+
+impl EvalReport {
+    /// The variant with the highest pass rate, the earlier one on ties
+    pub fn best(&self) -> Option<&VariantReport> {
+        self.variants
+            .iter()
+            .rev()
+            .max_by(|a, b| a.pass_rate().total_cmp(&b.pass_rate()))
+    }
+}
+
+/// Runs cases across variants and grades the outputs
+pub struct Eval {
+    client: GeminiClient,
+    cases: Vec<EvalCase>,
+    variants: Vec<Variant>,
+    graders: Vec<Arc<dyn Grader>>,
+    concurrency: usize,
+}
+
+impl Eval {
+    /// An empty evaluation on a client
+    pub fn new(client: GeminiClient) -> Self {
+        Self {
+            client,
+            cases: Vec::new(),
+            variants: Vec::new(),
+            graders: Vec::new(),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Add a case
+    pub fn case(mut self, case: EvalCase) -> Self {
+        self.cases.push(case);
+        self
+    }
+
+    /// Add several cases
+    pub fn cases(mut self, cases: impl IntoIterator<Item = EvalCase>) -> Self {
+        self.cases.extend(cases);
+        self
+    }
+
+    /// Add a variant
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    /// Grade every output with `grader`
+    pub fn grader(mut self, grader: impl Grader + 'static) -> Self {
+        self.graders.push(Arc::new(grader));
+        self
+    }
+
+    /// Number of cases in flight at once (default 4)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Run every case through every variant
+    ///
+    /// Request and grader failures are recorded in the report rather than returned.
+    pub async fn run(&self) -> Result<EvalReport> {
+        if self.variants.is_empty() {
+            return Err(Error::Config("An evaluation needs a variant".to_string()));
+        }
+
+        let jobs = self.variants.iter().enumerate().flat_map(|(v, variant)| {
+            self.cases
+                .iter()
+                .enumerate()
+                .map(move |(c, case)| async move { (v, c, self.run_case(variant, case).await) })
+        });
+        let mut outcomes: Vec<_> = futures::stream::iter(jobs)
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        outcomes.sort_by_key(|(v, c, _)| (*v, *c));
+
+        let mut variants: Vec<VariantReport> = self
+            .variants
+            .iter()
+            .map(|variant| VariantReport {
+                variant: variant.name.clone(),
+                results: Vec::new(),
+                usage: ChatUsage::default(),
+            })
+            .collect();
+        for (v, _, (result, usage)) in outcomes {
+            let report = &mut variants[v];
+            if result.error.is_none() {
+                report.usage.record(usage.as_ref());
+            }
+            report.results.push(result);
+        }
+        for report in &variants {
+            debug!(
+                "Variant {} passed {:.0}% of cases",
+                report.variant,
+                report.pass_rate() * 100.0
+            );
+        }
+        Ok(EvalReport { variants })
+    }
+
+    async fn run_case(
+        &self,
+        variant: &Variant,
+        case: &EvalCase,
+    ) -> (CaseResult, Option<UsageMetadata>) {
+        let started = Instant::now();
+        let response = self
+            .client
+            .generate_content(variant.model.as_deref(), variant.request(case))
+            .await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                let result = CaseResult {
+                    case: case.name.clone(),
+                    output: None,
+                    error: Some(e.to_string()),
+                    grades: self
+                        .graders
+                        .iter()
+                        .map(|grader| (grader.name().to_string(), Grade::fail("request failed")))
+                        .collect(),
+                    latency_ms,
+                };
+                return (result, None);
+            }
+        };
+        let output = response.text().unwrap_or_default();
+        let grades = futures::future::join_all(self.graders.iter().map(|grader| async {
+            let grade = grader
+                .grade(&self.client, case, &output)
+                .await
+                .unwrap_or_else(|e| Grade::fail(e.to_string()));
+            (grader.name().to_string(), grade)
+        }))
+        .await;
+
+        let result = CaseResult {
+            case: case.name.clone(),
+            output: Some(output),
+            error: None,
+            grades,
+            latency_ms,
+        };
+        (result, response.usage_metadata)
+    }
+}
@@ -0,0 +1,212 @@
+//! Structured extraction over document batches
+//!
+//! [`GeminiClient::extract`] uploads each [`SourceDocument`] through the Files API, asks the
+//! model for JSON matching the [`ExtractOptions`] schema and instructions, and deserializes
+//! the answer into `T`. Documents run concurrently up to a bound, answers that fail to parse
+//! are retried, and each document gets its own `Result` so one bad file does not sink the
+//! batch.
+
+use crate::{
+    client::GeminiClient,
+    error::{Error, Result},
+    files::File,
+    models::{Content, GenerateContentRequest, GenerationConfig, Part, ResponseSchema, Role},
+};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Default number of documents processed at once
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Default number of extraction attempts per document
+const DEFAULT_MAX_ATTEMPTS: u32 = 2;
+
+/// A document to extract from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceDocument {
+    /// Name used as the upload's display name and in logs
+    pub name: String,
+    /// MIME type, e.g. `application/pdf`
+    pub mime_type: String,
+    /// File contents
+    pub data: Vec<u8>,
+}
+
+impl SourceDocument {
+    /// A document from bytes
+    pub fn new(name: impl Into<String>, mime_type: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            mime_type: mime_type.into(),
+            data,
+        }
+    }
+
+    /// Read a document from disk, taking the MIME type from the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let mime_type = match extension.as_str() {
+            "pdf" => "application/pdf",
+            "txt" => "text/plain",
+            "md" => "text/markdown",
+            "html" | "htm" => "text/html",
+            "csv" => "text/csv",
+            "xml" => "text/xml",
+            "json" => "application/json",
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "webp" => "image/webp",
+            _ => {
+                return Err(Error::Config(format!(
+                    "Unknown document type for {}",
+                    path.display()
+                )))
+            }
+        };
+        Ok(Self {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            mime_type: mime_type.to_string(),
+            data: std::fs::read(path)?,
+        })
+    }
+}
+
+/// What to extract and how hard to try
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    instructions: String,
+    schema: Option<ResponseSchema>,
+    concurrency: usize,
+    max_attempts: u32,
+    delete_uploads: bool,
+}
+
+impl ExtractOptions {
+    /// Extract according to `instructions`, e.g. "Extract the invoice number, date and total"
+    pub fn new(instructions: impl Into<String>) -> Self {
+        Self {
+            instructions: instructions.into(),
+            schema: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            delete_uploads: true,
+        }
+    }
+
+    /// Constrain the output to `schema`
+    ///
+    /// With the `schemars` feature, [`ResponseSchema::from_type`] derives it from `T`.
+    pub fn schema(mut self, schema: ResponseSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Number of documents processed at once (default 4)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Attempts per document when the answer does not deserialize (default 2)
+    ///
+    /// Transport errors are retried by the client's retry policy, not here.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Delete uploaded documents once their extraction finishes (default true)
+    pub fn delete_uploads(mut self, delete: bool) -> Self {
+        self.delete_uploads = delete;
+        self
+    }
+
+    fn request(&self, file: &File, mime_type: &str) -> Result<GenerateContentRequest> {
+        let uri = file.uri.clone().ok_or_else(|| {
+            Error::InvalidResponse(format!("Uploaded file {} has no URI", file.name))
+        })?;
+        Ok(GenerateContentRequest {
+            generation_config: Some(GenerationConfig {
+                response_mime_type: Some("application/json".to_string()),
+                response_schema: self.schema.clone(),
+                ..Default::default()
+            }),
+            ..GenerateContentRequest::new(Content {
+                role: Role::User,
+                parts: vec![
+                    Part::file(file.mime_type.as_deref().unwrap_or(mime_type), uri),
+                    Part::text(self.instructions.clone()),
+                ],
+            })
+        })
+    }
+}
+
+impl GeminiClient {
+    /// Extract a `T` from each document
+    ///
+    /// Results come back in the order of `documents`. An answer that is not valid JSON for
+    /// `T` is asked for again up to the configured number of attempts.
+    pub async fn extract<T: DeserializeOwned>(
+        &self,
+        model: Option<&str>,
+        documents: Vec<SourceDocument>,
+        options: &ExtractOptions,
+    ) -> Vec<Result<T>> {
+        futures::stream::iter(documents)
+            .map(|document| self.extract_one(model, document, options))
+            .buffered(options.concurrency)
+            .collect()
+            .await
+    }
+
+    async fn extract_one<T: DeserializeOwned>(
+        &self,
+        model: Option<&str>,
+        document: SourceDocument,
+        options: &ExtractOptions,
+    ) -> Result<T> {
+        let SourceDocument {
+            name,
+            mime_type,
+            data,
+        } = document;
+        let file = self.upload_file(data, &mime_type, Some(&name)).await?;
+        let file_name = file.name.clone();
+
+        let result = async {
+            let file = self.wait_until_active(file).await?;
+            let request = options.request(&file, &mime_type)?;
+            let mut attempt = 1;
+            loop {
+                match self.generate_json(model, request.clone()).await {
+                    Err(Error::Json(_) | Error::InvalidResponse(_))
+                        if attempt < options.max_attempts =>
+                    {
+                        debug!("Retrying extraction from {} (attempt {})", name, attempt);
+                        attempt += 1;
+                    }
+                    result => return result,
+                }
+            }
+        }
+        .await;
+
+        if options.delete_uploads {
+            if let Err(e) = self.delete_file(&file_name).await {
+                warn!("Failed to delete uploaded file {}: {}", file_name, e);
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,383 @@
+//! Parts from remote media
+//!
+//! [`Part::from_url`] downloads a public URL, checks its size and MIME type, and returns it as
+//! inline data or, above the inline threshold, as a file uploaded through the Files API.
+//!
+//! Downloads use a plain HTTP client rather than the Gemini one, so the configured TLS
+//! identity and attribution headers never reach third-party hosts. Every redirect hop is
+//! resolved and checked, and hosts resolving to loopback, private, link-local or other
+//! non-public addresses (such as cloud metadata endpoints) are refused.
+
+use crate::{
+    client::GeminiClient,
+    error::{Error, Result},
+    models::{InlineData, Part},
+};
+use base64::Engine;
+use futures::StreamExt;
+use reqwest::{redirect, Response, Url};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tracing::debug;
+
+/// Default largest download
+const DEFAULT_MAX_BYTES: usize = 100 * 1024 * 1024;
+
+/// Default largest download sent inline, leaving room for base64 in a 20 MB request
+const DEFAULT_INLINE_THRESHOLD: usize = 15 * 1024 * 1024;
+
+/// Most redirects followed for one download
+const MAX_REDIRECTS: usize = 10;
+
+/// Limits on fetched media
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchOptions {
+    max_bytes: usize,
+    inline_threshold: usize,
+    allowed_mime_types: Vec<String>,
+    allow_private_hosts: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_BYTES,
+            inline_threshold: DEFAULT_INLINE_THRESHOLD,
+            allowed_mime_types: ["image/", "audio/", "video/", "text/", "application/pdf"]
+                .map(String::from)
+                .to_vec(),
+            allow_private_hosts: false,
+        }
+    }
+}
+
+impl FetchOptions {
+    /// Accept images, audio, video, text and PDFs up to 100 MB
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail downloads larger than `bytes`
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = bytes;
+        self
+    }
+
+    /// Upload media larger than `bytes` through the Files API instead of sending it inline
+    pub fn inline_threshold(mut self, bytes: usize) -> Self {
+        self.inline_threshold = bytes;
+        self
+    }
+
+    /// Accept only these MIME types; entries ending in `/` match a whole family
+    pub fn allowed_mime_types<I, S>(mut self, mime_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_mime_types = mime_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Also fetch from loopback, private and link-local addresses, e.g. an intranet server
+    pub fn allow_private_hosts(mut self, allow: bool) -> Self {
+        self.allow_private_hosts = allow;
+        self
+    }
+
+    fn allows(&self, mime_type: &str) -> bool {
+        self.allowed_mime_types.iter().any(|allowed| {
+            if allowed.ends_with('/') {
+                mime_type.starts_with(allowed.as_str())
+            } else {
+                mime_type == allowed
+            }
+        })
+    }
+}
+
+impl Part {
+    /// Download `url` and turn it into a part
+    ///
+    /// The MIME type comes from the `Content-Type` header, or from the extension of the URL
+    /// finally fetched when the server sends none or a generic one. Only `http` and `https`
+    /// URLs on public addresses are fetched, unless [`FetchOptions::allow_private_hosts`] is
+    /// set. Uploaded files are left for the Files API to expire.
+    pub async fn from_url(
+        client: &GeminiClient,
+        url: &str,
+        options: &FetchOptions,
+    ) -> Result<Self> {
+        let http = &client.config().http_config;
+        let response = fetch(client, url, options).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(Error::InvalidResponse(format!(
+                "Fetching {} failed with status {}",
+                url, status
+            )));
+        }
+        if response
+            .content_length()
+            .is_some_and(|len| len > options.max_bytes as u64)
+        {
+            return Err(too_large(url, options));
+        }
+
+        let header_mime = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase())
+            .filter(|value| !value.is_empty() && value != "application/octet-stream");
+        let mime_type = header_mime
+            .or_else(|| mime_type_from_url(response.url().as_str()).map(str::to_string))
+            .ok_or_else(|| Error::Config(format!("Cannot tell the media type of {}", url)))?;
+        if !options.allows(&mime_type) {
+            return Err(Error::Config(format!(
+                "{} has media type {}, which is not allowed",
+                url, mime_type
+            )));
+        }
+
+        let mut data = Vec::new();
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(|e| Error::from_reqwest(e, http))?;
+            if data.len() + chunk.len() > options.max_bytes {
+                return Err(too_large(url, options));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        debug!("Fetched {} bytes of {} from {}", data.len(), mime_type, url);
+
+        if data.len() <= options.inline_threshold {
+            return Ok(Part::InlineData {
+                inline_data: InlineData {
+                    mime_type,
+                    data: base64::engine::general_purpose::STANDARD.encode(&data),
+                },
+                thought_signature: None,
+            });
+        }
+        let file = client.upload_file(data, &mime_type, None).await?;
+        let file = client.wait_until_active(file).await?;
+        let uri = file.uri.ok_or_else(|| {
+            Error::InvalidResponse(format!("Uploaded file {} has no URI", file.name))
+        })?;
+        Ok(Part::file(mime_type, uri))
+    }
+}
+
+/// GET `url`, following redirects only to hosts that pass [`check_host`]
+///
+/// Each hop gets a client pinned to the addresses that were checked, so a second DNS answer
+/// cannot swap in another host. Proxies are bypassed, since a proxy would resolve the host
+/// itself.
+async fn fetch(client: &GeminiClient, url: &str, options: &FetchOptions) -> Result<Response> {
+    let http = &client.config().http_config;
+    let mut current = parse_http_url(url)?;
+    for _ in 0..=MAX_REDIRECTS {
+        let host = current
+            .host_str()
+            .ok_or_else(|| Error::Config(format!("Cannot fetch {}: no host", current)))?
+            .to_string();
+        let addrs = check_host(&current, options).await?;
+        let fetcher = reqwest::Client::builder()
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .timeout(http.timeout)
+            .connect_timeout(http.connect_timeout)
+            .resolve_to_addrs(&host, &addrs)
+            .build()
+            .map_err(Error::Http)?;
+        let response = fetcher
+            .get(current.clone())
+            .send()
+            .await
+            .map_err(|e| Error::from_reqwest(e, http))?;
+        if !response.status().is_redirection() {
+            return Ok(response);
+        }
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| {
+                Error::InvalidResponse(format!("Redirect from {} has no location", current))
+            })?;
+        let next = current.join(location).map_err(|e| {
+            Error::InvalidResponse(format!("Invalid redirect from {}: {}", current, e))
+        })?;
+        debug!("Following redirect from {} to {}", current, next);
+        current = parse_http_url(next.as_str())?;
+    }
+    Err(Error::InvalidResponse(format!(
+        "Fetching {} took more than {} redirects",
+        url, MAX_REDIRECTS
+    )))
+}
+
+fn parse_http_url(url: &str) -> Result<Url> {
+    match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(parsed),
+        _ => Err(Error::Config(format!(
+            "Cannot fetch {}: not an HTTP URL",
+            url
+        ))),
+    }
+}
+
+/// Resolve the URL's host, refusing it if any address is not public
+async fn check_host(url: &Url, options: &FetchOptions) -> Result<Vec<SocketAddr>> {
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(443);
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = match literal.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| Error::Config(format!("Cannot resolve {}: {}", host, e)))?
+            .collect(),
+    };
+    if addrs.is_empty() {
+        return Err(Error::Config(format!("Cannot resolve {}", host)));
+    }
+    if !options.allow_private_hosts {
+        if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+            return Err(Error::Config(format!(
+                "Refusing to fetch {}: {} is not a public address",
+                url,
+                addr.ip()
+            )));
+        }
+    }
+    Ok(addrs)
+}
+
+/// Whether an address is routable on the public internet
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_v4(mapped),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Shared address space (carrier-grade NAT)
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments
+        || (a == 192 && b == 0 && ip.octets()[2] == 0)
+        // Benchmarking
+        || (a == 198 && (18..20).contains(&b))
+        // Reserved
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local
+        || (first & 0xfe00) == 0xfc00
+        // Link-local
+        || (first & 0xffc0) == 0xfe80
+        // Documentation
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8)
+        // NAT64, which reaches IPv4 addresses
+        || (first == 0x0064 && ip.segments()[1] == 0xff9b)
+        // Teredo and 6to4, which embed IPv4 addresses
+        || (first == 0x2001 && ip.segments()[1] == 0x0000)
+        || first == 0x2002)
+}
+
+fn too_large(url: &str, options: &FetchOptions) -> Error {
+    Error::Config(format!(
+        "{} is larger than the {} byte limit",
+        url, options.max_bytes
+    ))
+}
+
+fn mime_type_from_url(url: &str) -> Option<&'static str> {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    let mime_type = match path.rsplit_once('.')?.1 {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "heic" => "image/heic",
+        "pdf" => "application/pdf",
+        "mp3" => "audio/mp3",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "flac" => "audio/flac",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        _ => return None,
+    };
+    Some(mime_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_public_addresses_pass() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "2002:7f00:1::1",
+            "2002:a9fe:a9fe::1",
+            "2001:0:4136:e378:8000:63bf:3fff:fdd2",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["8.8.8.8", "142.250.1.1", "2a00:1450:4001::1"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn metadata_and_literal_hosts_are_refused() {
+        let options = FetchOptions::new();
+        for url in [
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]:8080/",
+            "https://127.0.0.1/image.png",
+        ] {
+            let url = parse_http_url(url).unwrap();
+            assert!(check_host(&url, &options).await.is_err(), "{}", url);
+        }
+        assert!(parse_http_url("file:///etc/passwd").is_err());
+
+        let url = parse_http_url("http://127.0.0.1:9/").unwrap();
+        let allowed = options.allow_private_hosts(true);
+        assert_eq!(check_host(&url, &allowed).await.unwrap().len(), 1);
+    }
+}
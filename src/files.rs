@@ -0,0 +1,521 @@
+//! Files API uploads and downloads, and automatic offloading of oversized inline data
+
+use crate::{
+    client::{GeminiClient, API_KEY_HEADER},
+    config::OffloadCleanup,
+    error::{Error, Result},
+    models::{GenerateContentRequest, Part},
+};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+/// How often to check on a file the API is still processing
+const PROCESSING_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest wait for an uploaded file to become usable
+const PROCESSING_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A file uploaded through the Files API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct File {
+    /// Resource name (e.g. "files/abc-123")
+    pub name: String,
+
+    /// Display name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    /// MIME type of the content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+
+    /// Size in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<String>,
+
+    /// URI to reference the file from requests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+
+    /// Processing state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<FileState>,
+
+    /// Creation time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<DateTime<Utc>>,
+
+    /// When the file is deleted automatically
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_time: Option<DateTime<Utc>>,
+}
+
+/// Processing state of an uploaded file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FileState {
+    /// State not reported
+    StateUnspecified,
+    /// Still being processed; not yet usable in requests
+    Processing,
+    /// Ready for use
+    Active,
+    /// Processing failed
+    Failed,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    file: File,
+}
+
+impl GeminiClient {
+    /// Upload bytes through the Files API's resumable upload protocol
+    pub async fn upload_file(
+        &self,
+        bytes: Vec<u8>,
+        mime_type: &str,
+        display_name: Option<&str>,
+    ) -> Result<File> {
+        self.upload_file_to(&self.config().base_url, bytes, mime_type, display_name)
+            .await
+    }
+
+    async fn upload_file_to(
+        &self,
+        base_url: &str,
+        bytes: Vec<u8>,
+        mime_type: &str,
+        display_name: Option<&str>,
+    ) -> Result<File> {
+        let metadata = match display_name {
+            Some(display_name) => serde_json::json!({ "file": { "displayName": display_name } }),
+            None => serde_json::json!({ "file": {} }),
+        };
+        let start_url = format!(
+            "{}/upload/{}/files",
+            base_url,
+            self.config().api_version.as_str()
+        );
+
+        let response = self
+            .send_with_retry(
+                |client| {
+                    client
+                        .http_client()
+                        .post(&start_url)
+                        .header(API_KEY_HEADER, client.config().api_key.expose())
+                        .header("X-Goog-Upload-Protocol", "resumable")
+                        .header("X-Goog-Upload-Command", "start")
+                        .header("X-Goog-Upload-Header-Content-Length", bytes.len())
+                        .header("X-Goog-Upload-Header-Content-Type", mime_type)
+                        .json(&metadata)
+                },
+                self.config().retry_config.max_attempts,
+            )
+            .await?;
+        let upload_url = response
+            .headers()
+            .get("x-goog-upload-url")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                Error::InvalidResponse("Upload start response has no upload URL".to_string())
+            })?;
+
+        debug!("Uploading {} bytes of {}", bytes.len(), mime_type);
+        let response = self
+            .send_with_retry(
+                |client| {
+                    client
+                        .http_client()
+                        .post(&upload_url)
+                        .header("X-Goog-Upload-Offset", "0")
+                        .header("X-Goog-Upload-Command", "upload, finalize")
+                        .body(bytes.clone())
+                },
+                1,
+            )
+            .await?;
+        let uploaded: UploadResponse = response
+            .json()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &self.config().http_config))?;
+
+        info!("Uploaded file: {}", uploaded.file.name);
+        Ok(uploaded.file)
+    }
+
+    /// Poll an uploaded file until the API has finished processing it
+    pub(crate) async fn wait_until_active(&self, mut file: File) -> Result<File> {
+        let started = tokio::time::Instant::now();
+        loop {
+            match file.state {
+                Some(FileState::Processing) => {}
+                Some(FileState::Failed) => {
+                    return Err(Error::InvalidResponse(format!(
+                        "Processing of file {} failed",
+                        file.name
+                    )))
+                }
+                _ => return Ok(file),
+            }
+            if started.elapsed() >= PROCESSING_TIMEOUT {
+                return Err(Error::Timeout(PROCESSING_TIMEOUT));
+            }
+            tokio::time::sleep(PROCESSING_POLL_INTERVAL).await;
+            file = self.get_file(&file.name).await?;
+        }
+    }
+
+    /// Get an uploaded file by resource name
+    pub async fn get_file(&self, name: &str) -> Result<File> {
+        let url = self.files_url(name);
+        self.execute_with_retry(|client| {
+            client
+                .http_client()
+                .get(&url)
+                .header(API_KEY_HEADER, client.config().api_key.expose())
+        })
+        .await
+    }
+
+    /// Delete an uploaded file
+    pub async fn delete_file(&self, name: &str) -> Result<()> {
+        self.delete_file_at(&self.config().base_url, name).await
+    }
+
+    async fn delete_file_at(&self, base_url: &str, name: &str) -> Result<()> {
+        let url = self.files_url_on(base_url, name);
+        self.execute_with_retry::<serde_json::Value, _>(|client| {
+            client
+                .http_client()
+                .delete(&url)
+                .header(API_KEY_HEADER, client.config().api_key.expose())
+        })
+        .await?;
+        info!("Deleted file: {}", name);
+        Ok(())
+    }
+
+    /// Stream the contents of a file, e.g. a generated video
+    ///
+    /// `name` is a resource name such as `files/abc-123` or a download URI returned by the
+    /// API. A connection that drops mid-body is reopened with a `Range` request from the last
+    /// byte received, up to the retry limit.
+    pub fn download_file(&self, name: &str) -> Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>> {
+        self.download_file_from(name, 0)
+    }
+
+    /// Like [`download_file`](Self::download_file), starting `offset` bytes into the file
+    pub fn download_file_from(
+        &self,
+        name: &str,
+        offset: u64,
+    ) -> Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>> {
+        let (url, with_key) = match self.download_url(name) {
+            Ok(target) => target,
+            Err(e) => return Box::pin(futures::stream::once(async { Err(e) })),
+        };
+        let state = Download {
+            client: self.clone(),
+            url,
+            with_key,
+            offset,
+            body: None,
+            skip: 0,
+            interruptions: 0,
+            done: false,
+        };
+        Box::pin(futures::stream::unfold(state, Download::next))
+    }
+
+    /// Download a file to `path`, continuing a partial download already there
+    ///
+    /// Returns the size of the finished file. A file already complete on disk is left as is.
+    pub async fn download_file_to(&self, name: &str, path: impl AsRef<Path>) -> Result<u64> {
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        let mut size = file.metadata().await?.len();
+        if size > 0 {
+            debug!("Resuming download of {} at byte {}", name, size);
+        }
+        let mut chunks = self.download_file_from(name, size);
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            file.write_all(&chunk).await?;
+            size += chunk.len() as u64;
+        }
+        file.flush().await?;
+        Ok(size)
+    }
+
+    /// The URL to download `name` from, and whether the API key may be sent to it
+    ///
+    /// Download URIs must use HTTPS, and only those on a configured API host (the base URL
+    /// or a model endpoint) get the key.
+    fn download_url(&self, name: &str) -> Result<(String, bool)> {
+        if name.starts_with("http://") {
+            return Err(Error::Config(format!(
+                "Refusing to download {} over plain HTTP",
+                name
+            )));
+        }
+        if !name.starts_with("https://") {
+            return Ok((format!("{}:download?alt=media", self.files_url(name)), true));
+        }
+        let url = reqwest::Url::parse(name)
+            .map_err(|e| Error::Config(format!("Invalid download URI {}: {}", name, e)))?;
+        let config = self.config();
+        let same_origin = std::iter::once(&config.base_url)
+            .chain(config.model_endpoints.values())
+            .filter_map(|base| reqwest::Url::parse(base).ok())
+            .any(|base| base.origin() == url.origin());
+        Ok((name.to_string(), same_origin))
+    }
+
+    /// Request the file from `offset`, returning the body and how many leading bytes to drop
+    ///
+    /// Servers that ignore the `Range` header send the whole file, so the bytes before
+    /// `offset` have to be skipped. A `416` reply to a resumed request means nothing is left
+    /// to send, and gives an empty body. URLs off the API host are fetched with a plain
+    /// client, so they see neither the TLS client identity nor the attribution headers.
+    async fn open_download(
+        &self,
+        url: &str,
+        with_key: bool,
+        offset: u64,
+    ) -> Result<(DownloadBody, u64)> {
+        let foreign = if with_key {
+            None
+        } else {
+            Some(self.foreign_http_client()?)
+        };
+        let response = self
+            .send_with_retry(
+                |client| {
+                    let mut request = match &foreign {
+                        Some(foreign) => foreign.get(url),
+                        None => client.http_client().get(url),
+                    };
+                    if with_key {
+                        request = request.header(API_KEY_HEADER, client.config().api_key.expose());
+                    }
+                    if offset > 0 {
+                        request.header(reqwest::header::RANGE, format!("bytes={}-", offset))
+                    } else {
+                        request
+                    }
+                },
+                self.config().retry_config.max_attempts,
+            )
+            .await;
+        let response = match response {
+            Err(Error::Api { status: 416, .. }) if offset > 0 => {
+                debug!("Download of {} already complete at byte {}", url, offset);
+                return Ok((futures::stream::empty().boxed(), 0));
+            }
+            response => response?,
+        };
+        let skip = if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            0
+        } else {
+            offset
+        };
+        let body = response
+            .bytes_stream()
+            .map(|chunk| chunk.map(|bytes| bytes.to_vec()))
+            .boxed();
+        Ok((body, skip))
+    }
+
+    fn files_url(&self, name: &str) -> String {
+        self.files_url_on(&self.config().base_url, name)
+    }
+
+    fn files_url_on(&self, base_url: &str, name: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            base_url,
+            self.config().api_version.as_str(),
+            name
+        )
+    }
+
+    /// Upload inline data parts while the serialized request exceeds the offload threshold
+    ///
+    /// The largest parts go first, uploaded to the endpoint serving `model`. Returns the
+    /// uploaded files' resource names.
+    pub(crate) async fn offload_inline_data(
+        &self,
+        model: &str,
+        request: &mut GenerateContentRequest,
+    ) -> Result<Vec<String>> {
+        let threshold = self.config().inline_offload.threshold_bytes;
+        let mut size = serde_json::to_vec(request)?.len();
+        if size <= threshold {
+            return Ok(Vec::new());
+        }
+
+        let mut parts: Vec<(usize, usize, usize)> = request
+            .contents
+            .iter()
+            .enumerate()
+            .flat_map(|(content, c)| {
+                c.parts
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(part, p)| match p {
+                        Part::InlineData { inline_data, .. } => {
+                            Some((inline_data.data.len(), content, part))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect();
+        parts.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut uploaded = Vec::new();
+        let offloaded: Result<()> = async {
+            for (encoded_len, content, part) in parts {
+                if size <= threshold {
+                    break;
+                }
+                let slot = &mut request.contents[content].parts[part];
+                let Part::InlineData { inline_data, .. } = slot else {
+                    continue;
+                };
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(&inline_data.data)
+                    .map_err(|e| {
+                        Error::Config(format!("Inline data is not valid base64: {}", e))
+                    })?;
+                let mime_type = inline_data.mime_type.clone();
+
+                let base_url = self.config().base_url_for(model);
+                let file = self
+                    .upload_file_to(base_url, bytes, &mime_type, None)
+                    .await?;
+                uploaded.push(file.name.clone());
+                let uri = file.uri.ok_or_else(|| {
+                    Error::InvalidResponse(format!("Uploaded file {} has no URI", file.name))
+                })?;
+                *slot = Part::file(mime_type, uri);
+                size = size.saturating_sub(encoded_len);
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = offloaded {
+            // The request will not be sent, so nothing refers to the files uploaded so far
+            self.delete_offloaded(model, uploaded).await;
+            return Err(e);
+        }
+
+        if !uploaded.is_empty() {
+            debug!("Offloaded {} inline parts to the Files API", uploaded.len());
+        }
+        Ok(uploaded)
+    }
+
+    /// Delete files offloaded for `model` if the cleanup policy asks for it
+    pub(crate) async fn cleanup_offloaded(&self, model: &str, files: Vec<String>) {
+        if self.config().inline_offload.cleanup == OffloadCleanup::AfterRequest {
+            self.delete_offloaded(model, files).await;
+        }
+    }
+
+    /// Delete files offloaded for `model`, logging failures
+    async fn delete_offloaded(&self, model: &str, files: Vec<String>) {
+        let base_url = self.config().base_url_for(model);
+        for name in files {
+            if let Err(e) = self.delete_file_at(base_url, &name).await {
+                warn!("Failed to delete offloaded file {}: {}", name, e);
+            }
+        }
+    }
+}
+
+type DownloadBody = BoxStream<'static, reqwest::Result<Vec<u8>>>;
+
+/// State of a resumable download stream
+struct Download {
+    client: GeminiClient,
+    url: String,
+    /// Whether the URL is on the API host and gets the API key
+    with_key: bool,
+    /// Bytes of the file delivered so far, counting any starting offset
+    offset: u64,
+    body: Option<DownloadBody>,
+    /// Bytes still to drop from the start of a body that ignored the range
+    skip: u64,
+    interruptions: u32,
+    done: bool,
+}
+
+impl Download {
+    async fn next(mut self) -> Option<(Result<Vec<u8>>, Self)> {
+        loop {
+            if self.done {
+                return None;
+            }
+            let body = match &mut self.body {
+                Some(body) => body,
+                None => match self
+                    .client
+                    .open_download(&self.url, self.with_key, self.offset)
+                    .await
+                {
+                    Ok((body, skip)) => {
+                        self.skip = skip;
+                        self.body.insert(body)
+                    }
+                    Err(e) => {
+                        self.done = true;
+                        return Some((Err(e), self));
+                    }
+                },
+            };
+            match body.next().await {
+                None => return None,
+                Some(Ok(mut chunk)) => {
+                    if self.skip > 0 {
+                        let dropped = (self.skip as usize).min(chunk.len());
+                        chunk.drain(..dropped);
+                        self.skip -= dropped as u64;
+                        if chunk.is_empty() {
+                            continue;
+                        }
+                    }
+                    self.offset += chunk.len() as u64;
+                    return Some((Ok(chunk), self));
+                }
+                Some(Err(e)) => {
+                    self.body = None;
+                    self.interruptions += 1;
+                    let config = self.client.config();
+                    if self.interruptions >= config.retry_config.max_attempts {
+                        self.done = true;
+                        let error = Error::from_reqwest(e, &config.http_config);
+                        return Some((Err(error), self));
+                    }
+                    warn!(
+                        "Download interrupted at byte {}, resuming: {}",
+                        self.offset, e
+                    );
+                }
+            }
+        }
+    }
+}
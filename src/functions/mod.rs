@@ -1,31 +1,14 @@
 //! Function calling support for Gemini API
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+pub use crate::models::{CodeExecutionConfig, Tool, ToolConfig};
 
-/// Tool configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum Tool {
-    /// Function declarations
-    FunctionDeclarations {
-        /// List of function declarations available to the model
-        #[serde(rename = "functionDeclarations")]
-        function_declarations: Vec<FunctionDeclaration>,
-    },
-    /// Google Search tool
-    #[cfg(feature = "grounding")]
-    GoogleSearch(crate::grounding::SearchGrounding),
-    /// URL Context tool
-    #[cfg(feature = "grounding")]
-    UrlContext(crate::grounding::UrlContext),
-    /// Code execution tool
-    CodeExecution {
-        /// Configuration for code execution
-        #[serde(rename = "codeExecution")]
-        code_execution: CodeExecutionConfig,
-    },
-}
+use crate::error::{Error, Result};
+use futures::future::BoxFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::debug;
 
 /// Function declaration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +21,32 @@ pub struct FunctionDeclaration {
 
     /// Parameters schema (OpenAPI format)
     pub parameters: ParameterSchema,
+
+    /// Whether the model waits for the response (Live API only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub behavior: Option<FunctionBehavior>,
+}
+
+/// How the model treats a pending function call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FunctionBehavior {
+    /// The model waits for the response before continuing (default)
+    Blocking,
+    /// The model keeps interacting with the user while the function runs
+    NonBlocking,
+}
+
+/// When a non-blocking function's response is delivered to the conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FunctionResponseScheduling {
+    /// Add the result to the context without prompting a new model turn
+    Silent,
+    /// Wait for the model to finish its current output, then respond
+    WhenIdle,
+    /// Interrupt the model's current output and respond immediately
+    Interrupt,
 }
 
 /// Parameter schema for functions
@@ -47,7 +56,8 @@ pub struct ParameterSchema {
     #[serde(rename = "type")]
     pub schema_type: String, // Usually "object"
 
-    /// Properties definition
+    /// Properties definition, serialized in name order
+    #[serde(serialize_with = "serialize_sorted")]
     pub properties: HashMap<String, PropertySchema>,
 
     /// Required parameter names
@@ -55,6 +65,129 @@ pub struct ParameterSchema {
     pub required: Option<Vec<String>>,
 }
 
+impl ParameterSchema {
+    /// Check arguments against this schema, returning every violation found
+    pub fn validate(&self, args: &HashMap<String, serde_json::Value>) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        for name in self.required.iter().flatten() {
+            if !args.contains_key(name) {
+                violations.push(format!("missing required argument `{}`", name));
+            }
+        }
+
+        for (name, value) in args {
+            let required = self.required.iter().flatten().any(|r| r == name);
+            match self.properties.get(name) {
+                Some(property) => property.validate_value(name, value, required, &mut violations),
+                None => violations.push(format!("unexpected argument `{}`", name)),
+            }
+        }
+
+        violations.sort();
+        violations
+    }
+}
+
+impl PropertySchema {
+    /// Check a value; `null` stands for an absent value, so it is only rejected where a value
+    /// is required and the schema is not nullable
+    fn validate_value(
+        &self,
+        path: &str,
+        value: &serde_json::Value,
+        required: bool,
+        violations: &mut Vec<String>,
+    ) {
+        use serde_json::Value;
+
+        if value.is_null() {
+            if required && self.nullable != Some(true) {
+                violations.push(format!(
+                    "argument `{}` is required and cannot be null",
+                    path
+                ));
+            }
+            return;
+        }
+
+        let type_matches = match (self.property_type.as_str(), value) {
+            ("string", Value::String(_)) => true,
+            ("integer", Value::Number(n)) => {
+                n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            ("number", Value::Number(_)) => true,
+            ("boolean", Value::Bool(_)) => true,
+            ("array", Value::Array(_)) => true,
+            ("object", Value::Object(_)) => true,
+            _ => false,
+        };
+        if !type_matches {
+            violations.push(format!(
+                "argument `{}` should be of type {} but was {}",
+                path, self.property_type, value
+            ));
+            return;
+        }
+
+        if let (Some(allowed), Value::String(s)) = (&self.enum_values, value) {
+            if !allowed.contains(s) {
+                violations.push(format!(
+                    "argument `{}` must be one of {:?} but was {:?}",
+                    path, allowed, s
+                ));
+            }
+        }
+
+        match value {
+            Value::Array(items) => {
+                if let Some(schema) = &self.items {
+                    for (i, item) in items.iter().enumerate() {
+                        schema.validate_value(&format!("{}[{}]", path, i), item, true, violations);
+                    }
+                }
+            }
+            Value::Object(fields) => {
+                for name in self.required.iter().flatten() {
+                    if !fields.contains_key(name) {
+                        violations.push(format!("missing required argument `{}.{}`", path, name));
+                    }
+                }
+                if let Some(properties) = &self.properties {
+                    for (name, field) in fields {
+                        if let Some(schema) = properties.get(name) {
+                            let required = self.required.iter().flatten().any(|r| r == name);
+                            schema.validate_value(
+                                &format!("{}.{}", path, name),
+                                field,
+                                required,
+                                violations,
+                            );
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl FunctionDeclaration {
+    /// Check a function call against this declaration's parameter schema
+    pub fn validate_call(&self, call: &FunctionCall) -> Result<()> {
+        let violations = self.parameters.validate(&call.args);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::FunctionCall(format!(
+                "Invalid call to {}: {}",
+                self.name,
+                violations.join("; ")
+            )))
+        }
+    }
+}
+
 /// Individual property schema
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertySchema {
@@ -66,22 +199,105 @@ pub struct PropertySchema {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 
-    /// Allowed enum values
+    /// Whether `null` is accepted for the property even when it is required
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
+
+    /// Allowed enum values
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
     pub enum_values: Option<Vec<String>>,
 
     /// Schema for array items
     #[serde(skip_serializing_if = "Option::is_none")]
     pub items: Option<Box<PropertySchema>>,
+
+    /// Properties of nested object types, serialized in name order
+    #[serde(
+        serialize_with = "serialize_sorted_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub properties: Option<HashMap<String, PropertySchema>>,
+
+    /// Required property names of nested object types
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Vec<String>>,
+}
+
+impl From<crate::models::ResponseSchema> for PropertySchema {
+    fn from(schema: crate::models::ResponseSchema) -> Self {
+        Self {
+            property_type: schema.schema_type.as_str().to_string(),
+            description: schema.description,
+            nullable: schema.nullable,
+            enum_values: schema.enum_values,
+            items: schema.items.map(|items| Box::new((*items).into())),
+            properties: schema.properties.map(|properties| {
+                properties
+                    .into_iter()
+                    .map(|(name, schema)| (name, schema.into()))
+                    .collect()
+            }),
+            required: schema.required,
+        }
+    }
 }
 
 /// Function call from the model
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct FunctionCall {
     /// Name of the function to call
+    #[serde(default)]
     pub name: String,
     /// Arguments to pass to the function
+    #[serde(default)]
     pub args: HashMap<String, serde_json::Value>,
+    /// Arguments streamed incrementally when argument streaming is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_args: Option<Vec<PartialArg>>,
+    /// Whether more partial arguments for this call follow in later chunks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub will_continue: Option<bool>,
+}
+
+/// A fragment of a streamed function call argument
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialArg {
+    /// JSON path of the argument (e.g. `$.location` or `$.stops[0].city`)
+    pub json_path: String,
+    /// String value, or a piece of it when `will_continue` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub string_value: Option<String>,
+    /// Numeric value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_value: Option<f64>,
+    /// Boolean value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bool_value: Option<bool>,
+    /// Present when the value is null
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub null_value: Option<serde_json::Value>,
+    /// Whether the string value continues in the next fragment for this path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub will_continue: Option<bool>,
+}
+
+impl PartialArg {
+    /// The fragment's value as JSON
+    pub fn value(&self) -> serde_json::Value {
+        if let Some(text) = &self.string_value {
+            serde_json::Value::String(text.clone())
+        } else if let Some(number) = self.number_value {
+            serde_json::Number::from_f64(number)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null)
+        } else if let Some(flag) = self.bool_value {
+            serde_json::Value::Bool(flag)
+        } else {
+            serde_json::Value::Null
+        }
+    }
 }
 
 /// Function response to send back to the model
@@ -91,19 +307,58 @@ pub struct FunctionResponse {
     pub name: String,
     /// Response data from the function
     pub response: serde_json::Value,
+    /// Scheduling of the response for non-blocking functions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduling: Option<FunctionResponseScheduling>,
 }
 
-/// Code execution configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct CodeExecutionConfig {}
+impl FunctionCall {
+    /// Deserialize the call's arguments into a typed struct
+    ///
+    /// Missing and mistyped fields are reported with the function name; use
+    /// `#[serde(deny_unknown_fields)]` on `T` to also reject unexpected arguments.
+    pub fn args_as<T: DeserializeOwned>(&self) -> Result<T> {
+        let args = serde_json::Value::Object(
+            self.args
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        );
 
-/// Tool configuration for controlling function calling behavior
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ToolConfig {
-    /// Function calling configuration
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub function_calling_config: Option<FunctionCallingConfig>,
+        serde_json::from_value(args)
+            .map_err(|e| Error::FunctionCall(format!("Invalid arguments for {}: {}", self.name, e)))
+    }
+}
+
+impl FunctionResponse {
+    /// Create a response from any serializable value
+    ///
+    /// The API requires the response to be a JSON object, so other values are wrapped as
+    /// `{"result": value}`.
+    pub fn from_serialize<T: Serialize + ?Sized>(
+        name: impl Into<String>,
+        value: &T,
+    ) -> Result<Self> {
+        Ok(Self {
+            name: name.into(),
+            response: response_object(serde_json::to_value(value)?),
+            scheduling: None,
+        })
+    }
+
+    /// Set how a non-blocking function's response is scheduled
+    pub fn with_scheduling(mut self, scheduling: FunctionResponseScheduling) -> Self {
+        self.scheduling = Some(scheduling);
+        self
+    }
+}
+
+/// Wrap non-object values so they form a valid function response payload
+fn response_object(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(_) => value,
+        other => serde_json::json!({ "result": other }),
+    }
 }
 
 /// Function calling configuration
@@ -116,6 +371,10 @@ pub struct FunctionCallingConfig {
     /// Allowed function names (for restricting which functions can be called)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allowed_function_names: Option<Vec<String>>,
+
+    /// Stream function call arguments incrementally as partial args
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_function_call_arguments: Option<bool>,
 }
 
 /// Function calling mode
@@ -136,6 +395,7 @@ pub struct FunctionBuilder {
     description: String,
     parameters: HashMap<String, PropertySchema>,
     required: Vec<String>,
+    behavior: Option<FunctionBehavior>,
 }
 
 impl FunctionBuilder {
@@ -146,6 +406,7 @@ impl FunctionBuilder {
             description: String::new(),
             parameters: HashMap::new(),
             required: Vec::new(),
+            behavior: None,
         }
     }
 
@@ -155,6 +416,12 @@ impl FunctionBuilder {
         self
     }
 
+    /// Set whether the model waits for the function's response
+    pub fn behavior(mut self, behavior: FunctionBehavior) -> Self {
+        self.behavior = Some(behavior);
+        self
+    }
+
     /// Add a parameter to the function
     pub fn param(
         mut self,
@@ -170,8 +437,11 @@ impl FunctionBuilder {
             PropertySchema {
                 property_type: param_type.into(),
                 description: Some(description.into()),
+                nullable: None,
                 enum_values: None,
                 items: None,
+                properties: None,
+                required: None,
             },
         );
 
@@ -182,6 +452,52 @@ impl FunctionBuilder {
         self
     }
 
+    /// Add a parameter with a fully specified property schema
+    pub fn property(
+        mut self,
+        name: impl Into<String>,
+        schema: PropertySchema,
+        required: bool,
+    ) -> Self {
+        let name = name.into();
+        self.parameters.insert(name.clone(), schema);
+
+        if required {
+            self.required.push(name);
+        }
+
+        self
+    }
+
+    /// Add parameters from the fields of a Rust type implementing `schemars::JsonSchema`
+    ///
+    /// Keeps the declared parameters in lockstep with the struct used to deserialize them
+    /// via [`FunctionCall::args_as`].
+    #[cfg(feature = "schemars")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
+    pub fn params_from<T: schemars::JsonSchema>(mut self) -> Result<Self> {
+        let schema = crate::models::ResponseSchema::from_type::<T>()?;
+        let mut properties = match schema.properties {
+            Some(properties) if matches!(schema.schema_type, crate::models::SchemaType::Object) => {
+                properties
+            }
+            _ => {
+                return Err(Error::SchemaValidation(
+                    "function parameters must be derived from a struct".to_string(),
+                ))
+            }
+        };
+
+        for name in schema.property_ordering.unwrap_or_default() {
+            if let Some(property) = properties.remove(&name) {
+                self.parameters.insert(name, property.into());
+            }
+        }
+        self.required.extend(schema.required.unwrap_or_default());
+
+        Ok(self)
+    }
+
     /// Add an enum parameter to the function
     pub fn enum_param(
         mut self,
@@ -197,8 +513,11 @@ impl FunctionBuilder {
             PropertySchema {
                 property_type: "string".to_string(),
                 description: Some(description.into()),
+                nullable: None,
                 enum_values: Some(values),
                 items: None,
+                properties: None,
+                required: None,
             },
         );
 
@@ -223,6 +542,7 @@ impl FunctionBuilder {
                     Some(self.required)
                 },
             },
+            behavior: self.behavior,
         }
     }
 }
@@ -235,25 +555,6 @@ impl Tool {
             function_declarations: declarations,
         }
     }
-
-    /// Create a Google Search tool
-    #[cfg(feature = "grounding")]
-    pub fn google_search() -> Self {
-        Tool::GoogleSearch(crate::grounding::SearchGrounding::default())
-    }
-
-    /// Create a URL context tool
-    #[cfg(feature = "grounding")]
-    pub fn url_context() -> Self {
-        Tool::UrlContext(crate::grounding::UrlContext::default())
-    }
-
-    /// Create a code execution tool
-    pub fn code_execution() -> Self {
-        Tool::CodeExecution {
-            code_execution: CodeExecutionConfig::default(),
-        }
-    }
 }
 
 /// Extension trait for easy tool configuration
@@ -264,39 +565,194 @@ pub trait ToolExt {
     fn with_any_function_calling(self, allowed: Option<Vec<String>>) -> Self;
     /// Disable function calling
     fn without_function_calling(self) -> Self;
+    /// Stream function call arguments as partial args (see `StreamAccumulator`)
+    fn with_streamed_function_arguments(self) -> Self;
 }
 
 impl ToolExt for crate::models::GenerateContentRequest {
     /// Configure automatic function calling
     fn with_auto_function_calling(mut self) -> Self {
-        self.tool_config = Some(ToolConfig {
-            function_calling_config: Some(FunctionCallingConfig {
-                mode: FunctionCallingMode::Auto,
-                allowed_function_names: None,
-            }),
-        });
+        let config = self.function_calling_config();
+        config.mode = FunctionCallingMode::Auto;
+        config.allowed_function_names = None;
         self
     }
 
     /// Configure any function calling with optional allowed functions
     fn with_any_function_calling(mut self, allowed: Option<Vec<String>>) -> Self {
-        self.tool_config = Some(ToolConfig {
-            function_calling_config: Some(FunctionCallingConfig {
-                mode: FunctionCallingMode::Any,
-                allowed_function_names: allowed,
-            }),
-        });
+        let config = self.function_calling_config();
+        config.mode = FunctionCallingMode::Any;
+        config.allowed_function_names = allowed;
         self
     }
 
     /// Disable function calling
     fn without_function_calling(mut self) -> Self {
-        self.tool_config = Some(ToolConfig {
-            function_calling_config: Some(FunctionCallingConfig {
-                mode: FunctionCallingMode::None,
+        let config = self.function_calling_config();
+        config.mode = FunctionCallingMode::None;
+        config.allowed_function_names = None;
+        self
+    }
+
+    /// Stream function call arguments as partial args
+    fn with_streamed_function_arguments(mut self) -> Self {
+        self.function_calling_config()
+            .stream_function_call_arguments = Some(true);
+        self
+    }
+}
+
+impl crate::models::GenerateContentRequest {
+    /// Get the function calling config, creating an automatic one if unset
+    fn function_calling_config(&mut self) -> &mut FunctionCallingConfig {
+        self.tool_config
+            .get_or_insert_with(ToolConfig::default)
+            .function_calling_config
+            .get_or_insert(FunctionCallingConfig {
+                mode: FunctionCallingMode::Auto,
                 allowed_function_names: None,
-            }),
-        });
+                stream_function_call_arguments: None,
+            })
+    }
+}
+
+/// Serialize properties in name order, so the same declaration always serializes the same way
+fn serialize_sorted<S: Serializer>(
+    properties: &HashMap<String, PropertySchema>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    properties
+        .iter()
+        .collect::<BTreeMap<_, _>>()
+        .serialize(serializer)
+}
+
+fn serialize_sorted_option<S: Serializer>(
+    properties: &Option<HashMap<String, PropertySchema>>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match properties {
+        Some(properties) => {
+            serializer.serialize_some(&properties.iter().collect::<BTreeMap<_, _>>())
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Async handler invoked with the arguments of a function call
+pub type ToolHandler = Arc<
+    dyn Fn(HashMap<String, serde_json::Value>) -> BoxFuture<'static, Result<serde_json::Value>>
+        + Send
+        + Sync,
+>;
+
+/// How a [`ToolRegistry`] treats calls whose arguments don't match the declared schema
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CallValidation {
+    /// Pass arguments to handlers without checking them
+    #[default]
+    Off,
+    /// Skip the handler and send the violations back to the model so it can correct itself
+    RejectToModel,
+    /// Abort with `Error::FunctionCall` describing the violations
+    Fail,
+}
+
+/// Registry of executable functions, keyed by name
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: BTreeMap<String, (FunctionDeclaration, ToolHandler)>,
+    validation: CallValidation,
+}
+
+impl ToolRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler for a function declaration, replacing any handler with the same name
+    pub fn register<F, Fut>(&mut self, declaration: FunctionDeclaration, handler: F) -> &mut Self
+    where
+        F: Fn(HashMap<String, serde_json::Value>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<serde_json::Value>> + Send + 'static,
+    {
+        let handler: ToolHandler = Arc::new(move |args| Box::pin(handler(args)));
+        self.tools
+            .insert(declaration.name.clone(), (declaration, handler));
+        self
+    }
+
+    /// Set how calls are validated against their declarations before execution
+    pub fn set_validation(&mut self, validation: CallValidation) -> &mut Self {
+        self.validation = validation;
         self
     }
+
+    /// Whether a function with the given name is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+
+    /// Declarations of all registered functions, in name order
+    pub fn declarations(&self) -> Vec<FunctionDeclaration> {
+        self.tools
+            .values()
+            .map(|(declaration, _)| declaration.clone())
+            .collect()
+    }
+
+    /// A tool declaring every registered function
+    pub fn tool(&self) -> Tool {
+        Tool::functions(self.declarations())
+    }
+
+    /// Execute a function call
+    ///
+    /// Failures (including unknown functions) are reported back to the model as an
+    /// `{"error": ...}` response rather than aborting the conversation. An error is only
+    /// returned for invalid arguments when validation is set to [`CallValidation::Fail`].
+    pub async fn execute(&self, call: &FunctionCall) -> Result<FunctionResponse> {
+        let response = match self.tools.get(&call.name) {
+            Some((declaration, handler)) => {
+                let violations = match self.validation {
+                    CallValidation::Off => Vec::new(),
+                    CallValidation::RejectToModel => declaration.parameters.validate(&call.args),
+                    CallValidation::Fail => {
+                        declaration.validate_call(call)?;
+                        Vec::new()
+                    }
+                };
+
+                if violations.is_empty() {
+                    match handler(call.args.clone()).await {
+                        Ok(value) => response_object(value),
+                        Err(e) => serde_json::json!({ "error": e.to_string() }),
+                    }
+                } else {
+                    debug!("Rejected invalid call to {}: {:?}", call.name, violations);
+                    serde_json::json!({
+                        "error": format!("Invalid arguments for {}", call.name),
+                        "violations": violations,
+                    })
+                }
+            }
+            None => serde_json::json!({
+                "error": format!("Unknown function: {}", call.name)
+            }),
+        };
+
+        debug!("Executed function call: {}", call.name);
+
+        Ok(FunctionResponse {
+            name: call.name.clone(),
+            response,
+            scheduling: None,
+        })
+    }
+
+    /// Execute several function calls concurrently, preserving their order
+    pub async fn execute_all(&self, calls: &[FunctionCall]) -> Result<Vec<FunctionResponse>> {
+        futures::future::try_join_all(calls.iter().map(|call| self.execute(call))).await
+    }
 }
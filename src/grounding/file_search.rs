@@ -0,0 +1,358 @@
+//! File Search (managed retrieval) stores
+
+use crate::{
+    client::{GeminiClient, API_KEY_HEADER},
+    config::HttpConfig,
+    error::{Error, Result},
+};
+use chrono::{DateTime, Utc};
+use reqwest::Response;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{debug, info};
+
+/// File Search tool configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSearch {
+    /// Resource names of the stores to search (e.g. "fileSearchStores/my-store-123")
+    pub file_search_store_names: Vec<String>,
+
+    /// Filter on document custom metadata (e.g. `author = "Jane"`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_filter: Option<String>,
+
+    /// Maximum number of chunks to retrieve
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+}
+
+impl FileSearch {
+    /// Search the given stores
+    pub fn new<I, S>(stores: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            file_search_store_names: stores.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Only search documents matching a metadata filter
+    pub fn metadata_filter(mut self, filter: impl Into<String>) -> Self {
+        self.metadata_filter = Some(filter.into());
+        self
+    }
+
+    /// Set the maximum number of chunks to retrieve
+    pub fn top_k(mut self, top_k: u32) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+}
+
+/// A File Search store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSearchStore {
+    /// Resource name (e.g. "fileSearchStores/my-store-123")
+    pub name: String,
+
+    /// Display name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    /// Creation time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<DateTime<Utc>>,
+
+    /// Update time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<DateTime<Utc>>,
+
+    /// Number of documents ready for retrieval
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_documents_count: Option<String>,
+
+    /// Number of documents still being processed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_documents_count: Option<String>,
+
+    /// Number of documents that failed processing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_documents_count: Option<String>,
+
+    /// Total size of the store in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<String>,
+}
+
+/// Response from the list stores API
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListFileSearchStoresResponse {
+    /// Stores on this page
+    #[serde(default)]
+    pub file_search_stores: Vec<FileSearchStore>,
+
+    /// Token for next page of results
+    pub next_page_token: Option<String>,
+}
+
+/// Custom metadata attached to an imported document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomMetadata {
+    /// Metadata key
+    pub key: String,
+
+    /// String value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub string_value: Option<String>,
+
+    /// Numeric value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub numeric_value: Option<f64>,
+}
+
+impl CustomMetadata {
+    /// Create a string metadata entry
+    pub fn string(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            string_value: Some(value.into()),
+            numeric_value: None,
+        }
+    }
+
+    /// Create a numeric metadata entry
+    pub fn numeric(key: impl Into<String>, value: f64) -> Self {
+        Self {
+            key: key.into(),
+            string_value: None,
+            numeric_value: Some(value),
+        }
+    }
+}
+
+/// How imported documents are split into chunks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkingConfig {
+    /// Whitespace-based chunking
+    pub white_space_config: WhiteSpaceConfig,
+}
+
+/// Whitespace-based chunking parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhiteSpaceConfig {
+    /// Maximum tokens per chunk
+    pub max_tokens_per_chunk: u32,
+
+    /// Tokens shared between consecutive chunks
+    pub max_overlap_tokens: u32,
+}
+
+/// Options for importing a file into a store
+#[derive(Debug, Clone, Default)]
+pub struct ImportFileConfig {
+    /// Metadata to attach to the document
+    pub custom_metadata: Vec<CustomMetadata>,
+
+    /// Chunking configuration (server default if unset)
+    pub chunking_config: Option<ChunkingConfig>,
+}
+
+/// A long-running operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    /// Operation resource name
+    pub name: String,
+
+    /// Whether the operation has completed
+    #[serde(default)]
+    pub done: bool,
+
+    /// Error, if the operation failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<serde_json::Value>,
+
+    /// Result, if the operation succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportFileRequest<'a> {
+    file_name: &'a str,
+
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    custom_metadata: &'a [CustomMetadata],
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chunking_config: Option<&'a ChunkingConfig>,
+}
+
+impl GeminiClient {
+    /// Create a File Search store
+    pub async fn create_file_search_store(
+        &self,
+        display_name: Option<&str>,
+    ) -> Result<FileSearchStore> {
+        let body = match display_name {
+            Some(display_name) => serde_json::json!({ "displayName": display_name }),
+            None => serde_json::json!({}),
+        };
+
+        let response = self
+            .http_client()
+            .post(self.file_search_url("fileSearchStores"))
+            .header(API_KEY_HEADER, self.config().api_key.expose())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &self.config().http_config))?;
+
+        let store: FileSearchStore =
+            file_search_response(response, "create store", &self.config().http_config).await?;
+        info!("Created File Search store: {}", store.name);
+        Ok(store)
+    }
+
+    /// Get a File Search store by resource name
+    pub async fn get_file_search_store(&self, name: &str) -> Result<FileSearchStore> {
+        let response = self
+            .http_client()
+            .get(self.file_search_url(name))
+            .header(API_KEY_HEADER, self.config().api_key.expose())
+            .send()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &self.config().http_config))?;
+
+        file_search_response(response, "get store", &self.config().http_config).await
+    }
+
+    /// List File Search stores
+    pub async fn list_file_search_stores(
+        &self,
+        page_size: Option<i32>,
+        page_token: Option<&str>,
+    ) -> Result<ListFileSearchStoresResponse> {
+        let mut query = Vec::new();
+        if let Some(size) = page_size {
+            query.push(("pageSize", size.to_string()));
+        }
+        if let Some(token) = page_token {
+            query.push(("pageToken", token.to_string()));
+        }
+
+        let response = self
+            .http_client()
+            .get(self.file_search_url("fileSearchStores"))
+            .header(API_KEY_HEADER, self.config().api_key.expose())
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &self.config().http_config))?;
+
+        file_search_response(response, "list stores", &self.config().http_config).await
+    }
+
+    /// Delete a File Search store, including its documents when `force` is set
+    pub async fn delete_file_search_store(&self, name: &str, force: bool) -> Result<()> {
+        let response = self
+            .http_client()
+            .delete(self.file_search_url(name))
+            .header(API_KEY_HEADER, self.config().api_key.expose())
+            .query(&[("force", if force { "true" } else { "false" })])
+            .send()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &self.config().http_config))?;
+
+        file_search_response::<serde_json::Value>(
+            response,
+            "delete store",
+            &self.config().http_config,
+        )
+        .await?;
+        info!("Deleted File Search store: {}", name);
+        Ok(())
+    }
+
+    /// Import an uploaded file (e.g. "files/abc-123") into a store
+    ///
+    /// Indexing happens asynchronously; poll the returned operation with
+    /// [`get_file_search_operation`](Self::get_file_search_operation).
+    pub async fn import_file_to_store(
+        &self,
+        store: &str,
+        file_name: &str,
+        config: ImportFileConfig,
+    ) -> Result<Operation> {
+        let request = ImportFileRequest {
+            file_name,
+            custom_metadata: &config.custom_metadata,
+            chunking_config: config.chunking_config.as_ref(),
+        };
+
+        debug!("Importing {} into {}", file_name, store);
+
+        let response = self
+            .http_client()
+            .post(self.file_search_url(&format!("{}:importFile", store)))
+            .header(API_KEY_HEADER, self.config().api_key.expose())
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &self.config().http_config))?;
+
+        file_search_response(response, "import file", &self.config().http_config).await
+    }
+
+    /// Get the current state of a File Search operation
+    pub async fn get_file_search_operation(&self, name: &str) -> Result<Operation> {
+        let response = self
+            .http_client()
+            .get(self.file_search_url(name))
+            .header(API_KEY_HEADER, self.config().api_key.expose())
+            .send()
+            .await
+            .map_err(|e| Error::from_reqwest(e, &self.config().http_config))?;
+
+        file_search_response(response, "get operation", &self.config().http_config).await
+    }
+
+    /// Stores belong to the project rather than a model, so they always use the base URL
+    fn file_search_url(&self, path: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.config().base_url,
+            self.config().api_version.as_str(),
+            path
+        )
+    }
+}
+
+/// Decode a File Search API response, mapping failures to `Error::Grounding`
+async fn file_search_response<T: DeserializeOwned>(
+    response: Response,
+    action: &str,
+    http: &HttpConfig,
+) -> Result<T> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_body = response.text().await.unwrap_or_default();
+        return Err(Error::Grounding(format!(
+            "Failed to {} (status {}): {}",
+            action, status, error_body
+        )));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| Error::from_reqwest(e, http))
+}
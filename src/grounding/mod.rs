@@ -1,11 +1,27 @@
 //! Grounding support for search and URL context
 
+use crate::{
+    client::GeminiClient,
+    error::{Error, Result},
+    models::{GenerateContentRequest, GenerateContentResponse, Tool},
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tracing::instrument;
+
+/// Maximum number of URLs the URL context tool accepts per request
+const MAX_CONTEXT_URLS: usize = 20;
+
+pub mod file_search;
+
+pub use file_search::{FileSearch, FileSearchStore};
 
 /// Configuration for grounding tools
+///
+/// Serialized with the variant name as the key, e.g. `{"url_context": {"max_urls": 5}}`,
+/// since the variants' own fields are all optional and could not be told apart otherwise.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
+#[serde(rename_all = "snake_case")]
 pub enum GroundingConfig {
     /// Google Search grounding
     Search(SearchGrounding),
@@ -18,6 +34,10 @@ pub enum GroundingConfig {
         /// URL context configuration
         url_context: UrlContext,
     },
+    /// Google Maps grounding
+    Maps(GoogleMaps),
+    /// Any other combination of grounding tools
+    Multiple(Vec<GroundingConfig>),
 }
 
 /// Google Search grounding configuration
@@ -36,6 +56,89 @@ pub struct UrlContext {
     pub max_urls: Option<u32>,
 }
 
+/// Google Maps grounding configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleMaps {
+    /// Whether to return a widget context token for rendering a Maps widget
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_widget: Option<bool>,
+}
+
+/// Retrieval tool configuration (Vertex AI only)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Retrieval {
+    /// Vertex AI Search datastore to retrieve from
+    pub vertex_ai_search: VertexAiSearch,
+
+    /// Don't attribute retrieved content in grounding metadata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_attribution: Option<bool>,
+}
+
+/// A Vertex AI Search datastore or engine
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VertexAiSearch {
+    /// Datastore resource name
+    /// (e.g. "projects/p/locations/global/collections/default_collection/dataStores/d")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datastore: Option<String>,
+
+    /// Search engine resource name, for searching several datastores
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+
+    /// Filter applied to the search (Vertex AI Search filter syntax)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+
+    /// Maximum number of results to retrieve
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<u32>,
+}
+
+impl VertexAiSearch {
+    /// Search a single datastore
+    pub fn datastore(name: impl Into<String>) -> Self {
+        Self {
+            datastore: Some(name.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Search through a search engine
+    pub fn engine(name: impl Into<String>) -> Self {
+        Self {
+            engine: Some(name.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Retrieval configuration passed in the request's tool config
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RetrievalConfig {
+    /// Location of the user, used to ground place-based answers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lat_lng: Option<LatLng>,
+
+    /// Language of the user (e.g. "en_US")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_code: Option<String>,
+}
+
+/// A latitude/longitude pair in degrees
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LatLng {
+    /// Latitude in degrees
+    pub latitude: f64,
+    /// Longitude in degrees
+    pub longitude: f64,
+}
+
 /// Dynamic retrieval configuration for search grounding
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DynamicRetrievalConfig {
@@ -79,7 +182,53 @@ pub struct GroundingMetadata {
 
     /// Retrieval metadata
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub retrieval_metadata: Option<HashMap<String, serde_json::Value>>,
+    pub retrieval_metadata: Option<RetrievalMetadata>,
+
+    /// Token for rendering a Google Maps widget with the grounded places
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub google_maps_widget_context_token: Option<String>,
+}
+
+impl GroundingMetadata {
+    /// Whether Google Search was actually used to ground the response
+    ///
+    /// With dynamic retrieval the model may answer without searching; in that case no
+    /// queries or web chunks are returned.
+    pub fn used_search(&self) -> bool {
+        self.web_search_queries
+            .as_ref()
+            .is_some_and(|queries| !queries.is_empty())
+            || self
+                .grounding_chunks
+                .iter()
+                .flatten()
+                .any(|chunk| chunk.web.is_some())
+    }
+
+    /// Predicted usefulness of search (0.0 to 1.0) from dynamic retrieval
+    pub fn dynamic_retrieval_score(&self) -> Option<f32> {
+        self.retrieval_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.google_search_dynamic_retrieval_score)
+    }
+}
+
+/// Metadata about the retrieval decision
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RetrievalMetadata {
+    /// Predicted probability (0.0 to 1.0) that Google Search helps answer the prompt;
+    /// search is used when it exceeds the configured dynamic threshold
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub google_search_dynamic_retrieval_score: Option<f32>,
+}
+
+impl RetrievalMetadata {
+    /// Whether the score exceeds a dynamic retrieval threshold
+    pub fn exceeds_threshold(&self, threshold: f32) -> bool {
+        self.google_search_dynamic_retrieval_score
+            .is_some_and(|score| score > threshold)
+    }
 }
 
 /// Search entry point for rendering search suggestions
@@ -88,14 +237,210 @@ pub struct GroundingMetadata {
 pub struct SearchEntryPoint {
     /// Rendered content for search suggestions
     pub rendered_content: String,
+
+    /// Base64-encoded JSON array of search terms and URLs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sdk_blob: Option<String>,
+}
+
+/// A Google Search suggestion chip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchSuggestion {
+    /// Search query shown on the chip
+    pub query: String,
+    /// Google Search URL for the query
+    pub url: String,
+}
+
+impl SearchEntryPoint {
+    /// Extract the suggestion chips from the rendered HTML
+    pub fn suggestions(&self) -> Vec<SearchSuggestion> {
+        let html = &self.rendered_content;
+        let mut suggestions = Vec::new();
+        let mut rest = html.as_str();
+
+        while let Some(start) = rest.find("<a") {
+            rest = &rest[start..];
+            let Some(tag_end) = rest.find('>') else {
+                break;
+            };
+            let tag = &rest[..tag_end];
+            let Some(close) = rest.find("</a>") else {
+                break;
+            };
+            let inner = rest.get(tag_end + 1..close).unwrap_or_default();
+            rest = &rest[close + 4..];
+
+            let Some(url) = html_attribute(tag, "href") else {
+                continue;
+            };
+            let query = decode_entities(strip_tags(inner).trim());
+            if !query.is_empty() {
+                suggestions.push(SearchSuggestion {
+                    query,
+                    url: decode_entities(&url),
+                });
+            }
+        }
+
+        suggestions
+    }
+
+    /// The rendered HTML cleaned with an allow-list sanitizer, safe to embed
+    ///
+    /// Only ammonia's default set of formatting tags and attributes survives, plus `class`
+    /// for the chip layout; scripts, event handlers, frames, embedded objects and
+    /// `javascript:` URLs are removed. Links open in a new tab.
+    ///
+    /// The `<style>` blocks Google requires for the chip layout are kept when they hold plain
+    /// rules: no URLs, imports, escapes or markup. Their selectors apply to the whole page, so
+    /// embed the result in a shadow root or iframe if they could clash with your own styles.
+    #[cfg(feature = "sanitize")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sanitize")))]
+    pub fn sanitized_html(&self) -> String {
+        let body = ammonia::Builder::default()
+            .add_generic_attributes(["class"])
+            .link_rel(Some("noopener noreferrer"))
+            .set_tag_attribute_value("a", "target", "_blank")
+            .clean(&self.rendered_content)
+            .to_string();
+        let styles: String = style_blocks(&self.rendered_content)
+            .into_iter()
+            .filter(|css| is_plain_css(css))
+            .map(|css| format!("<style>{}</style>", css))
+            .collect();
+        styles + &body
+    }
+
+    /// Suggestions as plain text, one query per line
+    pub fn to_plain_text(&self) -> String {
+        self.suggestions()
+            .into_iter()
+            .map(|suggestion| suggestion.query)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Suggestions as a line of markdown links
+    pub fn to_markdown(&self) -> String {
+        self.suggestions()
+            .iter()
+            .map(|suggestion| format!("[{}]({})", suggestion.query, suggestion.url))
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+}
+
+/// Read a quoted attribute value from an HTML start tag
+fn html_attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut offset = 0;
+    while let Some(found) = lower[offset..].find(name) {
+        let start = offset + found;
+        offset = start + name.len();
+        // Make sure we matched a whole attribute name
+        if start > 0 && !lower.as_bytes()[start - 1].is_ascii_whitespace() {
+            continue;
+        }
+        let after = tag[offset..].trim_start().strip_prefix('=')?.trim_start();
+        let quote = after.chars().next()?;
+        if quote == '"' || quote == '\'' {
+            let value = &after[1..];
+            return value.find(quote).map(|end| value[..end].to_string());
+        }
+        let end = after
+            .find(|c: char| c.is_whitespace() || c == '>')
+            .unwrap_or(after.len());
+        return Some(after[..end].to_string());
+    }
+    None
+}
+
+/// Remove all tags, keeping text content
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Decode the handful of HTML entities that appear in rendered suggestions
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
 }
 
 /// A chunk of grounding information
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GroundingChunk {
     /// Web source
     #[serde(skip_serializing_if = "Option::is_none")]
     pub web: Option<WebSource>,
+
+    /// Google Maps place source
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maps: Option<MapsSource>,
+
+    /// Document chunk retrieved by File Search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieved_context: Option<RetrievedContext>,
+}
+
+/// Document chunk retrieved from a File Search store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetrievedContext {
+    /// URI of the source document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+
+    /// Title of the source document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Retrieved chunk text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    /// Store the chunk was retrieved from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_search_store: Option<String>,
+
+    /// Vertex AI Search document the chunk belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_name: Option<String>,
+}
+
+/// Google Maps place used for grounding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapsSource {
+    /// URI of the place on Google Maps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+
+    /// Name of the place
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Text describing the place
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    /// Google Maps place ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place_id: Option<String>,
 }
 
 /// Web source information
@@ -162,9 +507,21 @@ pub struct UrlMetadata {
     pub url_retrieval_status: UrlRetrievalStatus,
 }
 
+impl UrlContextMetadata {
+    /// URLs that could not be retrieved, with their status
+    pub fn failed_urls(&self) -> impl Iterator<Item = &UrlMetadata> {
+        self.url_metadata.iter().filter(|metadata| {
+            !matches!(metadata.url_retrieval_status, UrlRetrievalStatus::Success)
+        })
+    }
+}
+
 /// Status of URL retrieval for grounding
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UrlRetrievalStatus {
+    /// Status not reported
+    #[serde(rename = "URL_RETRIEVAL_STATUS_UNSPECIFIED")]
+    Unspecified,
     /// URL was successfully retrieved
     #[serde(rename = "URL_RETRIEVAL_STATUS_SUCCESS")]
     Success,
@@ -174,35 +531,142 @@ pub enum UrlRetrievalStatus {
     /// URL was unreachable
     #[serde(rename = "URL_RETRIEVAL_STATUS_UNREACHABLE")]
     Unreachable,
+    /// Content is behind a paywall
+    #[serde(rename = "URL_RETRIEVAL_STATUS_PAYWALL")]
+    Paywall,
+    /// Content was flagged as unsafe
+    #[serde(rename = "URL_RETRIEVAL_STATUS_UNSAFE")]
+    Unsafe,
+}
+
+/// Helpers to create grounding tools
+impl Tool {
+    /// Create a Google Search tool
+    pub fn google_search() -> Self {
+        Tool::GoogleSearch(SearchGrounding::default())
+    }
+
+    /// Create a URL context tool
+    pub fn url_context() -> Self {
+        Tool::UrlContext(UrlContext::default())
+    }
+
+    /// Create a Google Maps tool
+    pub fn google_maps() -> Self {
+        Tool::GoogleMaps(GoogleMaps::default())
+    }
+
+    /// Create a File Search tool over the given stores
+    pub fn file_search<I, S>(stores: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Tool::FileSearch(FileSearch::new(stores))
+    }
+
+    /// Create a Vertex AI Search retrieval tool (Vertex AI only)
+    pub fn retrieval(search: VertexAiSearch) -> Self {
+        Tool::Retrieval(Retrieval {
+            vertex_ai_search: search,
+            disable_attribution: None,
+        })
+    }
 }
 
 /// Helper to convert grounding config into tools
 impl GroundingConfig {
     /// Convert grounding configuration to tools vector
-    #[cfg(feature = "functions")]
-    pub fn to_tools(&self) -> Vec<crate::functions::Tool> {
+    pub fn to_tools(&self) -> Vec<Tool> {
         match self {
             GroundingConfig::Search(search) => {
-                vec![crate::functions::Tool::GoogleSearch(search.clone())]
+                vec![Tool::GoogleSearch(search.clone())]
             }
             GroundingConfig::UrlContext(url_context) => {
-                vec![crate::functions::Tool::UrlContext(url_context.clone())]
+                vec![Tool::UrlContext(url_context.clone())]
             }
             GroundingConfig::Combined {
                 search,
                 url_context,
             } => vec![
-                crate::functions::Tool::GoogleSearch(search.clone()),
-                crate::functions::Tool::UrlContext(url_context.clone()),
+                Tool::GoogleSearch(search.clone()),
+                Tool::UrlContext(url_context.clone()),
             ],
+            GroundingConfig::Maps(maps) => {
+                vec![Tool::GoogleMaps(maps.clone())]
+            }
+            GroundingConfig::Multiple(configs) => {
+                configs.iter().flat_map(GroundingConfig::to_tools).collect()
+            }
+        }
+    }
+}
+
+/// Extension trait for adding grounding tools to a request
+pub trait GroundingExt {
+    /// Append the grounding tools, keeping any tools already on the request
+    fn with_grounding(self, config: GroundingConfig) -> Self;
+}
+
+impl GroundingExt for crate::models::GenerateContentRequest {
+    fn with_grounding(mut self, config: GroundingConfig) -> Self {
+        let tools = self.tools.get_or_insert_with(Vec::new);
+        for tool in config.to_tools() {
+            // Each grounding tool may only be declared once per request
+            let kind = std::mem::discriminant(&tool);
+            if !tools
+                .iter()
+                .any(|existing| std::mem::discriminant(existing) == kind)
+            {
+                tools.push(tool);
+            }
         }
+        self
+    }
+}
+
+/// Contents of the `<style>` elements in an HTML fragment
+#[cfg(feature = "sanitize")]
+fn style_blocks(html: &str) -> Vec<&str> {
+    let lower = html.to_ascii_lowercase();
+    let mut blocks = Vec::new();
+    let mut rest = 0;
+    while let Some(open) = lower[rest..].find("<style") {
+        let Some(start) = lower[rest + open..].find('>').map(|i| rest + open + i + 1) else {
+            break;
+        };
+        let Some(end) = lower[start..].find("</style").map(|i| start + i) else {
+            break;
+        };
+        blocks.push(&html[start..end]);
+        rest = end;
     }
+    blocks
+}
+
+/// Whether CSS holds only plain rules, with nothing that loads resources or could close the
+/// `<style>` element
+#[cfg(feature = "sanitize")]
+fn is_plain_css(css: &str) -> bool {
+    let lower = css.to_ascii_lowercase();
+    !css.contains(['<', '\\'])
+        && [
+            "url(",
+            "@import",
+            "expression(",
+            "javascript:",
+            "image-set(",
+            "@font-face",
+        ]
+        .iter()
+        .all(|token| !lower.contains(token))
 }
 
 /// Builder for grounding configuration
 pub struct GroundingBuilder {
     search: Option<SearchGrounding>,
     url_context: Option<UrlContext>,
+    maps: Option<GoogleMaps>,
 }
 
 impl Default for GroundingBuilder {
@@ -217,6 +681,7 @@ impl GroundingBuilder {
         Self {
             search: None,
             url_context: None,
+            maps: None,
         }
     }
 
@@ -251,9 +716,34 @@ impl GroundingBuilder {
         self
     }
 
+    /// Enable Google Maps grounding
+    pub fn with_maps(mut self) -> Self {
+        self.maps = Some(GoogleMaps::default());
+        self
+    }
+
+    /// Enable Google Maps grounding and request a widget context token
+    pub fn with_maps_widget(mut self) -> Self {
+        self.maps = Some(GoogleMaps {
+            enable_widget: Some(true),
+        });
+        self
+    }
+
+    /// Add the configured grounding tools to a request
+    pub fn apply(
+        self,
+        request: crate::models::GenerateContentRequest,
+    ) -> crate::models::GenerateContentRequest {
+        match self.build() {
+            Some(config) => request.with_grounding(config),
+            None => request,
+        }
+    }
+
     /// Build the grounding configuration
     pub fn build(self) -> Option<GroundingConfig> {
-        match (self.search, self.url_context) {
+        let base = match (self.search, self.url_context) {
             (Some(search), Some(url_context)) => Some(GroundingConfig::Combined {
                 search,
                 url_context,
@@ -261,6 +751,198 @@ impl GroundingBuilder {
             (Some(search), None) => Some(GroundingConfig::Search(search)),
             (None, Some(url_context)) => Some(GroundingConfig::UrlContext(url_context)),
             (None, None) => None,
+        };
+
+        match (base, self.maps) {
+            (Some(base), Some(maps)) => Some(GroundingConfig::Multiple(vec![
+                base,
+                GroundingConfig::Maps(maps),
+            ])),
+            (None, Some(maps)) => Some(GroundingConfig::Maps(maps)),
+            (base, None) => base,
+        }
+    }
+}
+
+/// Render grounded text as markdown with inline footnote markers and a sources list
+///
+/// Markers are placed at the end of each `grounding_supports` segment (byte offsets into
+/// `text`, falling back to locating the segment text) and at the end of each citation
+/// range. Sources are numbered in grounding-chunk order, followed by any citation URIs
+/// not already listed.
+pub fn render_markdown(
+    text: &str,
+    grounding: Option<&GroundingMetadata>,
+    citations: Option<&crate::models::CitationMetadata>,
+) -> String {
+    let mut sources: Vec<(String, Option<String>)> = Vec::new();
+    // Maps grounding chunk index to footnote number
+    let mut chunk_numbers: HashMap<usize, usize> = HashMap::new();
+    // Byte offset -> footnote numbers to insert there
+    let mut markers: std::collections::BTreeMap<usize, Vec<usize>> = Default::default();
+
+    if let Some(grounding) = grounding {
+        for (index, chunk) in grounding.grounding_chunks.iter().flatten().enumerate() {
+            if let Some(web) = &chunk.web {
+                sources.push((web.uri.clone(), Some(web.title.clone())));
+                chunk_numbers.insert(index, sources.len());
+            } else if let Some(uri) = chunk.maps.as_ref().and_then(|maps| maps.uri.as_ref()) {
+                let title = chunk.maps.as_ref().and_then(|maps| maps.title.clone());
+                sources.push((uri.clone(), title));
+                chunk_numbers.insert(index, sources.len());
+            } else if let Some(context) = &chunk.retrieved_context {
+                let Some(uri) = context.uri.clone().or_else(|| context.title.clone()) else {
+                    continue;
+                };
+                sources.push((uri, context.title.clone()));
+                chunk_numbers.insert(index, sources.len());
+            }
+        }
+
+        for support in grounding.grounding_supports.iter().flatten() {
+            let Some(segment) = &support.segment else {
+                continue;
+            };
+            let offset = match segment.end_index {
+                Some(end) => Some(end.max(0) as usize),
+                None => text
+                    .find(&segment.text)
+                    .map(|start| start + segment.text.len()),
+            };
+            let Some(offset) = offset else {
+                continue;
+            };
+
+            for index in support.grounding_chunk_indices.iter().flatten() {
+                if let Some(&number) = chunk_numbers.get(&(*index as usize)) {
+                    markers.entry(offset).or_default().push(number);
+                }
+            }
+        }
+    }
+
+    if let Some(citations) = citations {
+        for source in &citations.citation_sources {
+            let Some(uri) = &source.uri else {
+                continue;
+            };
+            let number = match sources.iter().position(|(u, _)| u == uri) {
+                Some(position) => position + 1,
+                None => {
+                    sources.push((uri.clone(), None));
+                    sources.len()
+                }
+            };
+            if let Some(end) = source.end_index {
+                markers.entry(end.max(0) as usize).or_default().push(number);
+            }
         }
     }
+
+    let mut output = String::with_capacity(text.len() + sources.len() * 64);
+    let mut cursor = 0;
+    for (offset, mut numbers) in markers {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset += 1;
+        }
+        output.push_str(&text[cursor..offset]);
+        cursor = offset;
+
+        numbers.sort_unstable();
+        numbers.dedup();
+        for number in numbers {
+            output.push_str(&format!("[^{}]", number));
+        }
+    }
+    output.push_str(&text[cursor..]);
+
+    if !sources.is_empty() {
+        output.push_str("\n\n");
+        for (number, (uri, title)) in sources.iter().enumerate() {
+            let label = title.as_deref().unwrap_or(uri);
+            output.push_str(&format!("[^{}]: [{}]({})\n", number + 1, label, uri));
+        }
+    }
+
+    output
+}
+
+impl crate::models::Candidate {
+    /// Render this candidate's text as markdown with footnotes for its grounding sources
+    pub fn render_markdown(&self) -> String {
+        let text: String = self
+            .content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                crate::models::Part::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        render_markdown(
+            &text,
+            self.grounding_metadata.as_ref(),
+            self.citation_metadata.as_ref(),
+        )
+    }
+}
+
+/// Answer to a question about a set of URLs
+#[derive(Debug, Clone)]
+pub struct UrlContextAnswer {
+    /// Text of the answer
+    pub text: String,
+    /// Per-URL retrieval status
+    pub url_context_metadata: Option<UrlContextMetadata>,
+    /// The full response
+    pub response: GenerateContentResponse,
+}
+
+impl GeminiClient {
+    /// Ask a question about the content of up to 20 URLs using the URL context tool
+    #[instrument(skip(self, urls, question))]
+    pub async fn ask_about_urls<I, S>(
+        &self,
+        model: Option<&str>,
+        urls: I,
+        question: &str,
+    ) -> Result<UrlContextAnswer>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let urls: Vec<String> = urls.into_iter().map(|u| u.as_ref().to_string()).collect();
+        if urls.is_empty() {
+            return Err(Error::Grounding("No URLs given".to_string()));
+        }
+        if urls.len() > MAX_CONTEXT_URLS {
+            return Err(Error::Grounding(format!(
+                "URL context supports at most {} URLs, got {}",
+                MAX_CONTEXT_URLS,
+                urls.len()
+            )));
+        }
+
+        // The tool only fetches URLs that appear in the prompt
+        let mut prompt = format!("{}\n\nUse the content of these URLs:", question);
+        for url in &urls {
+            prompt.push_str("\n- ");
+            prompt.push_str(url);
+        }
+
+        let request = GenerateContentRequest::new(prompt)
+            .with_grounding(GroundingConfig::UrlContext(UrlContext::default()));
+        let response = self.generate_content(model, request).await?;
+
+        Ok(UrlContextAnswer {
+            text: response.text().unwrap_or_default(),
+            url_context_metadata: response
+                .candidates
+                .first()
+                .and_then(|candidate| candidate.url_context_metadata.clone()),
+            response,
+        })
+    }
 }
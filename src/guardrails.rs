@@ -0,0 +1,213 @@
+//! Output guardrails with automatic repair
+//!
+//! [`GeminiClient::generate_guarded`] checks each response against a set of [`Guardrails`].
+//! When a check fails, the client shows the model its previous answer with the problems found
+//! and asks again, returning the first response that passes or [`Error::GuardrailFailed`] once
+//! the attempts run out.
+
+use crate::{
+    client::GeminiClient,
+    error::{Error, Result},
+    models::{Content, GenerateContentRequest, GenerateContentResponse, ResponseSchema},
+};
+use regex::Regex;
+use std::fmt;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Default number of responses generated before giving up
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// A check over a model response
+///
+/// Implemented for closures taking the response and returning the problems found.
+pub trait Guardrail: Send + Sync {
+    /// Check a response, describing each problem found; an empty list passes
+    fn check(&self, response: &GenerateContentResponse) -> Vec<String>;
+}
+
+impl<F> Guardrail for F
+where
+    F: Fn(&GenerateContentResponse) -> Vec<String> + Send + Sync,
+{
+    fn check(&self, response: &GenerateContentResponse) -> Vec<String> {
+        self(response)
+    }
+}
+
+/// Requires the response text to be JSON matching a schema
+///
+/// Code fences and trailing commas are tolerated, as in
+/// [`parse_json_repaired`](GenerateContentResponse::parse_json_repaired).
+#[derive(Debug, Clone)]
+pub struct JsonSchemaCheck {
+    schema: ResponseSchema,
+}
+
+impl JsonSchemaCheck {
+    /// Check responses against `schema`
+    pub fn new(schema: ResponseSchema) -> Self {
+        Self { schema }
+    }
+}
+
+impl Guardrail for JsonSchemaCheck {
+    fn check(&self, response: &GenerateContentResponse) -> Vec<String> {
+        match response.parse_json_repaired::<serde_json::Value>() {
+            Ok(value) => self.schema.validate(&value),
+            Err(e) => vec![e.to_string()],
+        }
+    }
+}
+
+/// Rejects responses whose text matches any of a set of regular expressions
+#[derive(Debug, Clone)]
+pub struct BannedPatterns {
+    patterns: Vec<Regex>,
+}
+
+impl BannedPatterns {
+    /// Compile the patterns, failing with `Error::Config` on invalid syntax
+    pub fn new<I, S>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                Regex::new(pattern.as_ref()).map_err(|e| {
+                    Error::Config(format!(
+                        "Invalid banned pattern {:?}: {}",
+                        pattern.as_ref(),
+                        e
+                    ))
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+}
+
+impl Guardrail for BannedPatterns {
+    fn check(&self, response: &GenerateContentResponse) -> Vec<String> {
+        let text = response.text().unwrap_or_default();
+        self.patterns
+            .iter()
+            .filter(|pattern| pattern.is_match(&text))
+            .map(|pattern| format!("the response must not match `{}`", pattern.as_str()))
+            .collect()
+    }
+}
+
+/// Checks a response must pass and how many responses to try
+#[derive(Clone)]
+pub struct Guardrails {
+    checks: Vec<Arc<dyn Guardrail>>,
+    max_attempts: u32,
+}
+
+impl Default for Guardrails {
+    fn default() -> Self {
+        Self {
+            checks: Vec::new(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        }
+    }
+}
+
+impl fmt::Debug for Guardrails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Guardrails")
+            .field("checks", &self.checks.len())
+            .field("max_attempts", &self.max_attempts)
+            .finish()
+    }
+}
+
+impl Guardrails {
+    /// No checks, with the default of 3 attempts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require the response to pass a guardrail
+    pub fn with_guardrail(mut self, guardrail: impl Guardrail + 'static) -> Self {
+        self.checks.push(Arc::new(guardrail));
+        self
+    }
+
+    /// Generate at most this many responses while repairing failures (default 3)
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Whether no guardrail is set
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    /// Run every guardrail, collecting the problems found
+    pub fn check(&self, response: &GenerateContentResponse) -> Vec<String> {
+        self.checks
+            .iter()
+            .flat_map(|guardrail| guardrail.check(response))
+            .collect()
+    }
+}
+
+impl GeminiClient {
+    /// Generate content, re-prompting until the response passes `guardrails`
+    ///
+    /// Each repair sends the original request followed by the failed answer and the problems
+    /// found in it. Errors from the requests themselves are returned as usual.
+    pub async fn generate_guarded(
+        &self,
+        model: Option<&str>,
+        request: GenerateContentRequest,
+        guardrails: &Guardrails,
+    ) -> Result<GenerateContentResponse> {
+        let mut response = self.generate_content(model, request.clone()).await?;
+        let mut attempts = 1;
+        loop {
+            let violations = guardrails.check(&response);
+            if violations.is_empty() {
+                return Ok(response);
+            }
+            if attempts >= guardrails.max_attempts {
+                return Err(Error::GuardrailFailed {
+                    violations,
+                    attempts,
+                });
+            }
+            warn!(
+                "Response failed guardrails (attempt {}): {}",
+                attempts,
+                violations.join("; ")
+            );
+
+            let mut repair = request.clone();
+            if let Some(candidate) = response.candidates.first() {
+                repair.contents.push(candidate.content.clone());
+            }
+            repair
+                .contents
+                .push(Content::user(repair_prompt(&violations)));
+            response = self.generate_content(model, repair).await?;
+            attempts += 1;
+        }
+    }
+}
+
+/// Ask the model to fix the problems in its previous answer
+fn repair_prompt(violations: &[String]) -> String {
+    let problems: Vec<String> = violations
+        .iter()
+        .map(|violation| format!("- {}", violation))
+        .collect();
+    format!(
+        "Your previous answer has these problems:\n{}\nAnswer again, fixing all of them.",
+        problems.join("\n")
+    )
+}
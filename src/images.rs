@@ -0,0 +1,122 @@
+//! Image output
+//!
+//! Image-capable models return pictures as inline data parts when the request asks for the
+//! `IMAGE` modality. [`GenerateContentRequest::with_image_output`] enables it, and
+//! [`Candidate::images`] and [`GenerateContentResponse::save_images`] decode the results.
+
+use crate::{
+    error::{Error, Result},
+    models::{Candidate, GenerateContentRequest, GenerateContentResponse, Modality, Part},
+};
+use base64::Engine;
+use std::path::{Path, PathBuf};
+
+/// A decoded image from a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedImage {
+    /// MIME type, e.g. `image/png`
+    pub mime_type: String,
+    /// Raw image bytes
+    pub data: Vec<u8>,
+}
+
+impl GeneratedImage {
+    /// File extension for the MIME type, `bin` if unknown
+    pub fn extension(&self) -> &'static str {
+        match self.mime_type.as_str() {
+            "image/png" => "png",
+            "image/jpeg" | "image/jpg" => "jpg",
+            "image/webp" => "webp",
+            "image/gif" => "gif",
+            "image/heic" => "heic",
+            "image/heif" => "heif",
+            _ => "bin",
+        }
+    }
+
+    /// Write the image bytes to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, &self.data)?;
+        Ok(())
+    }
+}
+
+impl GenerateContentRequest {
+    /// Ask for images as well as text
+    ///
+    /// Image output cannot be combined with structured output, so a response MIME type
+    /// other than `text/plain` and any response schema are removed.
+    pub fn with_image_output(mut self) -> Self {
+        let config = self.generation_config.get_or_insert_with(Default::default);
+        config.response_modalities = Some(vec![Modality::Text, Modality::Image]);
+        if config
+            .response_mime_type
+            .as_deref()
+            .is_some_and(|mime| mime != "text/plain")
+        {
+            config.response_mime_type = None;
+        }
+        config.response_schema = None;
+        config.response_json_schema = None;
+        self
+    }
+}
+
+impl Candidate {
+    /// Decoded image parts of this candidate, in order
+    pub fn images(&self) -> Result<Vec<GeneratedImage>> {
+        self.content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::InlineData { inline_data, .. }
+                    if inline_data.mime_type.starts_with("image/") =>
+                {
+                    Some(inline_data)
+                }
+                _ => None,
+            })
+            .map(|inline_data| {
+                let data = base64::engine::general_purpose::STANDARD
+                    .decode(&inline_data.data)
+                    .map_err(|e| {
+                        Error::InvalidResponse(format!("Invalid base64 image data: {}", e))
+                    })?;
+                Ok(GeneratedImage {
+                    mime_type: inline_data.mime_type.clone(),
+                    data,
+                })
+            })
+            .collect()
+    }
+}
+
+impl GenerateContentResponse {
+    /// Decoded images of the first candidate
+    pub fn images(&self) -> Result<Vec<GeneratedImage>> {
+        match self.candidates.first() {
+            Some(candidate) => candidate.images(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Write the first candidate's images to `dir` as `image-1.png`, `image-2.png`, ...
+    ///
+    /// The directory is created if needed. Returns the paths written.
+    pub fn save_images(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        let images = self.images()?;
+        if !images.is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+        images
+            .iter()
+            .enumerate()
+            .map(|(i, image)| {
+                let path = dir.join(format!("image-{}.{}", i + 1, image.extension()));
+                image.save(&path)?;
+                Ok(path)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,136 @@
+//! Helpers for parsing JSON produced by the model
+
+use crate::error::{Error, Result};
+use serde::de::DeserializeOwned;
+
+/// Maximum number of characters of offending text included in parse errors
+const SNIPPET_RADIUS: usize = 40;
+
+/// Strip surrounding whitespace and markdown code fences from model output
+pub fn strip_code_fences(text: &str) -> &str {
+    let trimmed = text.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+
+    // Drop the optional language tag on the opening fence
+    let body = match rest.find('\n') {
+        Some(newline) => &rest[newline + 1..],
+        None => rest,
+    };
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
+
+/// Best-effort repair of almost-valid JSON
+///
+/// Removes trailing commas before closing brackets and braces, and closes strings, arrays
+/// and objects left open by a truncated answer.
+pub fn repair_json(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut open = Vec::new();
+
+    for c in text.chars() {
+        if in_string {
+            output.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '{' => {
+                open.push('}');
+                output.push(c);
+            }
+            '[' => {
+                open.push(']');
+                output.push(c);
+            }
+            '}' | ']' => {
+                drop_trailing_comma(&mut output);
+                if open.last() == Some(&c) {
+                    open.pop();
+                }
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+
+    if !open.is_empty() || in_string {
+        if in_string {
+            if escaped {
+                output.pop();
+            }
+            output.push('"');
+        }
+        drop_trailing_comma(&mut output);
+        output.truncate(output.trim_end().len());
+        if output.ends_with(':') {
+            output.push_str("null");
+        }
+        while let Some(close) = open.pop() {
+            output.push(close);
+        }
+    }
+
+    output
+}
+
+/// Remove a comma (and the whitespace after it) from the end of `output`
+fn drop_trailing_comma(output: &mut String) {
+    let kept = output.trim_end().len();
+    if output[..kept].ends_with(',') {
+        output.truncate(kept - 1);
+    }
+}
+
+/// Deserialize model output into `T`, optionally repairing it first
+pub(crate) fn parse<T: DeserializeOwned>(text: &str, repair: bool) -> Result<T> {
+    let json = strip_code_fences(text);
+    let repaired;
+    let json = if repair {
+        repaired = repair_json(json);
+        repaired.as_str()
+    } else {
+        json
+    };
+
+    serde_json::from_str(json).map_err(|e| {
+        Error::InvalidResponse(format!(
+            "Failed to parse JSON response: {} near `{}`",
+            e,
+            snippet(json, e.line(), e.column())
+        ))
+    })
+}
+
+/// Extract the text surrounding a line/column position reported by serde_json
+fn snippet(text: &str, line: usize, column: usize) -> String {
+    let offset: usize = text
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>()
+        + column.saturating_sub(1);
+
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let position = chars
+        .iter()
+        .position(|(i, _)| *i >= offset)
+        .unwrap_or(chars.len());
+    let start = position.saturating_sub(SNIPPET_RADIUS);
+    let end = (position + SNIPPET_RADIUS).min(chars.len());
+
+    chars[start..end].iter().map(|(_, c)| c).collect()
+}
@@ -37,10 +37,40 @@
 #![warn(missing_docs)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+pub mod audio;
+pub mod best_of;
+pub mod budget;
+pub mod chat;
 pub mod client;
 pub mod config;
 pub mod error;
+pub mod fetch;
+pub mod files;
+pub mod guardrails;
+pub mod images;
+pub mod json;
+pub mod logging;
+pub mod media;
+pub mod memory;
+pub mod metrics;
 pub mod models;
+pub mod retry;
+pub mod safety;
+pub mod transcribe;
+
+#[cfg(feature = "schemars")]
+mod schema;
+
+#[cfg(feature = "otel")]
+mod otel;
+
+#[cfg(feature = "image")]
+#[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+pub mod preprocess;
+
+#[cfg(feature = "pdf")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pdf")))]
+pub mod pdf;
 
 #[cfg(feature = "grounding")]
 #[cfg_attr(docsrs, doc(cfg(feature = "grounding")))]
@@ -54,44 +84,146 @@ pub mod cache;
 #[cfg_attr(docsrs, doc(cfg(feature = "functions")))]
 pub mod functions;
 
+#[cfg(feature = "functions")]
+#[cfg_attr(docsrs, doc(cfg(feature = "functions")))]
+pub mod agent;
+
 #[cfg(feature = "thinking")]
 #[cfg_attr(docsrs, doc(cfg(feature = "thinking")))]
 pub mod thinking;
 
+#[cfg(feature = "compat-openai")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compat-openai")))]
+pub mod openai;
+
+#[cfg(feature = "thinking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "thinking")))]
+pub mod router;
+
 #[cfg(feature = "streaming")]
 #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
 pub mod streaming;
 
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
+
+#[cfg(feature = "rag")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rag")))]
+pub mod rag;
+
+#[cfg(feature = "eval")]
+#[cfg_attr(docsrs, doc(cfg(feature = "eval")))]
+pub mod eval;
+
+#[cfg(feature = "extract")]
+#[cfg_attr(docsrs, doc(cfg(feature = "extract")))]
+pub mod extract;
+
 // Re-export main types
+pub use audio::AudioChunk;
+pub use best_of::{BestOf, Sampling, ScoredCandidate};
+pub use budget::{OverBudget, TokenBudget};
+pub use chat::{ChatSession, ChatState, ChatUsage};
 pub use client::{GeminiClient, GeminiClientBuilder};
-pub use config::{ApiVersion, GeminiConfig, ModelConfig};
-pub use error::{Error, Result};
+pub use config::{ApiVersion, GeminiConfig, ModelConfig, SecretString};
+pub use error::{ApiErrorCode, ApiErrorResponse, Error, QuotaKind, QuotaViolation, Result};
+#[cfg(feature = "extract")]
+pub use extract::{ExtractOptions, SourceDocument};
+pub use fetch::FetchOptions;
+pub use guardrails::{BannedPatterns, Guardrail, Guardrails, JsonSchemaCheck};
+pub use images::GeneratedImage;
+pub use media::{MediaEstimate, MediaTokenRates};
+pub use memory::{FullHistory, Memory, MessageWindow, Summarizing, TokenWindow};
+pub use metrics::MetricsObserver;
+#[cfg(feature = "prometheus")]
+pub use metrics::PrometheusMetrics;
 pub use models::*;
+#[cfg(feature = "pdf")]
+pub use pdf::{PdfAggregation, PdfAnswer, PdfChunk, PdfChunking};
+#[cfg(feature = "image")]
+pub use preprocess::ImagePreprocessing;
+pub use retry::{DefaultRetryPolicy, RetryPolicy};
+pub use safety::{SafetyAdjustment, SafetyRetry, SafetyRetryOutcome};
+pub use transcribe::{TranscribeOptions, Transcript, TranscriptSegment};
 
 #[cfg(feature = "grounding")]
-pub use grounding::{GroundingBuilder, GroundingConfig, SearchGrounding, UrlContext};
+pub use grounding::{
+    FileSearch, FileSearchStore, GroundingBuilder, GroundingConfig, SearchGrounding, UrlContext,
+};
 
 #[cfg(feature = "caching")]
-pub use cache::{CacheConfig, CacheManager, CachedContent};
+pub use cache::{
+    AutoCachePolicy, CacheConfig, CacheContents, CacheManager, CachePricing, CacheStore,
+    CacheUpdate, CacheUsage, CachedContent, CachedContentBuilder, JsonFileStore,
+    ModelNormalization, SavingsReport,
+};
+
+#[cfg(feature = "functions")]
+pub use agent::{Agent, AgentBuilder, AgentRun, StopReason};
+
+#[cfg(all(feature = "functions", feature = "streaming"))]
+pub use agent::AgentEvent;
 
 #[cfg(feature = "functions")]
-pub use functions::{FunctionBuilder, FunctionCall, FunctionDeclaration, FunctionResponse, Tool};
+pub use functions::{
+    FunctionBuilder, FunctionCall, FunctionDeclaration, FunctionResponse, ToolRegistry,
+};
 
 #[cfg(feature = "thinking")]
-pub use thinking::{ThinkingBudget, ThinkingConfig, ThinkingExt};
+pub use router::{Route, RoutedResponse, Router};
+
+#[cfg(feature = "thinking")]
+pub use thinking::{ThinkingBudget, ThinkingConfig, ThinkingExt, ThinkingLevel};
+
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use gemini_rust_macros::gemini_tool;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json;
+
+    /// Schema of a `#[gemini_tool]` parameter that is not a JSON primitive
+    ///
+    /// Fails if the type's schema has no Gemini equivalent, such as a recursive type.
+    pub fn property_schema<T: schemars::JsonSchema>(
+        description: Option<String>,
+    ) -> crate::Result<crate::functions::PropertySchema> {
+        let mut schema: crate::functions::PropertySchema =
+            crate::models::ResponseSchema::from_type::<T>()
+                .map_err(|e| {
+                    crate::Error::SchemaValidation(format!(
+                        "#[gemini_tool] parameter type {} cannot be declared: {}",
+                        std::any::type_name::<T>(),
+                        e
+                    ))
+                })?
+                .into();
+        if description.is_some() {
+            schema.description = description;
+        }
+        Ok(schema)
+    }
+}
 
 /// Prelude module for convenient imports
 pub mod prelude {
     pub use crate::{
         Content, GeminiClient, GeminiClientBuilder, GenerateContentRequest,
-        GenerateContentResponse, GenerationConfig, Part, ResponseSchema, Result, Role, SchemaType,
+        GenerateContentResponse, GenerationConfig, IntoContents, Part, ResponseSchema, Result,
+        Role, SchemaType, Tool,
     };
 
     #[cfg(feature = "grounding")]
     pub use crate::grounding::GroundingBuilder;
 
+    #[cfg(feature = "grounding")]
+    pub use crate::grounding::GroundingExt;
+
     #[cfg(feature = "functions")]
-    pub use crate::functions::{FunctionBuilder, Tool};
+    pub use crate::functions::FunctionBuilder;
 
     #[cfg(feature = "thinking")]
     pub use crate::thinking::ThinkingExt;
@@ -0,0 +1,165 @@
+//! Redacted debug logging of request and response bodies
+//!
+//! Enabled with [`LoggingConfig::log_bodies`](crate::config::LoggingConfig::log_bodies).
+
+use reqwest::{RequestBuilder, StatusCode, Url};
+use serde_json::Value;
+use tracing::debug;
+
+/// Placeholder for redacted secrets
+const REDACTED: &str = "[REDACTED]";
+
+/// Whether a JSON object key holds a credential
+///
+/// A bare `key` is left alone: it is a common name for ordinary fields in prompts and
+/// structured output.
+fn is_sensitive(key: &str) -> bool {
+    let normalized: String = key
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    matches!(
+        normalized.as_str(),
+        "apikey" | "xgoogapikey" | "authorization" | "accesstoken"
+    )
+}
+
+/// Whether a query parameter holds a credential, including the API's `key` parameter
+fn is_sensitive_param(name: &str) -> bool {
+    name.eq_ignore_ascii_case("key") || is_sensitive(name)
+}
+
+/// Copy a JSON value with secrets hidden, inline data summarized and long strings truncated
+///
+/// Values under credential keys (`apiKey`, `authorization`, `access_token`, ...) become
+/// `[REDACTED]`; other fields, including ones named `key`, are kept.
+/// The `data` of `inlineData` parts is replaced by its decoded size, keeping the MIME type.
+/// Strings longer than `max_text_length` characters are cut and marked with how much was
+/// dropped.
+pub fn redact(value: &Value, max_text_length: usize) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| {
+                    let value = if is_sensitive(key) {
+                        Value::String(REDACTED.to_string())
+                    } else if key == "inlineData" || key == "inline_data" {
+                        summarize_inline_data(value)
+                    } else {
+                        redact(value, max_text_length)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| redact(item, max_text_length))
+                .collect(),
+        ),
+        Value::String(text) => Value::String(truncate(text, max_text_length)),
+        other => other.clone(),
+    }
+}
+
+fn summarize_inline_data(value: &Value) -> Value {
+    let mut summary = value.clone();
+    if let Some(Value::String(data)) = summary.get("data") {
+        let padding = data.bytes().rev().take_while(|b| *b == b'=').count();
+        let size = (data.len() / 4 * 3).saturating_sub(padding);
+        summary["data"] = Value::String(format!("[{} bytes]", size));
+    }
+    summary
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!(
+            "{}... [{} more chars]",
+            &text[..end],
+            text[end..].chars().count()
+        ),
+        None => text.to_string(),
+    }
+}
+
+/// The URL with credential query parameters hidden
+fn redact_url(url: &Url) -> String {
+    let mut redacted = url.clone();
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if is_sensitive_param(&name) {
+                REDACTED.to_string()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    if !pairs.is_empty() {
+        redacted.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    redacted.to_string()
+}
+
+/// Redact and render a body, falling back to its length when it is not JSON
+fn render(body: &[u8], max_text_length: usize) -> String {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(json) => redact(&json, max_text_length).to_string(),
+        Err(_) => format!("<{} bytes, not JSON>", body.len()),
+    }
+}
+
+/// Log an outgoing request
+pub(crate) fn log_request(request: &RequestBuilder, max_text_length: usize) {
+    let Some(request) = request.try_clone().and_then(|request| request.build().ok()) else {
+        return;
+    };
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(|body| render(body, max_text_length))
+        .unwrap_or_default();
+    debug!(
+        method = %request.method(),
+        url = %redact_url(request.url()),
+        body = %body,
+        "Gemini request"
+    );
+}
+
+/// Log a response body
+pub(crate) fn log_response(status: StatusCode, body: &[u8], max_text_length: usize) {
+    debug!(
+        status = status.as_u16(),
+        body = %render(body, max_text_length),
+        "Gemini response"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_key_parameter_is_redacted() {
+        let url = Url::parse("https://example.com/v1/models?key=secret&alt=sse").unwrap();
+        assert_eq!(
+            redact_url(&url),
+            "https://example.com/v1/models?key=%5BREDACTED%5D&alt=sse"
+        );
+    }
+
+    #[test]
+    fn json_key_field_is_kept() {
+        let value = serde_json::json!({ "key": "C major", "api_key": "secret" });
+        assert_eq!(
+            redact(&value, 100),
+            serde_json::json!({ "key": "C major", "api_key": REDACTED })
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Media token accounting
+//!
+//! Gemini bills images, audio and video at fixed rates rather than by size: an image costs
+//! a fixed number of tokens per tile, audio a fixed number per second and video a fixed
+//! number per sampled frame. [`MediaTokenRates`] applies those rates offline, and
+//! [`MediaEstimate`] adds up a planned prompt by modality and compares it with the
+//! `prompt_tokens_details` the API reports afterwards.
+//!
+//! ```rust
+//! use gemini_rust::media::MediaEstimate;
+//! use std::time::Duration;
+//!
+//! let estimate = MediaEstimate::new()
+//!     .text("Describe these")
+//!     .image(1024, 768)
+//!     .audio(Duration::from_secs(30));
+//! assert_eq!(estimate.image_tokens, 516);
+//! assert_eq!(estimate.audio_tokens, 960);
+//! ```
+
+use crate::models::{Modality, UsageMetadata};
+use std::time::Duration;
+
+/// Per-unit token costs of media
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaTokenRates {
+    /// Images with both sides at most this many pixels count as a single tile
+    pub small_image_max: u32,
+    /// Side length of the square tiles larger images are cut into
+    pub tile_size: u32,
+    /// Tokens per image tile
+    pub tokens_per_tile: u32,
+    /// Tokens per second of audio
+    pub audio_tokens_per_second: u32,
+    /// Tokens per sampled video frame
+    pub tokens_per_video_frame: u32,
+}
+
+impl Default for MediaTokenRates {
+    /// Gemini's published rates at the default media resolution
+    fn default() -> Self {
+        Self {
+            small_image_max: 384,
+            tile_size: 768,
+            tokens_per_tile: 258,
+            audio_tokens_per_second: 32,
+            tokens_per_video_frame: 258,
+        }
+    }
+}
+
+impl MediaTokenRates {
+    /// Tokens for an image of `width` x `height` pixels
+    pub fn image(&self, width: u32, height: u32) -> u64 {
+        if width <= self.small_image_max && height <= self.small_image_max {
+            return self.tokens_per_tile as u64;
+        }
+        let tile = self.tile_size.max(1);
+        let tiles = width.div_ceil(tile) as u64 * height.div_ceil(tile) as u64;
+        tiles * self.tokens_per_tile as u64
+    }
+
+    /// Tokens for `duration` of audio
+    pub fn audio(&self, duration: Duration) -> u64 {
+        (duration.as_secs_f64() * self.audio_tokens_per_second as f64).ceil() as u64
+    }
+
+    /// Tokens for the frames of a video sampled at `fps` frames per second
+    ///
+    /// The audio track is counted separately with [`MediaTokenRates::audio`].
+    pub fn video_frames(&self, duration: Duration, fps: f64) -> u64 {
+        let frames = (duration.as_secs_f64() * fps.max(0.0)).ceil() as u64;
+        frames * self.tokens_per_video_frame as u64
+    }
+}
+
+/// Video frames per second the API samples when a request does not say otherwise
+pub const DEFAULT_VIDEO_FPS: f64 = 1.0;
+
+/// Estimated prompt tokens by modality
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaEstimate {
+    /// Rates used for the estimate
+    pub rates: MediaTokenRates,
+    /// Text tokens, at about four characters per token
+    pub text_tokens: u64,
+    /// Image tokens
+    pub image_tokens: u64,
+    /// Audio tokens, including video soundtracks
+    pub audio_tokens: u64,
+    /// Video frame tokens
+    pub video_tokens: u64,
+}
+
+impl MediaEstimate {
+    /// An empty estimate using the default rates
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty estimate using custom rates
+    pub fn with_rates(rates: MediaTokenRates) -> Self {
+        Self {
+            rates,
+            ..Self::default()
+        }
+    }
+
+    /// Add text
+    pub fn text(mut self, text: &str) -> Self {
+        self.text_tokens += text.len().div_ceil(4) as u64;
+        self
+    }
+
+    /// Add an image of `width` x `height` pixels
+    pub fn image(mut self, width: u32, height: u32) -> Self {
+        self.image_tokens += self.rates.image(width, height);
+        self
+    }
+
+    /// Add an audio clip
+    pub fn audio(mut self, duration: Duration) -> Self {
+        self.audio_tokens += self.rates.audio(duration);
+        self
+    }
+
+    /// Add a video with a soundtrack, sampled at [`DEFAULT_VIDEO_FPS`]
+    pub fn video(self, duration: Duration) -> Self {
+        self.video_at(duration, DEFAULT_VIDEO_FPS, true)
+    }
+
+    /// Add a video sampled at `fps`, counting its soundtrack if `with_audio` is set
+    pub fn video_at(mut self, duration: Duration, fps: f64, with_audio: bool) -> Self {
+        self.video_tokens += self.rates.video_frames(duration, fps);
+        if with_audio {
+            self.audio_tokens += self.rates.audio(duration);
+        }
+        self
+    }
+
+    /// Estimated prompt tokens across all modalities
+    pub fn total(&self) -> u64 {
+        self.text_tokens + self.image_tokens + self.audio_tokens + self.video_tokens
+    }
+
+    /// Estimated tokens for one modality; zero for modalities not estimated
+    pub fn tokens_for(&self, modality: Modality) -> u64 {
+        match modality {
+            Modality::Text => self.text_tokens,
+            Modality::Image => self.image_tokens,
+            Modality::Audio => self.audio_tokens,
+            Modality::Video => self.video_tokens,
+            _ => 0,
+        }
+    }
+
+    /// Compare the estimate with the prompt token breakdown of a response
+    ///
+    /// Returns one entry per modality that was estimated or reported. Modalities missing
+    /// from `prompt_tokens_details` count as zero actual tokens.
+    pub fn reconcile(&self, usage: &UsageMetadata) -> Vec<ModalityReconciliation> {
+        [
+            Modality::Text,
+            Modality::Image,
+            Modality::Audio,
+            Modality::Video,
+            Modality::Document,
+        ]
+        .into_iter()
+        .filter_map(|modality| {
+            let estimated = self.tokens_for(modality);
+            let actual = usage.prompt_tokens_for(modality);
+            if estimated == 0 && actual.is_none() {
+                return None;
+            }
+            Some(ModalityReconciliation {
+                modality,
+                estimated,
+                actual: actual.unwrap_or(0).max(0) as u64,
+            })
+        })
+        .collect()
+    }
+}
+
+/// Estimated and reported prompt tokens for one modality
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModalityReconciliation {
+    /// The modality compared
+    pub modality: Modality,
+    /// Tokens estimated before sending
+    pub estimated: u64,
+    /// Tokens the API reported
+    pub actual: u64,
+}
+
+impl ModalityReconciliation {
+    /// Actual minus estimated tokens; positive when the estimate was too low
+    pub fn difference(&self) -> i64 {
+        self.actual as i64 - self.estimated as i64
+    }
+
+    /// Actual tokens as a fraction of the estimate, `None` if nothing was estimated
+    pub fn ratio(&self) -> Option<f64> {
+        (self.estimated > 0).then(|| self.actual as f64 / self.estimated as f64)
+    }
+}
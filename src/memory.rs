@@ -0,0 +1,493 @@
+//! Conversation history strategies
+//!
+//! A [`Memory`] decides which past turns are sent with each request. [`FullHistory`] keeps
+//! everything; [`MessageWindow`] and [`TokenWindow`] drop the oldest exchanges past a limit;
+//! [`Summarizing`] folds them into a running summary written by a cheaper model. The trimming
+//! strategies never drop a pinned exchange.
+
+use crate::{
+    client::GeminiClient,
+    error::{Error, Result},
+    models::{estimate_tokens, Content, GenerateContentRequest, Part, Role},
+};
+use futures::future::BoxFuture;
+use std::ops::Range;
+use tracing::debug;
+
+/// Keeps the conversation history sent with each request
+pub trait Memory: Send + Sync {
+    /// Record a turn
+    fn push(&mut self, content: Content);
+
+    /// Contents to send with the next request, oldest first
+    fn history(&self) -> Vec<Content>;
+
+    /// Forget every turn
+    fn clear(&mut self);
+
+    /// Keep the turn at `index` of [`history`](Self::history), and the exchange it belongs
+    /// to, whatever the strategy would otherwise drop
+    ///
+    /// Returns `false` if the index is out of range or the strategy never drops turns.
+    fn pin(&mut self, index: usize) -> bool {
+        let _ = index;
+        false
+    }
+
+    /// Bring the history within limits that need the API, e.g. to count or summarize turns
+    ///
+    /// Called before each request with the model the request goes to.
+    fn prepare<'a>(
+        &'a mut self,
+        client: &'a GeminiClient,
+        model: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<()>> {
+        let _ = (client, model);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Keeps every turn
+#[derive(Debug, Clone, Default)]
+pub struct FullHistory {
+    contents: Vec<Content>,
+}
+
+impl FullHistory {
+    /// Create an empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Memory for FullHistory {
+    fn push(&mut self, content: Content) {
+        self.contents.push(content);
+    }
+
+    fn history(&self) -> Vec<Content> {
+        self.contents.clone()
+    }
+
+    fn clear(&mut self) {
+        self.contents.clear();
+    }
+}
+
+/// Keeps at most `max_messages` recent turns
+///
+/// Turns are dropped a whole exchange at a time — a user message with the answers and
+/// function calls that follow it — so a function response is never sent without its call.
+/// The latest exchange is always kept, even when it alone exceeds the limit.
+#[derive(Debug, Clone)]
+pub struct MessageWindow {
+    max_messages: usize,
+    turns: Turns,
+}
+
+impl MessageWindow {
+    /// Keep at most `max_messages` turns
+    pub fn new(max_messages: usize) -> Self {
+        Self {
+            max_messages,
+            turns: Turns::default(),
+        }
+    }
+}
+
+impl Memory for MessageWindow {
+    fn push(&mut self, content: Content) {
+        self.turns.push(content);
+        while self.turns.len() > self.max_messages && self.turns.drop_oldest().is_some() {}
+    }
+
+    fn history(&self) -> Vec<Content> {
+        self.turns.contents()
+    }
+
+    fn clear(&mut self) {
+        self.turns.clear();
+    }
+
+    fn pin(&mut self, index: usize) -> bool {
+        self.turns.pin(index)
+    }
+}
+
+/// Keeps as many recent exchanges as fit in `max_tokens`
+///
+/// Tokens are estimated locally at about four characters per token. With
+/// [`count_with_api`](Self::count_with_api), each request first counts the history with the
+/// `countTokens` endpoint and scales the local estimate to match, which is accurate for
+/// non-English text and media at the cost of an extra call. Exchanges are dropped as in
+/// [`MessageWindow`].
+#[derive(Debug, Clone)]
+pub struct TokenWindow {
+    max_tokens: usize,
+    count_with_api: bool,
+    scale: f64,
+    turns: Turns,
+}
+
+impl TokenWindow {
+    /// Keep at most about `max_tokens` tokens of history
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            count_with_api: false,
+            scale: 1.0,
+            turns: Turns::default(),
+        }
+    }
+
+    /// Calibrate the local estimate with the `countTokens` endpoint before each request
+    pub fn count_with_api(mut self) -> Self {
+        self.count_with_api = true;
+        self
+    }
+
+    fn tokens(&self) -> usize {
+        (estimate_tokens(&self.turns.contents()) as f64 * self.scale) as usize
+    }
+
+    fn trim(&mut self) {
+        while self.tokens() > self.max_tokens && self.turns.drop_oldest().is_some() {}
+    }
+}
+
+impl Memory for TokenWindow {
+    fn push(&mut self, content: Content) {
+        self.turns.push(content);
+        self.trim();
+    }
+
+    fn history(&self) -> Vec<Content> {
+        self.turns.contents()
+    }
+
+    fn clear(&mut self) {
+        self.turns.clear();
+        self.scale = 1.0;
+    }
+
+    fn pin(&mut self, index: usize) -> bool {
+        self.turns.pin(index)
+    }
+
+    fn prepare<'a>(
+        &'a mut self,
+        client: &'a GeminiClient,
+        model: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let contents = self.turns.contents();
+            let estimated = estimate_tokens(&contents);
+            if !self.count_with_api || estimated == 0 {
+                return Ok(());
+            }
+            let counted = client.count_tokens(model, contents).await?.total_tokens;
+            self.scale = counted.max(0) as f64 / estimated as f64;
+            debug!(
+                "Counted {} history tokens against an estimate of {}",
+                counted, estimated
+            );
+            self.trim();
+            Ok(())
+        })
+    }
+}
+
+/// Summarizes the oldest exchanges once the history exceeds `max_messages` turns
+///
+/// Before a request, exchanges past the limit are removed and, together with any earlier
+/// summary, condensed by `summary_model` into a new summary. The summary is sent at the start
+/// of the first remaining user turn. If summarizing fails, the history is left as it was.
+#[derive(Debug, Clone)]
+pub struct Summarizing {
+    max_messages: usize,
+    summary_model: String,
+    summary: Option<String>,
+    turns: Turns,
+}
+
+impl Summarizing {
+    /// Summarize with `summary_model` once there are more than `max_messages` turns
+    pub fn new(max_messages: usize, summary_model: impl Into<String>) -> Self {
+        Self {
+            max_messages,
+            summary_model: summary_model.into(),
+            summary: None,
+            turns: Turns::default(),
+        }
+    }
+
+    /// The current summary of the dropped turns
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+}
+
+impl Memory for Summarizing {
+    fn push(&mut self, content: Content) {
+        self.turns.push(content);
+    }
+
+    fn history(&self) -> Vec<Content> {
+        let mut contents = self.turns.contents();
+        if let Some(summary) = &self.summary {
+            let part = Part::from(format!("Summary of the earlier conversation:\n{}", summary));
+            match contents.first_mut() {
+                Some(first) if first.role == Role::User => first.parts.insert(0, part),
+                _ => contents.insert(0, Content::from(part)),
+            }
+        }
+        contents
+    }
+
+    fn clear(&mut self) {
+        self.turns.clear();
+        self.summary = None;
+    }
+
+    fn pin(&mut self, index: usize) -> bool {
+        // The summary is its own turn when the history does not start with a user turn
+        let summary_turn = self.summary.is_some()
+            && self.turns.turns.first().map(|(content, _)| content.role) != Some(Role::User);
+        match index.checked_sub(usize::from(summary_turn)) {
+            Some(index) => self.turns.pin(index),
+            None => false,
+        }
+    }
+
+    fn prepare<'a>(
+        &'a mut self,
+        client: &'a GeminiClient,
+        _model: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut remaining = self.turns.clone();
+            let mut dropped = Vec::new();
+            while remaining.len() > self.max_messages {
+                match remaining.drop_oldest() {
+                    Some(exchange) => dropped.extend(exchange),
+                    None => break,
+                }
+            }
+            if dropped.is_empty() {
+                return Ok(());
+            }
+
+            let mut prompt = String::from(
+                "Summarize the following conversation in a few sentences. Keep facts, \
+                 decisions and open questions that later turns may refer to.\n\n",
+            );
+            if let Some(summary) = &self.summary {
+                prompt.push_str(&format!("Earlier summary:\n{}\n\n", summary));
+            }
+            prompt.push_str(&transcript(&dropped));
+
+            let response = client
+                .generate_content(
+                    Some(&self.summary_model),
+                    GenerateContentRequest::new(prompt),
+                )
+                .await?;
+            let summary = response.text().ok_or_else(|| {
+                Error::InvalidResponse("Summary response contains no text".to_string())
+            })?;
+            debug!("Summarized {} turns", dropped.len());
+            self.summary = Some(summary);
+            self.turns = remaining;
+            Ok(())
+        })
+    }
+}
+
+/// Render turns as plain text for summarization
+fn transcript(contents: &[Content]) -> String {
+    let mut lines = Vec::new();
+    for content in contents {
+        let speaker = match content.role {
+            Role::Model => "Model",
+            _ => "User",
+        };
+        for part in &content.parts {
+            let line = match part {
+                Part::Text { text, .. } => text.clone(),
+                #[cfg(feature = "functions")]
+                Part::FunctionCall { function_call, .. } => format!(
+                    "[called {} with {}]",
+                    function_call.name,
+                    serde_json::to_string(&function_call.args).unwrap_or_default()
+                ),
+                #[cfg(feature = "functions")]
+                Part::FunctionResponse { function_response } => format!(
+                    "[{} returned {}]",
+                    function_response.name, function_response.response
+                ),
+                _ => continue,
+            };
+            lines.push(format!("{}: {}", speaker, line));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Drop the oldest exchanges of `contents` until `fits` accepts them
+///
+/// The latest exchange is always kept; `None` if even that does not fit.
+pub(crate) fn trim_to_fit(
+    contents: Vec<Content>,
+    fits: impl Fn(&[Content]) -> bool,
+) -> Option<Vec<Content>> {
+    let mut turns = Turns::default();
+    for content in contents {
+        turns.push(content);
+    }
+    loop {
+        let contents = turns.contents();
+        if fits(&contents) {
+            return Some(contents);
+        }
+        turns.drop_oldest()?;
+    }
+}
+
+/// Stored turns with their pinned flags
+#[derive(Debug, Clone, Default)]
+struct Turns {
+    turns: Vec<(Content, bool)>,
+}
+
+impl Turns {
+    fn push(&mut self, content: Content) {
+        self.turns.push((content, false));
+    }
+
+    fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    fn contents(&self) -> Vec<Content> {
+        self.turns
+            .iter()
+            .map(|(content, _)| content.clone())
+            .collect()
+    }
+
+    fn clear(&mut self) {
+        self.turns.clear();
+    }
+
+    fn pin(&mut self, index: usize) -> bool {
+        match self.turns.get_mut(index) {
+            Some((_, pinned)) => {
+                *pinned = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Index ranges of the exchanges, oldest first
+    fn exchanges(&self) -> Vec<Range<usize>> {
+        let mut starts: Vec<usize> = self
+            .turns
+            .iter()
+            .enumerate()
+            .filter(|(index, (content, _))| *index == 0 || starts_exchange(content))
+            .map(|(index, _)| index)
+            .collect();
+        starts.push(self.turns.len());
+        starts.windows(2).map(|pair| pair[0]..pair[1]).collect()
+    }
+
+    /// Remove the oldest unpinned exchange other than the latest one
+    fn drop_oldest(&mut self) -> Option<Vec<Content>> {
+        let exchanges = self.exchanges();
+        let (_, older) = exchanges.split_last()?;
+        let range = older
+            .iter()
+            .find(|range| {
+                !self.turns[(*range).clone()]
+                    .iter()
+                    .any(|(_, pinned)| *pinned)
+            })?
+            .clone();
+        Some(
+            self.turns
+                .drain(range)
+                .map(|(content, _)| content)
+                .collect(),
+        )
+    }
+}
+
+/// Whether a history may start at this turn: a user message that is not a function response
+fn starts_exchange(content: &Content) -> bool {
+    content.role == Role::User && !content.parts.iter().any(is_function_response)
+}
+
+fn is_function_response(part: &Part) -> bool {
+    #[cfg(feature = "functions")]
+    if matches!(part, Part::FunctionResponse { .. }) {
+        return true;
+    }
+    let _ = part;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_text(content: &Content) -> &str {
+        match &content.parts[0] {
+            Part::Text { text, .. } => text,
+            _ => panic!("Expected a text part"),
+        }
+    }
+
+    fn exchange(memory: &mut impl Memory, question: &str) {
+        memory.push(Content::user(question));
+        memory.push(Content::model(format!("answer to {}", question)));
+    }
+
+    #[test]
+    fn message_window_drops_whole_exchanges_except_pinned() {
+        let mut memory = MessageWindow::new(4);
+        exchange(&mut memory, "one");
+        assert!(memory.pin(0));
+        exchange(&mut memory, "two");
+        exchange(&mut memory, "three");
+
+        let history = memory.history();
+        assert_eq!(history.len(), 4);
+        assert_eq!(first_text(&history[0]), "one");
+        assert_eq!(first_text(&history[2]), "three");
+    }
+
+    #[tokio::test]
+    async fn failed_summary_keeps_the_history() {
+        // Nothing listens on a port whose listener was dropped
+        let closed = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = GeminiClient::builder()
+            .api_key("test-key")
+            .base_url(format!("http://{}", closed))
+            .max_retries(1)
+            .build()
+            .unwrap();
+
+        let mut memory = Summarizing::new(2, "gemini-2.0-flash-lite");
+        exchange(&mut memory, "one");
+        exchange(&mut memory, "two");
+        let before = memory.history();
+
+        assert!(memory.prepare(&client, None).await.is_err());
+        assert_eq!(memory.history().len(), before.len());
+        assert!(memory.summary().is_none());
+    }
+}
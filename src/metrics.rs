@@ -0,0 +1,293 @@
+//! Metrics hooks for monitoring client traffic
+//!
+//! Install a [`MetricsObserver`] with
+//! [`GeminiClient::with_metrics_observer`](crate::GeminiClient::with_metrics_observer).
+//! With the `prometheus` feature, [`PrometheusMetrics`] records into a
+//! `prometheus::Registry`.
+
+use crate::{error::Error, models::UsageMetadata};
+use std::time::Duration;
+
+/// Receives events about requests made by a client
+///
+/// All methods default to doing nothing. `operation` is the client method, e.g.
+/// `generate_content` or `stream_generate_content`.
+pub trait MetricsObserver: Send + Sync {
+    /// A request is about to be sent
+    fn request_started(&self, operation: &str, model: &str) {
+        let _ = (operation, model);
+    }
+
+    /// A request completed; for streams, when the stream ends
+    ///
+    /// A stream dropped before its end also counts as completed, with the usage seen so far.
+    fn request_succeeded(
+        &self,
+        operation: &str,
+        model: &str,
+        latency: Duration,
+        usage: Option<&UsageMetadata>,
+    ) {
+        let _ = (operation, model, latency, usage);
+    }
+
+    /// A request failed after any retries; for streams, also a failure mid-stream
+    fn request_failed(&self, operation: &str, model: &str, latency: Duration, error: &Error) {
+        let _ = (operation, model, latency, error);
+    }
+
+    /// A failed attempt is about to be retried
+    fn request_retried(&self, attempt: u32, error: &Error) {
+        let _ = (attempt, error);
+    }
+
+    /// A stream delivered its first chunk
+    fn time_to_first_token(&self, model: &str, elapsed: Duration) {
+        let _ = (model, elapsed);
+    }
+}
+
+#[cfg(feature = "streaming")]
+pub(crate) use observed::ObservedStream;
+
+#[cfg(feature = "streaming")]
+mod observed {
+    use super::MetricsObserver;
+    use crate::{
+        error::Result,
+        models::{GenerateContentResponse, UsageMetadata},
+    };
+    use futures::Stream;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use std::time::Instant;
+
+    /// A response stream reporting first-token latency and its outcome to an observer
+    pub(crate) struct ObservedStream<S> {
+        inner: Pin<Box<S>>,
+        observer: Arc<dyn MetricsObserver>,
+        model: String,
+        started: Instant,
+        usage: Option<UsageMetadata>,
+        first_chunk: bool,
+        finished: bool,
+    }
+
+    impl<S> ObservedStream<S> {
+        pub(crate) fn new(
+            inner: S,
+            observer: Arc<dyn MetricsObserver>,
+            model: String,
+            started: Instant,
+        ) -> Self {
+            Self {
+                inner: Box::pin(inner),
+                observer,
+                model,
+                started,
+                usage: None,
+                first_chunk: true,
+                finished: false,
+            }
+        }
+
+        fn succeed(&mut self) {
+            self.finished = true;
+            self.observer.request_succeeded(
+                "stream_generate_content",
+                &self.model,
+                self.started.elapsed(),
+                self.usage.as_ref(),
+            );
+        }
+    }
+
+    impl<S> Stream for ObservedStream<S>
+    where
+        S: Stream<Item = Result<GenerateContentResponse>>,
+    {
+        type Item = Result<GenerateContentResponse>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            let this = &mut *self;
+            let item = match this.inner.as_mut().poll_next(cx) {
+                Poll::Ready(item) => item,
+                Poll::Pending => return Poll::Pending,
+            };
+            if this.finished {
+                return Poll::Ready(item);
+            }
+            match &item {
+                Some(Ok(chunk)) => {
+                    if this.first_chunk {
+                        this.first_chunk = false;
+                        this.observer
+                            .time_to_first_token(&this.model, this.started.elapsed());
+                    }
+                    if let Some(usage) = &chunk.usage_metadata {
+                        this.usage = Some(usage.clone());
+                    }
+                }
+                Some(Err(error)) => {
+                    this.finished = true;
+                    this.observer.request_failed(
+                        "stream_generate_content",
+                        &this.model,
+                        this.started.elapsed(),
+                        error,
+                    );
+                }
+                None => this.succeed(),
+            }
+            Poll::Ready(item)
+        }
+    }
+
+    impl<S> Drop for ObservedStream<S> {
+        fn drop(&mut self) {
+            if !self.finished {
+                self.succeed();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "prometheus")]
+pub use self::prometheus_metrics::PrometheusMetrics;
+
+#[cfg(feature = "prometheus")]
+mod prometheus_metrics {
+    use super::MetricsObserver;
+    use crate::{
+        error::{Error, Result},
+        models::UsageMetadata,
+    };
+    use prometheus::{
+        HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry,
+    };
+    use std::time::Duration;
+
+    /// Records client metrics into a Prometheus registry
+    ///
+    /// | Metric | Type | Labels |
+    /// |---|---|---|
+    /// | `gemini_requests_total` | counter | `operation`, `model`, `outcome` |
+    /// | `gemini_requests_in_flight` | gauge | `operation`, `model` |
+    /// | `gemini_request_duration_seconds` | histogram | `operation`, `model` |
+    /// | `gemini_tokens_total` | counter | `model`, `kind` (`input`, `output`) |
+    /// | `gemini_retries_total` | counter | |
+    /// | `gemini_time_to_first_token_seconds` | histogram | `model` |
+    #[derive(Clone)]
+    pub struct PrometheusMetrics {
+        requests: IntCounterVec,
+        in_flight: IntGaugeVec,
+        duration: HistogramVec,
+        tokens: IntCounterVec,
+        retries: IntCounter,
+        first_token: HistogramVec,
+    }
+
+    impl PrometheusMetrics {
+        /// Create the metrics and register them with `registry`
+        pub fn new(registry: &Registry) -> Result<Self> {
+            let metrics = Self {
+                requests: IntCounterVec::new(
+                    Opts::new("gemini_requests_total", "Gemini API requests by outcome"),
+                    &["operation", "model", "outcome"],
+                )
+                .map_err(registration_error)?,
+                in_flight: IntGaugeVec::new(
+                    Opts::new("gemini_requests_in_flight", "Gemini API requests in flight"),
+                    &["operation", "model"],
+                )
+                .map_err(registration_error)?,
+                duration: HistogramVec::new(
+                    HistogramOpts::new(
+                        "gemini_request_duration_seconds",
+                        "Gemini API request latency, including retries",
+                    ),
+                    &["operation", "model"],
+                )
+                .map_err(registration_error)?,
+                tokens: IntCounterVec::new(
+                    Opts::new("gemini_tokens_total", "Tokens reported in usage metadata"),
+                    &["model", "kind"],
+                )
+                .map_err(registration_error)?,
+                retries: IntCounter::new("gemini_retries_total", "Retried Gemini API attempts")
+                    .map_err(registration_error)?,
+                first_token: HistogramVec::new(
+                    HistogramOpts::new(
+                        "gemini_time_to_first_token_seconds",
+                        "Time until a stream delivers its first chunk",
+                    ),
+                    &["model"],
+                )
+                .map_err(registration_error)?,
+            };
+
+            registry
+                .register(Box::new(metrics.requests.clone()))
+                .and_then(|_| registry.register(Box::new(metrics.in_flight.clone())))
+                .and_then(|_| registry.register(Box::new(metrics.duration.clone())))
+                .and_then(|_| registry.register(Box::new(metrics.tokens.clone())))
+                .and_then(|_| registry.register(Box::new(metrics.retries.clone())))
+                .and_then(|_| registry.register(Box::new(metrics.first_token.clone())))
+                .map_err(registration_error)?;
+            Ok(metrics)
+        }
+
+        fn finish(&self, operation: &str, model: &str, latency: Duration, outcome: &str) {
+            self.in_flight.with_label_values(&[operation, model]).dec();
+            self.duration
+                .with_label_values(&[operation, model])
+                .observe(latency.as_secs_f64());
+            self.requests
+                .with_label_values(&[operation, model, outcome])
+                .inc();
+        }
+    }
+
+    fn registration_error(error: prometheus::Error) -> Error {
+        Error::Config(format!("Failed to register Prometheus metrics: {}", error))
+    }
+
+    impl MetricsObserver for PrometheusMetrics {
+        fn request_started(&self, operation: &str, model: &str) {
+            self.in_flight.with_label_values(&[operation, model]).inc();
+        }
+
+        fn request_succeeded(
+            &self,
+            operation: &str,
+            model: &str,
+            latency: Duration,
+            usage: Option<&UsageMetadata>,
+        ) {
+            self.finish(operation, model, latency, "success");
+            if let Some(usage) = usage {
+                self.tokens
+                    .with_label_values(&[model, "input"])
+                    .inc_by(usage.prompt_token_count.max(0) as u64);
+                self.tokens
+                    .with_label_values(&[model, "output"])
+                    .inc_by(usage.candidates_token_count.max(0) as u64);
+            }
+        }
+
+        fn request_failed(&self, operation: &str, model: &str, latency: Duration, _: &Error) {
+            self.finish(operation, model, latency, "error");
+        }
+
+        fn request_retried(&self, _: u32, _: &Error) {
+            self.retries.inc();
+        }
+
+        fn time_to_first_token(&self, model: &str, elapsed: Duration) {
+            self.first_token
+                .with_label_values(&[model])
+                .observe(elapsed.as_secs_f64());
+        }
+    }
+}
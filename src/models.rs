@@ -1,6 +1,7 @@
 //! Core data models for the Gemini API
 
-use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Role in a conversation
@@ -19,16 +20,45 @@ pub enum Role {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Part {
+    /// Thought summary part, returned when thoughts are requested
+    #[cfg(feature = "thinking")]
+    Thought {
+        /// Summary of the model's reasoning
+        text: String,
+        /// Always `true` for thought parts
+        thought: bool,
+        /// Opaque signature to send back with the thought in later turns
+        #[serde(
+            rename = "thoughtSignature",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        thought_signature: Option<String>,
+    },
     /// Text content part
     Text {
         /// Text content as a string
         text: String,
+        /// Opaque signature to send back with this part in later turns
+        #[serde(
+            rename = "thoughtSignature",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        thought_signature: Option<String>,
     },
     /// Inline data part (base64 encoded)
     InlineData {
         /// Inline data with base64 encoded content
         #[serde(rename = "inlineData")]
         inline_data: InlineData,
+        /// Opaque signature to send back with this part in later turns
+        #[serde(
+            rename = "thoughtSignature",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        thought_signature: Option<String>,
     },
     /// File data part (file URI reference)
     FileData {
@@ -42,6 +72,13 @@ pub enum Part {
         /// Function call data
         #[serde(rename = "functionCall")]
         function_call: crate::functions::FunctionCall,
+        /// Opaque signature to send back with this part in later turns
+        #[serde(
+            rename = "thoughtSignature",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        thought_signature: Option<String>,
     },
     /// Function response part
     #[cfg(feature = "functions")]
@@ -87,7 +124,7 @@ impl Content {
     pub fn user(text: impl Into<String>) -> Self {
         Self {
             role: Role::User,
-            parts: vec![Part::Text { text: text.into() }],
+            parts: vec![Part::text(text)],
         }
     }
 
@@ -95,7 +132,7 @@ impl Content {
     pub fn model(text: impl Into<String>) -> Self {
         Self {
             role: Role::Model,
-            parts: vec![Part::Text { text: text.into() }],
+            parts: vec![Part::text(text)],
         }
     }
 
@@ -103,16 +140,128 @@ impl Content {
     pub fn system(text: impl Into<String>) -> Self {
         Self {
             role: Role::System,
-            parts: vec![Part::Text { text: text.into() }],
+            parts: vec![Part::text(text)],
         }
     }
 }
 
+impl Part {
+    /// Create a text part
+    pub fn text(text: impl Into<String>) -> Self {
+        Part::Text {
+            text: text.into(),
+            thought_signature: None,
+        }
+    }
+
+    /// Signature the model attached to this part
+    ///
+    /// Gemini 3 rejects a follow-up turn whose echoed function calls lost their signatures,
+    /// so model contents should be sent back unchanged.
+    pub fn thought_signature(&self) -> Option<&str> {
+        match self {
+            #[cfg(feature = "thinking")]
+            Part::Thought {
+                thought_signature, ..
+            } => thought_signature.as_deref(),
+            Part::Text {
+                thought_signature, ..
+            }
+            | Part::InlineData {
+                thought_signature, ..
+            } => thought_signature.as_deref(),
+            #[cfg(feature = "functions")]
+            Part::FunctionCall {
+                thought_signature, ..
+            } => thought_signature.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Create a part referencing an uploaded file by URI
+    pub fn file(mime_type: impl Into<String>, file_uri: impl Into<String>) -> Self {
+        Part::FileData {
+            file_data: FileData {
+                mime_type: mime_type.into(),
+                file_uri: file_uri.into(),
+            },
+        }
+    }
+}
+
+impl From<&str> for Part {
+    fn from(text: &str) -> Self {
+        Part::text(text)
+    }
+}
+
+impl From<String> for Part {
+    fn from(text: String) -> Self {
+        Part::text(text)
+    }
+}
+
+impl From<&str> for Content {
+    fn from(text: &str) -> Self {
+        Content::user(text)
+    }
+}
+
+impl From<String> for Content {
+    fn from(text: String) -> Self {
+        Content::user(text)
+    }
+}
+
+impl From<Part> for Content {
+    fn from(part: Part) -> Self {
+        Self {
+            role: Role::User,
+            parts: vec![part],
+        }
+    }
+}
+
+impl From<Vec<Part>> for Content {
+    fn from(parts: Vec<Part>) -> Self {
+        Self {
+            role: Role::User,
+            parts,
+        }
+    }
+}
+
+/// Conversion into the list of contents sent with a request
+///
+/// Plain strings and parts become a single user turn; a `Vec<Content>` is used as-is.
+pub trait IntoContents {
+    /// Convert into conversation contents
+    fn into_contents(self) -> Vec<Content>;
+}
+
+impl<T: Into<Content>> IntoContents for T {
+    fn into_contents(self) -> Vec<Content> {
+        vec![self.into()]
+    }
+}
+
+impl IntoContents for Vec<Content> {
+    fn into_contents(self) -> Vec<Content> {
+        self
+    }
+}
+
+impl IntoContents for &[Content] {
+    fn into_contents(self) -> Vec<Content> {
+        self.to_vec()
+    }
+}
+
 /// Generation configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerationConfig {
-    /// Controls randomness in output (0.0-1.0)
+    /// Controls randomness in output (0.0-2.0)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
 
@@ -144,6 +293,12 @@ pub struct GenerationConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_schema: Option<ResponseSchema>,
 
+    /// Raw JSON Schema for structured output, passed through unchanged
+    ///
+    /// Mutually exclusive with `response_schema`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_json_schema: Option<serde_json::Value>,
+
     /// Penalty for repeated presence (-2.0 to 2.0)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub presence_penalty: Option<f32>,
@@ -160,12 +315,304 @@ pub struct GenerationConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logprobs: Option<i32>,
 
+    /// Kinds of output the model may return, e.g. text and images
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_modalities: Option<Vec<Modality>>,
+
+    /// Let the model read timestamps in audio-only input, e.g. for transcription
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_timestamp: Option<bool>,
+
     /// Configuration for thinking/reasoning behavior
     #[cfg(feature = "thinking")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thinking_config: Option<crate::thinking::ThinkingConfig>,
 }
 
+impl GenerationConfig {
+    /// Fill every setting left unset with the value from `defaults`
+    pub fn merge_defaults(&mut self, defaults: &GenerationConfig) {
+        fn fill<T: Clone>(value: &mut Option<T>, default: &Option<T>) {
+            if value.is_none() {
+                value.clone_from(default);
+            }
+        }
+
+        fill(&mut self.temperature, &defaults.temperature);
+        fill(&mut self.top_p, &defaults.top_p);
+        fill(&mut self.top_k, &defaults.top_k);
+        fill(&mut self.candidate_count, &defaults.candidate_count);
+        fill(&mut self.max_output_tokens, &defaults.max_output_tokens);
+        fill(&mut self.stop_sequences, &defaults.stop_sequences);
+        fill(&mut self.response_mime_type, &defaults.response_mime_type);
+        // A schema set on either side wins over a default of the other kind
+        if self.response_json_schema.is_none() {
+            fill(&mut self.response_schema, &defaults.response_schema);
+        }
+        if self.response_schema.is_none() {
+            fill(
+                &mut self.response_json_schema,
+                &defaults.response_json_schema,
+            );
+        }
+        fill(&mut self.presence_penalty, &defaults.presence_penalty);
+        fill(&mut self.frequency_penalty, &defaults.frequency_penalty);
+        fill(&mut self.response_logprobs, &defaults.response_logprobs);
+        fill(&mut self.logprobs, &defaults.logprobs);
+        fill(&mut self.response_modalities, &defaults.response_modalities);
+        fill(&mut self.audio_timestamp, &defaults.audio_timestamp);
+        #[cfg(feature = "thinking")]
+        fill(&mut self.thinking_config, &defaults.thinking_config);
+    }
+
+    /// Check the configuration for conflicting settings
+    pub fn validate(&self) -> Result<()> {
+        if self.response_schema.is_some() && self.response_json_schema.is_some() {
+            return Err(Error::SchemaValidation(
+                "response_schema and response_json_schema cannot both be set".to_string(),
+            ));
+        }
+
+        let modalities = self.response_modalities.as_deref().unwrap_or_default();
+        if let Some(modality) = modalities
+            .iter()
+            .find(|modality| !modality.is_response_modality())
+        {
+            return Err(Error::Config(format!(
+                "{:?} is not a response modality",
+                modality
+            )));
+        }
+        if modalities.contains(&Modality::Audio) && modalities.len() > 1 {
+            return Err(Error::Config(
+                "audio output cannot be combined with other response modalities".to_string(),
+            ));
+        }
+
+        let image_output = modalities.contains(&Modality::Image);
+        let structured_output = self.response_schema.is_some()
+            || self.response_json_schema.is_some()
+            || self
+                .response_mime_type
+                .as_deref()
+                .is_some_and(|mime| mime != "text/plain");
+        if image_output && structured_output {
+            return Err(Error::Config(
+                "image output cannot be combined with a response schema or MIME type".to_string(),
+            ));
+        }
+
+        #[cfg(feature = "thinking")]
+        if let Some(thinking) = &self.thinking_config {
+            thinking.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Start building a configuration whose ranges are checked by
+    /// [`GenerationConfigBuilder::build`]
+    pub fn builder() -> GenerationConfigBuilder {
+        GenerationConfigBuilder::default()
+    }
+
+    /// Every setting outside its allowed range
+    ///
+    /// `output_token_limit` is the model's limit, from [`ModelInfo::output_token_limit`];
+    /// `max_output_tokens` is only checked against it when it is known.
+    pub fn violations(&self, output_token_limit: Option<i32>) -> Vec<String> {
+        let mut violations = Vec::new();
+        let mut check = |name: &str, value: Option<f32>, min: f32, max: f32| {
+            if let Some(value) = value {
+                if !(min..=max).contains(&value) {
+                    violations.push(format!(
+                        "{} must be between {} and {}, got {}",
+                        name, min, max, value
+                    ));
+                }
+            }
+        };
+        check("temperature", self.temperature, 0.0, 2.0);
+        check("top_p", self.top_p, 0.0, 1.0);
+        check("presence_penalty", self.presence_penalty, -2.0, 2.0);
+        check("frequency_penalty", self.frequency_penalty, -2.0, 2.0);
+
+        for (name, value) in [
+            ("top_k", self.top_k),
+            ("candidate_count", self.candidate_count),
+            ("max_output_tokens", self.max_output_tokens),
+        ] {
+            if let Some(value) = value.filter(|value| *value < 1) {
+                violations.push(format!("{} must be at least 1, got {}", name, value));
+            }
+        }
+        if let (Some(max_tokens), Some(limit)) = (self.max_output_tokens, output_token_limit) {
+            if max_tokens > limit {
+                violations.push(format!(
+                    "max_output_tokens must be at most the model's limit of {}, got {}",
+                    limit, max_tokens
+                ));
+            }
+        }
+        if let Some(logprobs) = self
+            .logprobs
+            .filter(|logprobs| !(0..=20).contains(logprobs))
+        {
+            violations.push(format!(
+                "logprobs must be between 0 and 20, got {}",
+                logprobs
+            ));
+        }
+        violations
+    }
+}
+
+/// Builder for a [`GenerationConfig`] checked against the API's ranges
+///
+/// Settings left unset are filled from any [`defaults`](Self::defaults), e.g. a client's
+/// generation profile, before the checks run. [`build`](Self::build) reports every
+/// violation at once rather than stopping at the first.
+#[derive(Debug, Clone, Default)]
+pub struct GenerationConfigBuilder {
+    config: GenerationConfig,
+    defaults: Vec<GenerationConfig>,
+    output_token_limit: Option<i32>,
+}
+
+impl GenerationConfigBuilder {
+    /// Set the sampling temperature (0.0-2.0)
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.config.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the nucleus sampling probability (0.0-1.0)
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.config.top_p = Some(top_p);
+        self
+    }
+
+    /// Set the number of tokens top-k sampling considers
+    pub fn top_k(mut self, top_k: i32) -> Self {
+        self.config.top_k = Some(top_k);
+        self
+    }
+
+    /// Set the number of response candidates
+    pub fn candidate_count(mut self, count: i32) -> Self {
+        self.config.candidate_count = Some(count);
+        self
+    }
+
+    /// Set the most tokens to generate
+    pub fn max_output_tokens(mut self, tokens: i32) -> Self {
+        self.config.max_output_tokens = Some(tokens);
+        self
+    }
+
+    /// Add a sequence that stops generation
+    pub fn stop_sequence(mut self, sequence: impl Into<String>) -> Self {
+        self.config
+            .stop_sequences
+            .get_or_insert_with(Vec::new)
+            .push(sequence.into());
+        self
+    }
+
+    /// Set the response MIME type, e.g. `application/json`
+    pub fn response_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.config.response_mime_type = Some(mime_type.into());
+        self
+    }
+
+    /// Constrain the output to `schema`
+    pub fn response_schema(mut self, schema: ResponseSchema) -> Self {
+        self.config.response_schema = Some(schema);
+        self
+    }
+
+    /// Constrain the output to a raw JSON Schema
+    pub fn response_json_schema(mut self, schema: serde_json::Value) -> Self {
+        self.config.response_json_schema = Some(schema);
+        self
+    }
+
+    /// Set the presence penalty (-2.0-2.0)
+    pub fn presence_penalty(mut self, penalty: f32) -> Self {
+        self.config.presence_penalty = Some(penalty);
+        self
+    }
+
+    /// Set the frequency penalty (-2.0-2.0)
+    pub fn frequency_penalty(mut self, penalty: f32) -> Self {
+        self.config.frequency_penalty = Some(penalty);
+        self
+    }
+
+    /// Return the log probabilities of the `top` most likely tokens at each step (0-20)
+    pub fn logprobs(mut self, top: i32) -> Self {
+        self.config.response_logprobs = Some(true);
+        self.config.logprobs = Some(top);
+        self
+    }
+
+    /// Set the kinds of output the model may return
+    pub fn response_modalities(mut self, modalities: impl IntoIterator<Item = Modality>) -> Self {
+        self.config.response_modalities = Some(modalities.into_iter().collect());
+        self
+    }
+
+    /// Let the model read timestamps in audio-only input
+    pub fn audio_timestamp(mut self, enabled: bool) -> Self {
+        self.config.audio_timestamp = Some(enabled);
+        self
+    }
+
+    /// Set the thinking configuration
+    #[cfg(feature = "thinking")]
+    pub fn thinking_config(mut self, thinking: crate::thinking::ThinkingConfig) -> Self {
+        self.config.thinking_config = Some(thinking);
+        self
+    }
+
+    /// Fill settings left unset from `defaults`
+    ///
+    /// May be called several times; earlier defaults take precedence over later ones.
+    pub fn defaults(mut self, defaults: &GenerationConfig) -> Self {
+        self.defaults.push(defaults.clone());
+        self
+    }
+
+    /// Check `max_output_tokens` against this model's output limit
+    pub fn model(mut self, model: &ModelInfo) -> Self {
+        self.output_token_limit = model.output_token_limit;
+        self
+    }
+
+    /// Check `max_output_tokens` against this output limit
+    pub fn output_token_limit(mut self, limit: i32) -> Self {
+        self.output_token_limit = Some(limit);
+        self
+    }
+
+    /// Merge the defaults and check the result
+    ///
+    /// Fails with an [`Error::Config`] listing every out-of-range setting, or with the
+    /// first conflict [`GenerationConfig::validate`] finds.
+    pub fn build(self) -> Result<GenerationConfig> {
+        let mut config = self.config;
+        for defaults in &self.defaults {
+            config.merge_defaults(defaults);
+        }
+        let violations = config.violations(self.output_token_limit);
+        if !violations.is_empty() {
+            return Err(Error::Config(violations.join("; ")));
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
 /// Response schema for structured output
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponseSchema {
@@ -186,7 +633,7 @@ pub struct ResponseSchema {
     pub nullable: Option<bool>,
 
     /// Allowed enum values
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
     pub enum_values: Option<Vec<String>>,
 
     /// Properties for object types
@@ -214,6 +661,107 @@ pub struct ResponseSchema {
     pub max_items: Option<i32>,
 }
 
+impl ResponseSchema {
+    /// Check a JSON value against this schema, returning every violation found
+    pub fn validate(&self, value: &serde_json::Value) -> Vec<String> {
+        let mut violations = Vec::new();
+        self.validate_value("$", value, &mut violations);
+        violations.sort();
+        violations
+    }
+
+    fn validate_value(&self, path: &str, value: &serde_json::Value, violations: &mut Vec<String>) {
+        use serde_json::Value;
+
+        let type_matches = match (self.schema_type, value) {
+            (_, Value::Null) => {
+                if !self.nullable.unwrap_or(false) {
+                    violations.push(format!("`{}` must not be null", path));
+                }
+                return;
+            }
+            (SchemaType::String, Value::String(_)) => true,
+            (SchemaType::Integer, Value::Number(n)) => {
+                n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            (SchemaType::Number, Value::Number(_)) => true,
+            (SchemaType::Boolean, Value::Bool(_)) => true,
+            (SchemaType::Array, Value::Array(_)) => true,
+            (SchemaType::Object, Value::Object(_)) => true,
+            _ => false,
+        };
+        if !type_matches {
+            violations.push(format!(
+                "`{}` should be of type {} but was {}",
+                path,
+                self.schema_type.as_str(),
+                value
+            ));
+            return;
+        }
+
+        if let (Some(allowed), Value::String(s)) = (&self.enum_values, value) {
+            if !allowed.contains(s) {
+                violations.push(format!(
+                    "`{}` must be one of {:?} but was {:?}",
+                    path, allowed, s
+                ));
+            }
+        }
+
+        match value {
+            Value::Array(items) => {
+                let len = items.len() as i64;
+                if self.min_items.is_some_and(|min| len < i64::from(min)) {
+                    violations.push(format!(
+                        "`{}` needs at least {} items but has {}",
+                        path,
+                        self.min_items.unwrap_or_default(),
+                        len
+                    ));
+                }
+                if self.max_items.is_some_and(|max| len > i64::from(max)) {
+                    violations.push(format!(
+                        "`{}` allows at most {} items but has {}",
+                        path,
+                        self.max_items.unwrap_or_default(),
+                        len
+                    ));
+                }
+                if let Some(schema) = &self.items {
+                    for (i, item) in items.iter().enumerate() {
+                        schema.validate_value(&format!("{}[{}]", path, i), item, violations);
+                    }
+                }
+            }
+            Value::Object(fields) => {
+                for name in self.required.iter().flatten() {
+                    if !fields.contains_key(name) {
+                        violations.push(format!("missing required property `{}.{}`", path, name));
+                    }
+                }
+                if let Some(properties) = &self.properties {
+                    for (name, field) in fields {
+                        if let Some(schema) = properties.get(name) {
+                            schema.validate_value(&format!("{}.{}", path, name), field, violations);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Derive a response schema from a Rust type implementing `schemars::JsonSchema`
+    ///
+    /// Property ordering follows the declaration order of struct fields.
+    #[cfg(feature = "schemars")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
+    pub fn from_type<T: schemars::JsonSchema>() -> Result<Self> {
+        crate::schema::response_schema_for::<T>()
+    }
+}
+
 /// JSON schema data types
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -232,6 +780,20 @@ pub enum SchemaType {
     Object,
 }
 
+impl SchemaType {
+    /// Convert the schema type to its JSON schema name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchemaType::String => "string",
+            SchemaType::Integer => "integer",
+            SchemaType::Number => "number",
+            SchemaType::Boolean => "boolean",
+            SchemaType::Array => "array",
+            SchemaType::Object => "object",
+        }
+    }
+}
+
 /// Safety settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SafetySetting {
@@ -242,7 +804,7 @@ pub struct SafetySetting {
 }
 
 /// Categories of harmful content
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum HarmCategory {
     /// Hate speech content
     #[serde(rename = "HARM_CATEGORY_HATE_SPEECH")]
@@ -259,7 +821,7 @@ pub enum HarmCategory {
 }
 
 /// Thresholds for blocking harmful content
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HarmBlockThreshold {
     /// Block no content
     #[serde(rename = "BLOCK_NONE")]
@@ -275,6 +837,124 @@ pub enum HarmBlockThreshold {
     BlockLowAndAbove,
 }
 
+/// A tool the model may use while generating a response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Tool {
+    /// Function declarations
+    #[cfg(feature = "functions")]
+    FunctionDeclarations {
+        /// List of function declarations available to the model
+        #[serde(rename = "functionDeclarations")]
+        function_declarations: Vec<crate::functions::FunctionDeclaration>,
+    },
+    /// Google Search tool
+    #[cfg(feature = "grounding")]
+    GoogleSearch(#[serde(with = "google_search_key")] crate::grounding::SearchGrounding),
+    /// URL Context tool
+    #[cfg(feature = "grounding")]
+    UrlContext(#[serde(with = "url_context_key")] crate::grounding::UrlContext),
+    /// Google Maps tool
+    #[cfg(feature = "grounding")]
+    GoogleMaps(#[serde(with = "google_maps_key")] crate::grounding::GoogleMaps),
+    /// File Search tool
+    #[cfg(feature = "grounding")]
+    FileSearch(#[serde(with = "file_search_key")] crate::grounding::FileSearch),
+    /// Retrieval from a Vertex AI Search datastore (Vertex AI only)
+    #[cfg(feature = "grounding")]
+    Retrieval(#[serde(with = "retrieval_key")] crate::grounding::Retrieval),
+    /// Code execution tool
+    CodeExecution {
+        /// Configuration for code execution
+        #[serde(rename = "codeExecution")]
+        code_execution: CodeExecutionConfig,
+    },
+}
+
+/// Generate a serde `with` module that nests a tool config under its API key,
+/// e.g. `{"googleSearch": {...}}`
+#[cfg(feature = "grounding")]
+macro_rules! keyed_tool {
+    ($module:ident, $key:literal, $ty:ty) => {
+        mod $module {
+            use serde::{ser::SerializeMap, Deserialize, Deserializer, Serializer};
+
+            pub fn serialize<S: Serializer>(
+                value: &$ty,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry($key, value)?;
+                map.end()
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<$ty, D::Error> {
+                #[derive(Deserialize)]
+                struct Keyed {
+                    #[serde(rename = $key)]
+                    value: $ty,
+                }
+
+                Keyed::deserialize(deserializer).map(|keyed| keyed.value)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "grounding")]
+keyed_tool!(
+    google_search_key,
+    "googleSearch",
+    crate::grounding::SearchGrounding
+);
+#[cfg(feature = "grounding")]
+keyed_tool!(url_context_key, "urlContext", crate::grounding::UrlContext);
+#[cfg(feature = "grounding")]
+keyed_tool!(google_maps_key, "googleMaps", crate::grounding::GoogleMaps);
+#[cfg(feature = "grounding")]
+keyed_tool!(file_search_key, "fileSearch", crate::grounding::FileSearch);
+#[cfg(feature = "grounding")]
+keyed_tool!(retrieval_key, "retrieval", crate::grounding::Retrieval);
+
+impl Tool {
+    /// Whether this is a function declarations tool rather than a built-in tool
+    pub fn is_function_declarations(&self) -> bool {
+        #[cfg(feature = "functions")]
+        if let Tool::FunctionDeclarations { .. } = self {
+            return true;
+        }
+        false
+    }
+
+    /// Create a code execution tool
+    pub fn code_execution() -> Self {
+        Tool::CodeExecution {
+            code_execution: CodeExecutionConfig::default(),
+        }
+    }
+}
+
+/// Code execution configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CodeExecutionConfig {}
+
+/// Tool configuration for controlling function calling and retrieval behavior
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolConfig {
+    /// Function calling configuration
+    #[cfg(feature = "functions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_calling_config: Option<crate::functions::FunctionCallingConfig>,
+
+    /// Retrieval configuration, e.g. the user location for Google Maps grounding
+    #[cfg(feature = "grounding")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_config: Option<crate::grounding::RetrievalConfig>,
+}
+
 /// Main request structure
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -286,15 +966,13 @@ pub struct GenerateContentRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub system_instruction: Option<Content>,
 
-    /// Available tools for function calling
-    #[cfg(feature = "functions")]
+    /// Available tools (functions, grounding, code execution)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tools: Option<Vec<crate::functions::Tool>>,
+    pub tools: Option<Vec<Tool>>,
 
     /// Configuration for tool usage
-    #[cfg(feature = "functions")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tool_config: Option<crate::functions::ToolConfig>,
+    pub tool_config: Option<ToolConfig>,
 
     /// Safety settings for content filtering
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -307,13 +985,157 @@ pub struct GenerateContentRequest {
     /// Reference to cached content
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cached_content: Option<String>,
+
+    /// Named generation profile from the client's `ModelConfig` to apply; not sent to the API
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+impl GenerateContentRequest {
+    /// Create a request from anything convertible into contents
+    pub fn new(contents: impl IntoContents) -> Self {
+        Self {
+            contents: contents.into_contents(),
+            ..Default::default()
+        }
+    }
+
+    /// Apply a named generation profile from the client configuration
+    pub fn with_profile(mut self, name: impl Into<String>) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Move system-role contents into `system_instruction`
+    ///
+    /// The API rejects `role: system` inside `contents`, so any such entries are removed and
+    /// their parts appended (in order) to the system instruction, creating it if needed.
+    pub fn normalize(&mut self) {
+        if !self.contents.iter().any(|c| c.role == Role::System) {
+            return;
+        }
+
+        let (system, contents): (Vec<Content>, Vec<Content>) = std::mem::take(&mut self.contents)
+            .into_iter()
+            .partition(|c| c.role == Role::System);
+        self.contents = contents;
+
+        let instruction = self.system_instruction.get_or_insert_with(|| Content {
+            role: Role::System,
+            parts: Vec::new(),
+        });
+        for content in system {
+            instruction.parts.extend(content.parts);
+        }
+    }
+
+    /// Check the request for invalid settings before sending it
+    pub fn validate(&self) -> Result<()> {
+        if let Some(config) = &self.generation_config {
+            config.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Check the tools for combinations the given model rejects
+    ///
+    /// Models before Gemini 3 cannot combine built-in tools (search, URL context, code
+    /// execution, ...) with function declarations, nor use any tool together with structured
+    /// output. File Search cannot be combined with other tools on any model. Models whose
+    /// generation cannot be read from the name, such as aliases and tuned models, are not
+    /// checked for mixed tools.
+    pub fn validate_tools(&self, model: &str) -> Result<()> {
+        let tools = match &self.tools {
+            Some(tools) if !tools.is_empty() => tools,
+            _ => return Ok(()),
+        };
+
+        #[cfg(feature = "grounding")]
+        if tools.len() > 1 && tools.iter().any(|tool| matches!(tool, Tool::FileSearch(_))) {
+            return Err(Error::Config(
+                "the File Search tool cannot be combined with other tools".to_string(),
+            ));
+        }
+
+        let mixed_tools_supported = model_generation(model).is_none_or(|major| major >= 3);
+        if mixed_tools_supported {
+            return Ok(());
+        }
+
+        let has_functions = tools.iter().any(Tool::is_function_declarations);
+        let has_builtin = tools.iter().any(|tool| !tool.is_function_declarations());
+        if has_functions && has_builtin {
+            return Err(Error::Config(format!(
+                "{} does not support combining built-in tools with function declarations",
+                model
+            )));
+        }
+
+        let structured_output = self.generation_config.as_ref().is_some_and(|config| {
+            config.response_mime_type.as_deref() == Some("application/json")
+                || config.response_schema.is_some()
+                || config.response_json_schema.is_some()
+        });
+        if structured_output {
+            return Err(Error::Config(format!(
+                "{} does not support tools together with structured output",
+                model
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check that `model` can produce every requested response modality
+    ///
+    /// Only models named for image generation (e.g. `gemini-2.5-flash-image`) return images,
+    /// and only text-to-speech and native audio models return audio. Names that are not
+    /// `gemini-*` models, such as tuned models, are not checked.
+    pub fn validate_modalities(&self, model: &str) -> Result<()> {
+        let modalities = self
+            .generation_config
+            .as_ref()
+            .and_then(|config| config.response_modalities.as_deref())
+            .unwrap_or_default();
+        if let Some(modality) = modalities
+            .iter()
+            .find(|modality| !modality.supported_by(model))
+        {
+            return Err(Error::Config(format!(
+                "{} cannot return {:?} output",
+                model, modality
+            )));
+        }
+        Ok(())
+    }
+
+    /// Ask for these kinds of output
+    pub fn with_response_modalities(
+        mut self,
+        modalities: impl IntoIterator<Item = Modality>,
+    ) -> Self {
+        self.generation_config
+            .get_or_insert_with(Default::default)
+            .response_modalities = Some(modalities.into_iter().collect());
+        self
+    }
+}
+
+/// Major version of a Gemini model name (e.g. 2 for "gemini-2.5-flash")
+pub(crate) fn model_generation(model: &str) -> Option<u32> {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    let version = name.strip_prefix("gemini-")?;
+    let major: String = version.chars().take_while(char::is_ascii_digit).collect();
+    major.parse().ok()
 }
 
 /// Response structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerateContentResponse {
-    /// Generated response candidates
+    /// Generated response candidates; absent when the prompt is blocked
+    #[serde(default)]
     pub candidates: Vec<Candidate>,
 
     /// Feedback about the prompt
@@ -325,6 +1147,137 @@ pub struct GenerateContentResponse {
     pub usage_metadata: Option<UsageMetadata>,
 }
 
+impl GenerateContentResponse {
+    /// Concatenated thought summaries of the first candidate, if any were returned
+    #[cfg(feature = "thinking")]
+    pub fn thoughts(&self) -> Option<String> {
+        let candidate = self.candidates.first()?;
+        let thoughts: Vec<&str> = candidate
+            .content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Thought { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if thoughts.is_empty() {
+            None
+        } else {
+            Some(thoughts.concat())
+        }
+    }
+
+    /// Fraction of prompt tokens served from cache, if the response reported usage
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        self.usage_metadata
+            .as_ref()
+            .map(UsageMetadata::cache_hit_ratio)
+    }
+
+    /// Concatenated text of the first candidate, if it produced any text parts
+    pub fn text(&self) -> Option<String> {
+        self.candidates.first()?.text()
+    }
+
+    /// The candidate with `candidate.index` equal to `index`
+    pub fn candidate(&self, index: usize) -> Option<&Candidate> {
+        self.candidates
+            .iter()
+            .find(|candidate| candidate.index.and_then(|i| usize::try_from(i).ok()) == Some(index))
+    }
+
+    /// Give every candidate an index and order the candidates by it
+    ///
+    /// The API leaves out `index` when it is zero. Candidates without one take the lowest
+    /// index no other candidate claims, in the order they arrived, so a response to a
+    /// request with `candidate_count > 1` can be matched up by index.
+    pub fn index_candidates(&mut self) {
+        let mut taken: std::collections::HashSet<i32> = self
+            .candidates
+            .iter()
+            .filter_map(|candidate| candidate.index)
+            .collect();
+        let mut next = 0;
+        for candidate in &mut self.candidates {
+            if candidate.index.is_none() {
+                while taken.contains(&next) {
+                    next += 1;
+                }
+                candidate.index = Some(next);
+                taken.insert(next);
+            }
+        }
+        self.candidates.sort_by_key(|candidate| candidate.index);
+    }
+
+    /// [`Candidate::stop_sequence_hit`] for each candidate, in candidate order
+    pub fn stop_sequence_hits(&self, stop_sequences: &[String]) -> Vec<Option<StopSequenceHit>> {
+        self.candidates
+            .iter()
+            .map(|candidate| candidate.stop_sequence_hit(stop_sequences))
+            .collect()
+    }
+
+    /// [`Candidate::strip_stop_sequence`] for each candidate, in candidate order
+    pub fn strip_stop_sequences(
+        &mut self,
+        stop_sequences: &[String],
+    ) -> Vec<Option<StopSequenceHit>> {
+        self.candidates
+            .iter_mut()
+            .map(|candidate| candidate.strip_stop_sequence(stop_sequences))
+            .collect()
+    }
+
+    /// Whether the response has no candidates, or a first candidate without any parts
+    ///
+    /// Common when safety filters block the prompt or answer, or thinking uses up the output
+    /// token limit.
+    pub fn is_empty(&self) -> bool {
+        self.candidates
+            .first()
+            .is_none_or(|candidate| candidate.content.parts.is_empty())
+    }
+
+    /// Return the response, or `Error::EmptyResponse` if it [is empty](Self::is_empty)
+    pub fn require_content(self) -> Result<Self> {
+        if !self.is_empty() {
+            return Ok(self);
+        }
+        Err(Error::EmptyResponse {
+            finish_reason: self
+                .candidates
+                .first()
+                .and_then(|candidate| candidate.finish_reason),
+            block_reason: self
+                .prompt_feedback
+                .as_ref()
+                .and_then(|feedback| feedback.block_reason),
+        })
+    }
+
+    /// Deserialize the first candidate's text into `T`
+    ///
+    /// Text parts are concatenated and surrounding markdown code fences are removed. Parse
+    /// errors include a snippet of the offending text.
+    pub fn parse_json<T: DeserializeOwned>(&self) -> Result<T> {
+        let text = self
+            .text()
+            .ok_or_else(|| Error::InvalidResponse("No text in response".to_string()))?;
+        crate::json::parse(&text, false)
+    }
+
+    /// Like [`parse_json`](Self::parse_json), but repairs almost-valid JSON before parsing
+    pub fn parse_json_repaired<T: DeserializeOwned>(&self) -> Result<T> {
+        let text = self
+            .text()
+            .ok_or_else(|| Error::InvalidResponse("No text in response".to_string()))?;
+        crate::json::parse(&text, true)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// A response candidate
@@ -344,6 +1297,10 @@ pub struct Candidate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub citation_metadata: Option<CitationMetadata>,
 
+    /// Index of the candidate when several are requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<i32>,
+
     /// Grounding metadata for search results
     #[cfg(feature = "grounding")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -355,8 +1312,94 @@ pub struct Candidate {
     pub url_context_metadata: Option<crate::grounding::UrlContextMetadata>,
 }
 
+impl Candidate {
+    /// Concatenated text parts of this candidate
+    pub fn text(&self) -> Option<String> {
+        let texts: Vec<&str> = self
+            .content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if texts.is_empty() {
+            None
+        } else {
+            Some(texts.concat())
+        }
+    }
+
+    /// Whether this candidate may have been ended by one of `stop_sequences`
+    ///
+    /// The signal is a `STOP` finish while stop sequences were configured. Gemini leaves the
+    /// matched sequence out of the text and reports it as a plain `STOP`, so its responses
+    /// give [`StopSequenceHit::Unidentified`]; the sequence is only named when a backend
+    /// echoes it at the end of the text. Returns `None` for other finish reasons or when no
+    /// sequences were configured.
+    pub fn stop_sequence_hit(&self, stop_sequences: &[String]) -> Option<StopSequenceHit> {
+        if self.finish_reason != Some(FinishReason::Stop)
+            || stop_sequences.iter().all(String::is_empty)
+        {
+            return None;
+        }
+        let text = self
+            .content
+            .parts
+            .iter()
+            .rev()
+            .find_map(|part| match part {
+                Part::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let echoed = stop_sequences
+            .iter()
+            .filter(|sequence| !sequence.is_empty() && text.ends_with(sequence.as_str()))
+            .max_by_key(|sequence| sequence.len());
+        Some(match echoed {
+            Some(sequence) => StopSequenceHit::Echoed(sequence.clone()),
+            None => StopSequenceHit::Unidentified,
+        })
+    }
+
+    /// Like [`stop_sequence_hit`](Self::stop_sequence_hit), also removing an echoed
+    /// sequence from the end of the text
+    pub fn strip_stop_sequence(&mut self, stop_sequences: &[String]) -> Option<StopSequenceHit> {
+        let hit = self.stop_sequence_hit(stop_sequences)?;
+        if let StopSequenceHit::Echoed(sequence) = &hit {
+            let text = self
+                .content
+                .parts
+                .iter_mut()
+                .rev()
+                .find_map(|part| match part {
+                    Part::Text { text, .. } => Some(text),
+                    _ => None,
+                });
+            if let Some(text) = text {
+                text.truncate(text.len() - sequence.len());
+            }
+        }
+        Some(hit)
+    }
+}
+
+/// How a candidate's `STOP` finish relates to the request's stop sequences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopSequenceHit {
+    /// Stopped with stop sequences configured, without saying whether one fired or which
+    ///
+    /// What Gemini responses report, since the API strips the matched sequence.
+    Unidentified,
+    /// The text ends with this sequence, as returned by backends that echo the match
+    Echoed(String),
+}
+
 /// Reasons for finishing content generation
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FinishReason {
     /// Natural stopping point
     #[serde(rename = "STOP")]
@@ -377,6 +1420,7 @@ pub enum FinishReason {
 
 /// Feedback about the prompt before generation
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PromptFeedback {
     /// Reason for blocking the prompt
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -428,7 +1472,7 @@ pub enum HarmProbability {
 }
 
 /// Token usage metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UsageMetadata {
     /// Number of tokens in the prompt
@@ -441,10 +1485,115 @@ pub struct UsageMetadata {
     /// Number of tokens from cached content
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cached_content_token_count: Option<i32>,
+
+    /// Number of tokens used for thinking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thoughts_token_count: Option<i32>,
+
+    /// Prompt tokens broken down by modality
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens_details: Option<Vec<ModalityTokenCount>>,
+}
+
+/// Kind of content, in token usage details and in
+/// [`GenerationConfig::response_modalities`]
+///
+/// Only `Text`, `Image` and `Audio` can be requested as response modalities; see
+/// [`is_response_modality`](Self::is_response_modality).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Modality {
+    /// Not specified by the API
+    ModalityUnspecified,
+    /// Plain text
+    Text,
+    /// Images
+    Image,
+    /// Video frames
+    Video,
+    /// Audio, including the audio track of a video
+    Audio,
+    /// Documents such as PDFs
+    Document,
+}
+
+impl Modality {
+    /// Whether a response can be asked to contain this modality
+    pub fn is_response_modality(self) -> bool {
+        matches!(self, Modality::Text | Modality::Image | Modality::Audio)
+    }
+
+    /// Whether `model` can return this modality, judged from its name
+    pub fn supported_by(self, model: &str) -> bool {
+        let name = model.rsplit('/').next().unwrap_or(model);
+        if !name.starts_with("gemini-") {
+            return true;
+        }
+        let speech = name.contains("-tts") || name.contains("native-audio");
+        match self {
+            Modality::Text => !name.contains("-tts"),
+            Modality::Image => name.contains("-image") || name.starts_with("gemini-2.0-flash-exp"),
+            Modality::Audio => speech,
+            _ => false,
+        }
+    }
+}
+
+/// Tokens counted for one modality
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModalityTokenCount {
+    /// The modality counted
+    pub modality: Modality,
+    /// Number of tokens
+    pub token_count: i32,
+}
+
+impl UsageMetadata {
+    /// Fraction of prompt tokens served from cache (explicit or implicit), from 0.0 to 1.0
+    pub fn cache_hit_ratio(&self) -> f64 {
+        if self.prompt_token_count <= 0 {
+            return 0.0;
+        }
+        let cached = self.cached_content_token_count.unwrap_or(0).max(0);
+        (cached as f64 / self.prompt_token_count as f64).min(1.0)
+    }
+
+    /// Prompt tokens reported for `modality`, `None` if the response has no breakdown for it
+    pub fn prompt_tokens_for(&self, modality: Modality) -> Option<i32> {
+        self.prompt_tokens_details
+            .iter()
+            .flatten()
+            .find(|detail| detail.modality == modality)
+            .map(|detail| detail.token_count)
+    }
+}
+
+/// Rough token estimate (about four characters per token)
+///
+/// Text counts by length, inline data by decoded size and thought and function parts by
+/// their JSON length; file references are not counted.
+pub(crate) fn estimate_tokens(contents: &[Content]) -> usize {
+    contents
+        .iter()
+        .flat_map(|content| &content.parts)
+        .map(|part| match part {
+            Part::Text { text, .. } => text.len() / 4,
+            Part::InlineData { inline_data, .. } => inline_data.data.len() * 3 / 4 / 4,
+            Part::FileData { .. } => 0,
+            #[cfg(feature = "thinking")]
+            Part::Thought { .. } => serde_json::to_string(part).map_or(0, |json| json.len() / 4),
+            #[cfg(feature = "functions")]
+            Part::FunctionCall { .. } | Part::FunctionResponse { .. } => {
+                serde_json::to_string(part).map_or(0, |json| json.len() / 4)
+            }
+        })
+        .sum()
 }
 
 /// Citation metadata for generated content
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CitationMetadata {
     /// List of citation sources
     pub citation_sources: Vec<CitationSource>,
@@ -486,6 +1635,122 @@ pub struct CountTokensResponse {
     pub total_tokens: i32,
 }
 
+/// What an embedding will be used for, letting the model optimize it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EmbeddingTaskType {
+    /// A search query
+    RetrievalQuery,
+    /// A document to be searched
+    RetrievalDocument,
+    /// Text compared for similarity
+    SemanticSimilarity,
+    /// Text to classify
+    Classification,
+    /// Text to cluster
+    Clustering,
+    /// A question answered from documents
+    QuestionAnswering,
+    /// A claim checked against documents
+    FactVerification,
+    /// A natural language query for code
+    CodeRetrievalQuery,
+}
+
+/// Request to embed one content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedContentRequest {
+    /// Model resource name, e.g. `models/gemini-embedding-001`
+    pub model: String,
+
+    /// Content to embed; only text parts are used
+    pub content: Content,
+
+    /// Intended use of the embedding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_type: Option<EmbeddingTaskType>,
+
+    /// Title of the document, for `RetrievalDocument` embeddings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Truncate the embedding to this many dimensions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dimensionality: Option<i32>,
+}
+
+/// An embedding vector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentEmbedding {
+    /// Embedding values
+    pub values: Vec<f32>,
+}
+
+/// Response from the embed content API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedContentResponse {
+    /// The embedding
+    pub embedding: ContentEmbedding,
+}
+
+/// Request to embed several contents at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchEmbedContentsRequest {
+    /// One request per content, all for the same model
+    pub requests: Vec<EmbedContentRequest>,
+}
+
+/// Response from the batch embed contents API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchEmbedContentsResponse {
+    /// Embeddings in request order
+    pub embeddings: Vec<ContentEmbedding>,
+}
+
+/// A model available to the API key, as returned by the models list endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    /// Resource name, e.g. `models/gemini-2.5-flash`
+    pub name: String,
+    /// Base model this version belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_model_id: Option<String>,
+    /// Model version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Human-readable name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Maximum input tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_token_limit: Option<i32>,
+    /// Maximum output tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_token_limit: Option<i32>,
+    /// API methods the model supports, e.g. `generateContent`
+    #[serde(default)]
+    pub supported_generation_methods: Vec<String>,
+}
+
+impl ModelInfo {
+    /// Model name without the `models/` prefix
+    pub fn id(&self) -> &str {
+        self.name.strip_prefix("models/").unwrap_or(&self.name)
+    }
+}
+
+/// One page of the models list
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListModelsResponse {
+    #[serde(default)]
+    pub models: Vec<ModelInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+}
+
 /// Builder for structured output
 pub struct StructuredOutput;
 
@@ -0,0 +1,758 @@
+//! OpenAI-compatible chat completion types
+//!
+//! Lets applications written against the OpenAI chat completions API move to this crate
+//! piece by piece: a [`ChatCompletionRequest`] converts into a [`GenerateContentRequest`], and
+//! responses convert back with [`ChatCompletionResponse::from_gemini`], or chunk by chunk with
+//! a [`ChunkConverter`] when streaming.
+//!
+//! Only what Gemini can express is converted. Tool call ids are built from the completion id,
+//! the choice and the call's position, so they stay unique across turns, and tool messages
+//! are matched to their function by the id of an earlier assistant tool call. Images must be
+//! `data:` URLs; fetch remote images first, e.g. with [`Part::from_url`].
+
+use crate::{
+    error::{Error, Result},
+    functions::{
+        FunctionCall, FunctionCallingConfig, FunctionCallingMode, FunctionDeclaration,
+        FunctionResponse, ParameterSchema,
+    },
+    models::{
+        Candidate, Content, FinishReason, GenerateContentRequest, GenerateContentResponse,
+        GenerationConfig, InlineData, Part, Role, Tool, ToolConfig, UsageMetadata,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A chat completion request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatCompletionRequest {
+    /// Model name
+    pub model: String,
+    /// The conversation so far
+    pub messages: Vec<ChatMessage>,
+    /// Sampling temperature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling probability
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Maximum output tokens (deprecated in favor of `max_completion_tokens`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<i32>,
+    /// Maximum output tokens, including reasoning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<i32>,
+    /// Number of choices to generate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<i32>,
+    /// Sequences that stop generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Stop>,
+    /// Presence penalty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    /// Frequency penalty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    /// Functions the model may call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ChatTool>>,
+    /// Whether and which tools the model must call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    /// Output format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// Whether the answer is streamed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+}
+
+/// Stop sequences, as one string or a list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Stop {
+    /// A single stop sequence
+    One(String),
+    /// Several stop sequences
+    Many(Vec<String>),
+}
+
+/// Author of a chat message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    /// System instructions
+    System,
+    /// Developer instructions, treated like system instructions
+    Developer,
+    /// The user
+    User,
+    /// The model
+    Assistant,
+    /// A tool result
+    Tool,
+}
+
+/// A message in a chat completion conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Author of the message
+    pub role: ChatRole,
+    /// Text or content parts; absent on assistant messages that only call tools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<MessageContent>,
+    /// Tool calls made by the assistant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// Id of the tool call a tool message answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    /// A text message
+    pub fn new(role: ChatRole, text: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: Some(MessageContent::Text(text.into())),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+}
+
+/// Message content, as plain text or a list of parts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    /// Plain text
+    Text(String),
+    /// Text and image parts
+    Parts(Vec<ContentPart>),
+}
+
+/// A part of a message's content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    /// Text
+    Text {
+        /// The text
+        text: String,
+    },
+    /// An image inline as a `data:` URL
+    ImageUrl {
+        /// The image
+        image_url: ImageUrl,
+    },
+}
+
+/// An image reference
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageUrl {
+    /// `data:<mime>;base64,<data>` URL
+    pub url: String,
+}
+
+/// A function call made by the assistant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Id referred to by the tool message answering the call
+    pub id: String,
+    /// Always `function`
+    #[serde(rename = "type")]
+    pub call_type: String,
+    /// The function and its arguments
+    pub function: FunctionCallData,
+}
+
+/// Function name and JSON-encoded arguments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionCallData {
+    /// Function name
+    pub name: String,
+    /// Arguments as a JSON object string
+    pub arguments: String,
+}
+
+/// A tool the model may call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatTool {
+    /// Always `function`
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    /// The function
+    pub function: FunctionDefinition,
+}
+
+/// A function declaration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionDefinition {
+    /// Function name
+    pub name: String,
+    /// What the function does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON schema of the arguments object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<serde_json::Value>,
+}
+
+/// Which tools the model must call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    /// `none`, `auto` or `required`
+    Mode(String),
+    /// A specific function
+    Function {
+        /// Always `function`
+        #[serde(rename = "type")]
+        choice_type: String,
+        /// The function to call
+        function: NamedFunction,
+    },
+}
+
+/// A function picked by name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedFunction {
+    /// Function name
+    pub name: String,
+}
+
+/// Output format of the answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Free text
+    Text,
+    /// Any JSON object
+    JsonObject,
+    /// JSON matching a schema
+    JsonSchema {
+        /// The schema
+        json_schema: JsonSchemaFormat,
+    },
+}
+
+/// A named JSON schema for structured output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonSchemaFormat {
+    /// Schema name
+    pub name: String,
+    /// The JSON schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+    /// Whether the schema must be followed exactly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+/// A chat completion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionResponse {
+    /// Completion id
+    pub id: String,
+    /// Always `chat.completion`
+    pub object: String,
+    /// Creation time in seconds since the Unix epoch
+    pub created: u64,
+    /// Model name
+    pub model: String,
+    /// One choice per candidate
+    pub choices: Vec<ChatChoice>,
+    /// Token usage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<CompletionUsage>,
+}
+
+/// A generated answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatChoice {
+    /// Index of the choice
+    pub index: u32,
+    /// The assistant message
+    pub message: ChatMessage,
+    /// `stop`, `length`, `tool_calls` or `content_filter`
+    pub finish_reason: Option<String>,
+}
+
+/// Token usage of a completion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletionUsage {
+    /// Prompt tokens
+    pub prompt_tokens: u32,
+    /// Answer tokens, including reasoning
+    pub completion_tokens: u32,
+    /// Total tokens
+    pub total_tokens: u32,
+}
+
+/// A streamed piece of a chat completion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionChunk {
+    /// Completion id, the same for every chunk of a stream
+    pub id: String,
+    /// Always `chat.completion.chunk`
+    pub object: String,
+    /// Creation time in seconds since the Unix epoch
+    pub created: u64,
+    /// Model name
+    pub model: String,
+    /// Deltas, one per candidate in the chunk
+    pub choices: Vec<ChunkChoice>,
+    /// Token usage, on the chunks that report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<CompletionUsage>,
+}
+
+/// A streamed piece of a choice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkChoice {
+    /// Index of the choice
+    pub index: u32,
+    /// What the chunk adds
+    pub delta: ChatDelta,
+    /// Set on the last chunk of the choice
+    pub finish_reason: Option<String>,
+}
+
+/// The content a chunk adds to a choice
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatDelta {
+    /// `assistant`, on the first chunk of a choice
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<ChatRole>,
+    /// Text added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Tool calls added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+/// A tool call in a chunk
+///
+/// Gemini streams whole function calls, so each delta carries the complete call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallDelta {
+    /// Position of the call within the choice
+    pub index: u32,
+    /// Call id
+    pub id: String,
+    /// Always `function`
+    #[serde(rename = "type")]
+    pub call_type: String,
+    /// The function and its arguments
+    pub function: FunctionCallData,
+}
+
+impl TryFrom<ChatCompletionRequest> for GenerateContentRequest {
+    type Error = Error;
+
+    fn try_from(request: ChatCompletionRequest) -> Result<Self> {
+        let mut system = Vec::new();
+        let mut contents: Vec<Content> = Vec::new();
+        let mut call_names: HashMap<String, String> = HashMap::new();
+
+        for message in request.messages {
+            match message.role {
+                ChatRole::System | ChatRole::Developer => {
+                    system.extend(message_parts(message.content)?);
+                }
+                ChatRole::User => contents.push(Content {
+                    role: Role::User,
+                    parts: message_parts(message.content)?,
+                }),
+                ChatRole::Assistant => {
+                    let mut parts = message_parts(message.content)?;
+                    for call in message.tool_calls.into_iter().flatten() {
+                        let args = if call.function.arguments.trim().is_empty() {
+                            HashMap::new()
+                        } else {
+                            serde_json::from_str(&call.function.arguments)?
+                        };
+                        call_names.insert(call.id, call.function.name.clone());
+                        parts.push(Part::FunctionCall {
+                            function_call: FunctionCall {
+                                name: call.function.name,
+                                args,
+                                ..Default::default()
+                            },
+                            thought_signature: None,
+                        });
+                    }
+                    contents.push(Content {
+                        role: Role::Model,
+                        parts,
+                    });
+                }
+                ChatRole::Tool => {
+                    let id = message.tool_call_id.unwrap_or_default();
+                    let name = call_names.get(&id).cloned().ok_or_else(|| {
+                        Error::Config(format!("Tool message answers unknown tool call {:?}", id))
+                    })?;
+                    let text = message_text(message.content);
+                    let value =
+                        serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+                    let part = Part::FunctionResponse {
+                        function_response: FunctionResponse::from_serialize(name, &value)?,
+                    };
+                    // Answers to parallel calls go back in one turn
+                    match contents.last_mut() {
+                        Some(last)
+                            if last.role == Role::User
+                                && last
+                                    .parts
+                                    .iter()
+                                    .all(|part| matches!(part, Part::FunctionResponse { .. })) =>
+                        {
+                            last.parts.push(part)
+                        }
+                        _ => contents.push(Content {
+                            role: Role::User,
+                            parts: vec![part],
+                        }),
+                    }
+                }
+            }
+        }
+
+        let mut gemini = GenerateContentRequest::new(contents);
+        if !system.is_empty() {
+            gemini.system_instruction = Some(Content {
+                role: Role::System,
+                parts: system,
+            });
+        }
+
+        let mut config = GenerationConfig {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            max_output_tokens: request.max_completion_tokens.or(request.max_tokens),
+            candidate_count: request.n,
+            stop_sequences: request.stop.map(|stop| match stop {
+                Stop::One(sequence) => vec![sequence],
+                Stop::Many(sequences) => sequences,
+            }),
+            presence_penalty: request.presence_penalty,
+            frequency_penalty: request.frequency_penalty,
+            ..Default::default()
+        };
+        match request.response_format {
+            Some(ResponseFormat::JsonObject) => {
+                config.response_mime_type = Some("application/json".to_string());
+            }
+            Some(ResponseFormat::JsonSchema { json_schema }) => {
+                config.response_mime_type = Some("application/json".to_string());
+                config.response_json_schema = json_schema.schema;
+            }
+            Some(ResponseFormat::Text) | None => {}
+        }
+        gemini.generation_config = Some(config);
+
+        if let Some(tools) = request.tools {
+            let declarations = tools
+                .into_iter()
+                .map(function_declaration)
+                .collect::<Result<Vec<_>>>()?;
+            gemini.tools = Some(vec![Tool::FunctionDeclarations {
+                function_declarations: declarations,
+            }]);
+        }
+        if let Some(choice) = request.tool_choice {
+            let (mode, allowed) = match choice {
+                ToolChoice::Mode(mode) => match mode.as_str() {
+                    "none" => (FunctionCallingMode::None, None),
+                    "auto" => (FunctionCallingMode::Auto, None),
+                    "required" => (FunctionCallingMode::Any, None),
+                    other => return Err(Error::Config(format!("Unknown tool choice {:?}", other))),
+                },
+                ToolChoice::Function { function, .. } => {
+                    (FunctionCallingMode::Any, Some(vec![function.name]))
+                }
+            };
+            gemini
+                .tool_config
+                .get_or_insert_with(ToolConfig::default)
+                .function_calling_config = Some(FunctionCallingConfig {
+                mode,
+                allowed_function_names: allowed,
+                stream_function_call_arguments: None,
+            });
+        }
+        Ok(gemini)
+    }
+}
+
+impl ChatCompletionResponse {
+    /// Convert a Gemini response, reporting `model` as the model that answered
+    pub fn from_gemini(response: &GenerateContentResponse, model: impl Into<String>) -> Self {
+        let id = completion_id();
+        let choices = response
+            .candidates
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| {
+                let index = candidate_index(candidate, i);
+                let text = candidate.text();
+                let tool_calls: Vec<ToolCall> = function_calls(candidate)
+                    .enumerate()
+                    .map(|(i, call)| tool_call(&id, index, i, call))
+                    .collect();
+                let finish_reason = finish_reason(candidate.finish_reason, !tool_calls.is_empty());
+                ChatChoice {
+                    index,
+                    message: ChatMessage {
+                        role: ChatRole::Assistant,
+                        content: text.map(MessageContent::Text),
+                        tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                        tool_call_id: None,
+                    },
+                    finish_reason,
+                }
+            })
+            .collect();
+        Self {
+            id,
+            object: "chat.completion".to_string(),
+            created: now(),
+            model: model.into(),
+            choices,
+            usage: response.usage_metadata.as_ref().map(CompletionUsage::from),
+        }
+    }
+}
+
+impl From<&UsageMetadata> for CompletionUsage {
+    fn from(usage: &UsageMetadata) -> Self {
+        let count = |tokens: i32| tokens.max(0) as u32;
+        Self {
+            prompt_tokens: count(usage.prompt_token_count),
+            completion_tokens: count(usage.candidates_token_count)
+                + count(usage.thoughts_token_count.unwrap_or(0)),
+            total_tokens: count(usage.total_token_count),
+        }
+    }
+}
+
+/// Converts the chunks of a Gemini stream into chat completion chunks
+///
+/// Keeps the completion id and numbers tool calls across the stream.
+#[derive(Debug, Clone)]
+pub struct ChunkConverter {
+    id: String,
+    model: String,
+    created: u64,
+    started: Vec<u32>,
+    tool_calls: HashMap<u32, usize>,
+}
+
+impl ChunkConverter {
+    /// Start converting a stream, reporting `model` as the model that answers
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            id: completion_id(),
+            model: model.into(),
+            created: now(),
+            started: Vec::new(),
+            tool_calls: HashMap::new(),
+        }
+    }
+
+    /// Convert the next chunk of the stream
+    pub fn convert(&mut self, chunk: &GenerateContentResponse) -> ChatCompletionChunk {
+        let choices = chunk
+            .candidates
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| {
+                let index = candidate_index(candidate, i);
+                let role = if self.started.contains(&index) {
+                    None
+                } else {
+                    self.started.push(index);
+                    Some(ChatRole::Assistant)
+                };
+
+                let count = self.tool_calls.entry(index).or_insert(0);
+                let tool_calls: Vec<ToolCallDelta> = function_calls(candidate)
+                    .map(|call| {
+                        let ToolCall {
+                            id,
+                            call_type,
+                            function,
+                        } = tool_call(&self.id, index, *count, call);
+                        let delta = ToolCallDelta {
+                            index: *count as u32,
+                            id,
+                            call_type,
+                            function,
+                        };
+                        *count += 1;
+                        delta
+                    })
+                    .collect();
+
+                ChunkChoice {
+                    index,
+                    delta: ChatDelta {
+                        role,
+                        content: candidate.text(),
+                        tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                    },
+                    finish_reason: finish_reason(candidate.finish_reason, *count > 0),
+                }
+            })
+            .collect();
+        ChatCompletionChunk {
+            id: self.id.clone(),
+            object: "chat.completion.chunk".to_string(),
+            created: self.created,
+            model: self.model.clone(),
+            choices,
+            usage: chunk.usage_metadata.as_ref().map(CompletionUsage::from),
+        }
+    }
+}
+
+/// Parts of a message's content, with `data:` image URLs inlined
+fn message_parts(content: Option<MessageContent>) -> Result<Vec<Part>> {
+    match content {
+        None => Ok(Vec::new()),
+        Some(MessageContent::Text(text)) => Ok(vec![Part::from(text)]),
+        Some(MessageContent::Parts(parts)) => parts
+            .into_iter()
+            .map(|part| match part {
+                ContentPart::Text { text, .. } => Ok(Part::from(text)),
+                ContentPart::ImageUrl { image_url } => image_part(&image_url.url),
+            })
+            .collect(),
+    }
+}
+
+/// Text of a message's content, ignoring images
+fn message_text(content: Option<MessageContent>) -> String {
+    match content {
+        None => String::new(),
+        Some(MessageContent::Text(text)) => text,
+        Some(MessageContent::Parts(parts)) => parts
+            .into_iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text, .. } => Some(text),
+                ContentPart::ImageUrl { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// An inline part from a `data:` image URL
+///
+/// Remote URLs are rejected rather than passed on as file URIs the API cannot read; fetch
+/// them with [`Part::from_url`] instead.
+fn image_part(url: &str) -> Result<Part> {
+    let data_url = url.strip_prefix("data:").ok_or_else(|| {
+        Error::Config(format!(
+            "Image {} is not a data: URL; fetch it with Part::from_url first",
+            url
+        ))
+    })?;
+    let (mime_type, data) = data_url
+        .split_once(";base64,")
+        .ok_or_else(|| Error::Config("Image data URLs must be base64-encoded".to_string()))?;
+    Ok(Part::InlineData {
+        inline_data: InlineData {
+            mime_type: mime_type.to_string(),
+            data: data.to_string(),
+        },
+        thought_signature: None,
+    })
+}
+
+fn function_declaration(tool: ChatTool) -> Result<FunctionDeclaration> {
+    let mut parameters = tool
+        .function
+        .parameters
+        .unwrap_or_else(|| serde_json::json!({ "type": "object" }));
+    if let Some(object) = parameters.as_object_mut() {
+        object
+            .entry("properties")
+            .or_insert_with(|| serde_json::json!({}));
+    }
+    let parameters: ParameterSchema = serde_json::from_value(parameters).map_err(|e| {
+        Error::Config(format!(
+            "Unsupported parameters schema for {}: {}",
+            tool.function.name, e
+        ))
+    })?;
+    Ok(FunctionDeclaration {
+        name: tool.function.name,
+        description: tool.function.description.unwrap_or_default(),
+        parameters,
+        behavior: None,
+    })
+}
+
+fn function_calls(candidate: &Candidate) -> impl Iterator<Item = &FunctionCall> {
+    candidate
+        .content
+        .parts
+        .iter()
+        .filter_map(|part| match part {
+            Part::FunctionCall { function_call, .. } => Some(function_call),
+            _ => None,
+        })
+}
+
+/// A tool call whose id is unique to its completion, choice and position
+fn tool_call(completion_id: &str, choice: u32, index: usize, call: &FunctionCall) -> ToolCall {
+    let completion = completion_id
+        .strip_prefix("chatcmpl-")
+        .unwrap_or(completion_id);
+    ToolCall {
+        id: format!("call_{}_{}_{}", completion, choice, index),
+        call_type: "function".to_string(),
+        function: FunctionCallData {
+            name: call.name.clone(),
+            arguments: serde_json::to_string(&call.args).unwrap_or_default(),
+        },
+    }
+}
+
+fn candidate_index(candidate: &Candidate, position: usize) -> u32 {
+    candidate
+        .index
+        .map_or(position as u32, |index| index.max(0) as u32)
+}
+
+fn finish_reason(reason: Option<FinishReason>, called_tools: bool) -> Option<String> {
+    let reason = match reason? {
+        FinishReason::Stop | FinishReason::Other if called_tools => "tool_calls",
+        FinishReason::Stop | FinishReason::Other => "stop",
+        FinishReason::MaxTokens => "length",
+        FinishReason::Safety | FinishReason::Recitation => "content_filter",
+    };
+    Some(reason.to_string())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// A completion id, unique within the process even on coarse clocks
+fn completion_id() -> String {
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    format!("chatcmpl-{:x}{:04x}", nanos, sequence & 0xffff)
+}
@@ -0,0 +1,198 @@
+//! Spans following the OpenTelemetry GenAI semantic conventions
+//!
+//! Each generation call runs inside a `gen_ai` span whose fields use the convention's
+//! attribute names (`gen_ai.request.model`, `gen_ai.usage.input_tokens`, ...). Exported
+//! through `tracing-opentelemetry`, `otel.name` and `otel.kind` become the span name and kind.
+
+use crate::{
+    error::{Error, Result},
+    models::{GenerateContentRequest, GenerateContentResponse},
+};
+#[cfg(feature = "streaming")]
+use futures::Stream;
+#[cfg(feature = "streaming")]
+use std::pin::Pin;
+#[cfg(feature = "streaming")]
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tracing::{field::Empty, Span};
+
+/// Provider name reported as `gen_ai.provider.name`
+const PROVIDER: &str = "gcp.gemini";
+
+/// Create the span for one operation against a model
+pub(crate) fn operation_span(operation: &str, model: &str) -> Span {
+    tracing::info_span!(
+        "gen_ai",
+        otel.name = %format!("{} {}", operation, model),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        gen_ai.operation.name = operation,
+        gen_ai.provider.name = PROVIDER,
+        gen_ai.request.model = model,
+        gen_ai.request.temperature = Empty,
+        gen_ai.request.top_p = Empty,
+        gen_ai.request.max_tokens = Empty,
+        gen_ai.usage.input_tokens = Empty,
+        gen_ai.usage.output_tokens = Empty,
+        gen_ai.response.finish_reasons = Empty,
+        duration_ms = Empty,
+        error.type = Empty,
+    )
+}
+
+/// Record the request as sent, after defaults and profiles are applied
+pub(crate) fn record_request(span: &Span, model: &str, request: &GenerateContentRequest) {
+    span.record("gen_ai.request.model", model);
+    let Some(config) = &request.generation_config else {
+        return;
+    };
+    if let Some(temperature) = config.temperature {
+        span.record("gen_ai.request.temperature", f64::from(temperature));
+    }
+    if let Some(top_p) = config.top_p {
+        span.record("gen_ai.request.top_p", f64::from(top_p));
+    }
+    if let Some(max_tokens) = config.max_output_tokens {
+        span.record("gen_ai.request.max_tokens", max_tokens);
+    }
+}
+
+/// Record token usage and finish reasons, accumulating finish reasons across stream chunks
+fn record_response(span: &Span, response: &GenerateContentResponse, reasons: &mut Vec<String>) {
+    if let Some(usage) = &response.usage_metadata {
+        span.record("gen_ai.usage.input_tokens", usage.prompt_token_count);
+        span.record("gen_ai.usage.output_tokens", usage.candidates_token_count);
+    }
+
+    let mut changed = false;
+    for reason in response.candidates.iter().filter_map(|c| c.finish_reason) {
+        if let Some(name) = serde_json::to_value(reason)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+        {
+            reasons.push(name);
+            changed = true;
+        }
+    }
+    if changed {
+        span.record(
+            "gen_ai.response.finish_reasons",
+            serde_json::to_string(reasons).unwrap_or_default(),
+        );
+    }
+}
+
+fn record_error(span: &Span, error: &Error) {
+    let kind = match error {
+        Error::Api {
+            code: Some(code), ..
+        } => code.as_str(),
+        Error::Api { .. } => "api",
+        Error::Http(_) => "http",
+        Error::Connect(_) => "connect",
+        Error::Decode(_) => "decode",
+        Error::Json(_) => "json",
+        Error::Io(_) => "io",
+        #[cfg(feature = "image")]
+        Error::Image(_) => "image",
+        #[cfg(feature = "pdf")]
+        Error::Pdf(_) => "pdf",
+        Error::RateLimit { .. } => "rate_limit",
+        Error::Config(_) => "config",
+        Error::SchemaValidation(_) => "schema_validation",
+        Error::FunctionCall(_) => "function_call",
+        Error::Grounding(_) => "grounding",
+        Error::Cache(_) => "cache",
+        Error::Streaming(_) => "streaming",
+        Error::StreamInterrupted { .. } => "stream_interrupted",
+        Error::Shared(cause) => return record_error(span, cause),
+        Error::Timeout(_) => "timeout",
+        Error::InvalidResponse(_) => "invalid_response",
+        Error::EmptyResponse { .. } => "empty_response",
+        Error::GuardrailFailed { .. } => "guardrail_failed",
+        Error::TokenBudgetExceeded { .. } => "token_budget_exceeded",
+        Error::ThinkingBudgetExceeded => "thinking_budget_exceeded",
+    };
+    span.record("error.type", kind);
+    span.record("otel.status_code", "ERROR");
+}
+
+fn record_duration(span: &Span, started: Instant) {
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+}
+
+/// Record the outcome of a unary call
+pub(crate) fn record_result(
+    span: &Span,
+    result: &Result<GenerateContentResponse>,
+    started: Instant,
+) {
+    match result {
+        Ok(response) => record_response(span, response, &mut Vec::new()),
+        Err(error) => record_error(span, error),
+    }
+    record_duration(span, started);
+}
+
+/// Record the outcome of a streaming call; the span stays open until the stream ends
+#[cfg(feature = "streaming")]
+pub(crate) fn record_stream<S>(
+    span: Span,
+    result: Result<S>,
+    started: Instant,
+) -> Result<RecordedStream<S>>
+where
+    S: Stream<Item = Result<GenerateContentResponse>>,
+{
+    match result {
+        Ok(stream) => Ok(RecordedStream {
+            inner: Box::pin(stream),
+            span,
+            started,
+            reasons: Vec::new(),
+            finished: false,
+        }),
+        Err(error) => {
+            record_error(&span, &error);
+            record_duration(&span, started);
+            Err(error)
+        }
+    }
+}
+
+/// A response stream that records usage, errors and duration on its span
+#[cfg(feature = "streaming")]
+pub(crate) struct RecordedStream<S> {
+    inner: Pin<Box<S>>,
+    span: Span,
+    started: Instant,
+    reasons: Vec<String>,
+    finished: bool,
+}
+
+#[cfg(feature = "streaming")]
+impl<S> Stream for RecordedStream<S>
+where
+    S: Stream<Item = Result<GenerateContentResponse>>,
+{
+    type Item = Result<GenerateContentResponse>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let item = match this.inner.as_mut().poll_next(cx) {
+            Poll::Ready(item) => item,
+            Poll::Pending => return Poll::Pending,
+        };
+        match &item {
+            Some(Ok(chunk)) => record_response(&this.span, chunk, &mut this.reasons),
+            Some(Err(error)) => record_error(&this.span, error),
+            None if !this.finished => {
+                this.finished = true;
+                record_duration(&this.span, this.started);
+            }
+            None => {}
+        }
+        Poll::Ready(item)
+    }
+}
@@ -0,0 +1,285 @@
+//! Chunked PDF processing
+//!
+//! The API accepts a limited number of pages per document and a limited request size.
+//! [`split_pdf`] cuts a larger PDF into page ranges that each fit, and
+//! [`GeminiClient::generate_over_pdf`] asks the same question of every range and combines
+//! the answers with a [`PdfAggregation`].
+
+use crate::{
+    client::GeminiClient,
+    error::{Error, Result},
+    models::{Content, GenerateContentRequest, GenerateContentResponse, InlineData, Part, Role},
+};
+use base64::Engine;
+use lopdf::Document;
+use tracing::debug;
+
+/// Most pages the API reads from one document
+pub const DEFAULT_MAX_PAGES: u32 = 1000;
+
+/// Largest chunk in bytes, leaving room for base64 in a 20 MB request
+pub const DEFAULT_MAX_BYTES: usize = 15 * 1024 * 1024;
+
+/// Limits each chunk of a split PDF must respect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PdfChunking {
+    max_pages: u32,
+    max_bytes: usize,
+}
+
+impl Default for PdfChunking {
+    fn default() -> Self {
+        Self {
+            max_pages: DEFAULT_MAX_PAGES,
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+}
+
+impl PdfChunking {
+    /// Split at the API's page and request size limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put at most `pages` pages in each chunk
+    pub fn max_pages(mut self, pages: u32) -> Self {
+        self.max_pages = pages.max(1);
+        self
+    }
+
+    /// Keep each chunk under `bytes` bytes where possible
+    ///
+    /// A single page larger than this becomes a chunk of its own.
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = bytes;
+        self
+    }
+}
+
+/// A range of pages cut from a PDF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfChunk {
+    /// First page in the chunk, counting from 1
+    pub first_page: u32,
+    /// Last page in the chunk, inclusive
+    pub last_page: u32,
+    /// Pages in the whole document
+    pub total_pages: u32,
+    /// The chunk as a standalone PDF
+    pub data: Vec<u8>,
+}
+
+impl PdfChunk {
+    /// The chunk as an inline `application/pdf` part
+    pub fn to_part(&self) -> Part {
+        Part::InlineData {
+            inline_data: InlineData {
+                mime_type: "application/pdf".to_string(),
+                data: base64::engine::general_purpose::STANDARD.encode(&self.data),
+            },
+            thought_signature: None,
+        }
+    }
+
+    /// A request asking `prompt` about this chunk, telling the model which pages it holds
+    pub fn request(&self, prompt: &str) -> GenerateContentRequest {
+        let mut parts = vec![self.to_part()];
+        if self.first_page != 1 || self.last_page != self.total_pages {
+            parts.push(Part::text(format!(
+                "This document contains pages {}-{} of a {}-page PDF.",
+                self.first_page, self.last_page, self.total_pages
+            )));
+        }
+        parts.push(Part::text(prompt));
+        GenerateContentRequest::new(Content {
+            role: Role::User,
+            parts,
+        })
+    }
+}
+
+/// Split a PDF into chunks that each respect `chunking`
+///
+/// A document already within the limits comes back as one chunk holding the original bytes.
+/// Otherwise it is cut into runs of at most the page limit, and any run over the size limit
+/// is halved until it fits or is a single page.
+pub fn split_pdf(bytes: &[u8], chunking: &PdfChunking) -> Result<Vec<PdfChunk>> {
+    let document = Document::load_mem(bytes)?;
+    let total_pages = document.get_pages().len() as u32;
+    if total_pages == 0 {
+        return Err(Error::Config("PDF has no pages".to_string()));
+    }
+    if total_pages <= chunking.max_pages && bytes.len() <= chunking.max_bytes {
+        return Ok(vec![PdfChunk {
+            first_page: 1,
+            last_page: total_pages,
+            total_pages,
+            data: bytes.to_vec(),
+        }]);
+    }
+
+    // Page ranges still to extract, the next one last
+    let mut pending: Vec<(u32, u32)> = (1..=total_pages)
+        .step_by(chunking.max_pages as usize)
+        .map(|first| (first, (first + chunking.max_pages - 1).min(total_pages)))
+        .collect();
+    pending.reverse();
+    let mut chunks = Vec::new();
+    while let Some((first_page, last_page)) = pending.pop() {
+        let data = extract_pages(&document, first_page, last_page)?;
+        if data.len() > chunking.max_bytes && first_page < last_page {
+            let middle = first_page + (last_page - first_page) / 2;
+            pending.push((middle + 1, last_page));
+            pending.push((first_page, middle));
+            continue;
+        }
+        chunks.push(PdfChunk {
+            first_page,
+            last_page,
+            total_pages,
+            data,
+        });
+    }
+    debug!(
+        "Split {}-page PDF into {} chunks",
+        total_pages,
+        chunks.len()
+    );
+    Ok(chunks)
+}
+
+/// Save pages `first..=last` of `document` as a new PDF
+fn extract_pages(document: &Document, first: u32, last: u32) -> Result<Vec<u8>> {
+    let mut document = document.clone();
+    let others: Vec<u32> = document
+        .get_pages()
+        .into_keys()
+        .filter(|page| !(first..=last).contains(page))
+        .collect();
+    document.delete_pages(&others);
+    document.prune_objects();
+    let mut data = Vec::new();
+    document.save_to(&mut data)?;
+    Ok(data)
+}
+
+/// How per-chunk answers become one answer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PdfAggregation {
+    /// Join the answers in page order, each under a `Pages a-b:` or `Page n:` heading
+    #[default]
+    Concatenate,
+    /// Ask the model to merge the answers into one, following these instructions
+    Combine(String),
+}
+
+/// The answer for one chunk
+#[derive(Debug, Clone)]
+pub struct PdfChunkAnswer {
+    /// First page of the chunk
+    pub first_page: u32,
+    /// Last page of the chunk
+    pub last_page: u32,
+    /// The model's response for the chunk
+    pub response: GenerateContentResponse,
+}
+
+impl PdfChunkAnswer {
+    /// Text of the response, empty if it had none
+    pub fn text(&self) -> String {
+        self.response.text().unwrap_or_default()
+    }
+}
+
+/// The outcome of [`GeminiClient::generate_over_pdf`]
+#[derive(Debug, Clone)]
+pub struct PdfAnswer {
+    /// The combined answer
+    pub text: String,
+    /// The answer for each chunk, in page order
+    pub chunks: Vec<PdfChunkAnswer>,
+    /// The merging response, for [`PdfAggregation::Combine`] over several chunks
+    pub combined: Option<GenerateContentResponse>,
+}
+
+impl GeminiClient {
+    /// Ask `prompt` about a PDF of any size
+    ///
+    /// The PDF is split with [`split_pdf`] and the chunks are asked about one after another.
+    /// A document that fits in one chunk gets a single request and its answer as is. Parsing
+    /// and splitting run on the blocking thread pool.
+    pub async fn generate_over_pdf(
+        &self,
+        model: Option<&str>,
+        pdf: &[u8],
+        prompt: &str,
+        chunking: &PdfChunking,
+        aggregation: PdfAggregation,
+    ) -> Result<PdfAnswer> {
+        let (bytes, chunking) = (pdf.to_vec(), *chunking);
+        let pieces = tokio::task::spawn_blocking(move || split_pdf(&bytes, &chunking))
+            .await
+            .unwrap_or_else(|e| Err(Error::Config(format!("PDF splitting task failed: {}", e))))?;
+
+        let mut chunks = Vec::new();
+        for chunk in pieces {
+            let response = self
+                .generate_content(model, chunk.request(prompt))
+                .await?
+                .require_content()?;
+            chunks.push(PdfChunkAnswer {
+                first_page: chunk.first_page,
+                last_page: chunk.last_page,
+                response,
+            });
+        }
+
+        if let [only] = chunks.as_slice() {
+            return Ok(PdfAnswer {
+                text: only.text(),
+                chunks,
+                combined: None,
+            });
+        }
+        let partials = concatenate(&chunks);
+        match aggregation {
+            PdfAggregation::Concatenate => Ok(PdfAnswer {
+                text: partials,
+                chunks,
+                combined: None,
+            }),
+            PdfAggregation::Combine(instructions) => {
+                let request = GenerateContentRequest::new(format!(
+                    "{}\n\nThe question was asked separately about each part of a long \
+                     document:\n{}\n\nPartial answers:\n\n{}",
+                    instructions, prompt, partials
+                ));
+                let response = self
+                    .generate_content(model, request)
+                    .await?
+                    .require_content()?;
+                Ok(PdfAnswer {
+                    text: response.text().unwrap_or_default(),
+                    chunks,
+                    combined: Some(response),
+                })
+            }
+        }
+    }
+}
+
+fn concatenate(chunks: &[PdfChunkAnswer]) -> String {
+    chunks
+        .iter()
+        .map(|chunk| {
+            let pages = if chunk.first_page == chunk.last_page {
+                format!("Page {}", chunk.first_page)
+            } else {
+                format!("Pages {}-{}", chunk.first_page, chunk.last_page)
+            };
+            format!("{}:\n{}", pages, chunk.text())
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
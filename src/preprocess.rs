@@ -0,0 +1,264 @@
+//! Input image preprocessing
+//!
+//! Photos straight from a camera are often far larger than the model looks at and carry
+//! EXIF metadata such as GPS coordinates. [`ImagePreprocessing`] downscales them and strips
+//! that metadata before the bytes are base64 encoded, and [`Part::image`] builds an inline
+//! data part from the result.
+//!
+//! Metadata is removed without re-encoding where possible: JPEG `APP1`/`APP13`/comment
+//! segments, PNG text, time and `eXIf` chunks, and WebP `EXIF`/`XMP ` chunks are dropped and
+//! the pixels are left untouched. Images are only decoded and re-encoded when they need
+//! resizing or carry an EXIF rotation that would be lost along with the metadata.
+
+use crate::{
+    error::{Error, Result},
+    models::{InlineData, Part},
+};
+use base64::Engine;
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, metadata::Orientation, DynamicImage,
+    ImageDecoder, ImageFormat, ImageReader,
+};
+use std::io::Cursor;
+
+/// Longest side, in pixels, kept by default
+///
+/// The API scales larger images down itself, so sending more only costs bandwidth.
+pub const DEFAULT_MAX_DIMENSION: u32 = 3072;
+
+/// Default JPEG quality when a JPEG has to be re-encoded
+pub const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+/// How to prepare an image before sending it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePreprocessing {
+    max_dimension: Option<u32>,
+    strip_metadata: bool,
+    jpeg_quality: u8,
+}
+
+impl Default for ImagePreprocessing {
+    fn default() -> Self {
+        Self {
+            max_dimension: Some(DEFAULT_MAX_DIMENSION),
+            strip_metadata: true,
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+        }
+    }
+}
+
+/// An image ready to be sent inline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedImage {
+    /// MIME type of `data`, e.g. `image/jpeg`
+    pub mime_type: String,
+    /// Encoded image bytes
+    pub data: Vec<u8>,
+    /// Width in pixels after processing
+    pub width: u32,
+    /// Height in pixels after processing
+    pub height: u32,
+}
+
+impl PreparedImage {
+    /// Base64 encode the image as inline data
+    pub fn into_inline_data(self) -> InlineData {
+        InlineData {
+            mime_type: self.mime_type,
+            data: base64::engine::general_purpose::STANDARD.encode(self.data),
+        }
+    }
+}
+
+impl ImagePreprocessing {
+    /// Downscale to [`DEFAULT_MAX_DIMENSION`] and strip metadata
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scale images down so that neither side exceeds `pixels`, keeping the aspect ratio
+    pub fn max_dimension(mut self, pixels: u32) -> Self {
+        self.max_dimension = Some(pixels.max(1));
+        self
+    }
+
+    /// Never resize
+    pub fn keep_size(mut self) -> Self {
+        self.max_dimension = None;
+        self
+    }
+
+    /// Remove EXIF, XMP and text metadata (on by default)
+    pub fn strip_metadata(mut self, strip: bool) -> Self {
+        self.strip_metadata = strip;
+        self
+    }
+
+    /// JPEG quality from 1 to 100 used when a JPEG is re-encoded
+    pub fn jpeg_quality(mut self, quality: u8) -> Self {
+        self.jpeg_quality = quality.clamp(1, 100);
+        self
+    }
+
+    /// Process encoded image bytes
+    ///
+    /// JPEGs stay JPEGs; other formats are written as PNG when they have to be re-encoded.
+    /// Only the first frame of an animated image survives re-encoding.
+    pub fn apply(&self, bytes: &[u8]) -> Result<PreparedImage> {
+        let reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+        let format = reader
+            .format()
+            .ok_or_else(|| Error::Config("Unrecognized image format".to_string()))?;
+        let mut decoder = reader.into_decoder()?;
+        let (width, height) = decoder.dimensions();
+        let orientation = decoder.orientation()?;
+
+        let too_large = self
+            .max_dimension
+            .is_some_and(|max| width.max(height) > max);
+        let rotated = self.strip_metadata && orientation != Orientation::NoTransforms;
+        if !too_large && !rotated {
+            let data = if self.strip_metadata {
+                strip_metadata(format, bytes)?
+            } else {
+                bytes.to_vec()
+            };
+            return Ok(PreparedImage {
+                mime_type: format.to_mime_type().to_string(),
+                data,
+                width,
+                height,
+            });
+        }
+
+        let mut image = DynamicImage::from_decoder(decoder)?;
+        image.apply_orientation(orientation);
+        if let Some(max) = self.max_dimension.filter(|_| too_large) {
+            image = image.resize(max, max, FilterType::Lanczos3);
+        }
+
+        let mut data = Vec::new();
+        let format = if format == ImageFormat::Jpeg {
+            let encoder = JpegEncoder::new_with_quality(&mut data, self.jpeg_quality);
+            DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)?;
+            ImageFormat::Jpeg
+        } else {
+            image.write_to(&mut Cursor::new(&mut data), ImageFormat::Png)?;
+            ImageFormat::Png
+        };
+        Ok(PreparedImage {
+            mime_type: format.to_mime_type().to_string(),
+            data,
+            width: image.width(),
+            height: image.height(),
+        })
+    }
+}
+
+impl Part {
+    /// Create an inline image part from encoded bytes after preprocessing them
+    pub fn image(bytes: &[u8], preprocessing: &ImagePreprocessing) -> Result<Self> {
+        Ok(Part::InlineData {
+            inline_data: preprocessing.apply(bytes)?.into_inline_data(),
+            thought_signature: None,
+        })
+    }
+}
+
+/// Drop metadata blocks from an encoded image, leaving the pixel data as is
+fn strip_metadata(format: ImageFormat, bytes: &[u8]) -> Result<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => strip_jpeg(bytes),
+        ImageFormat::Png => strip_png(bytes),
+        ImageFormat::WebP => strip_webp(bytes),
+        _ => Ok(bytes.to_vec()),
+    }
+}
+
+fn truncated(format: &str) -> Error {
+    Error::Config(format!("Truncated {} image", format))
+}
+
+fn strip_jpeg(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut out = bytes[..2].to_vec();
+    let mut pos = 2;
+    loop {
+        // Markers may be preceded by any number of 0xFF fill bytes
+        while bytes.get(pos) == Some(&0xFF) && bytes.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        let marker = match bytes.get(pos..pos + 2) {
+            Some([0xFF, marker]) => *marker,
+            _ => return Err(truncated("JPEG")),
+        };
+        // Start of scan: the entropy-coded data and everything after it is kept
+        if marker == 0xDA || marker == 0xD9 {
+            out.extend_from_slice(&bytes[pos..]);
+            return Ok(out);
+        }
+        let len = match bytes.get(pos + 2..pos + 4) {
+            Some(len) => u16::from_be_bytes([len[0], len[1]]) as usize,
+            None => return Err(truncated("JPEG")),
+        };
+        let end = pos + 2 + len;
+        if end > bytes.len() {
+            return Err(truncated("JPEG"));
+        }
+        // APP1 holds EXIF and XMP, APP13 holds IPTC, 0xFE is a comment
+        if !matches!(marker, 0xE1 | 0xED | 0xFE) {
+            out.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+    }
+}
+
+fn strip_png(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut out = bytes[..8].to_vec();
+    let mut pos = 8;
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + 8).ok_or_else(|| truncated("PNG"))?;
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let end = pos + 12 + len;
+        if end > bytes.len() {
+            return Err(truncated("PNG"));
+        }
+        if !matches!(
+            &header[4..8],
+            b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME"
+        ) {
+            out.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+    }
+    Ok(out)
+}
+
+fn strip_webp(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut out = bytes[..12].to_vec();
+    let mut pos = 12;
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + 8).ok_or_else(|| truncated("WebP"))?;
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let end = (pos + 8 + len + len % 2).min(bytes.len());
+        if pos + 8 + len > bytes.len() {
+            return Err(truncated("WebP"));
+        }
+        match &header[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                let start = out.len();
+                out.extend_from_slice(&bytes[pos..end]);
+                // Clear the EXIF and XMP presence flags
+                if let Some(flags) = out.get_mut(start + 8) {
+                    *flags &= !0x0C;
+                }
+            }
+            _ => out.extend_from_slice(&bytes[pos..end]),
+        }
+        pos = end;
+    }
+    let riff_len = u32::try_from(out.len() - 8)
+        .map_err(|_| Error::Config("WebP image too large".to_string()))?;
+    out[4..8].copy_from_slice(&riff_len.to_le_bytes());
+    Ok(out)
+}
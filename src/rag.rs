@@ -0,0 +1,380 @@
+//! Retrieval-augmented generation
+//!
+//! [`Rag`] splits documents into chunks, embeds them with an [`Embedder`] into a
+//! [`VectorStore`], retrieves the chunks closest to a question and asks the model to answer
+//! from them, citing each chunk by its number. [`GeminiEmbedder`] embeds with the Gemini
+//! embeddings API; implement [`Embedder`] to use another service. [`InMemoryVectorStore`] is enough for small corpora; implement
+//! [`VectorStore`] to use a database.
+
+use crate::{
+    client::GeminiClient,
+    error::{Error, Result},
+    models::{Content, EmbeddingTaskType, GenerateContentRequest, GenerateContentResponse},
+};
+use futures::future::BoxFuture;
+use std::sync::RwLock;
+use tracing::debug;
+
+/// Default chunk length in characters
+const DEFAULT_CHUNK_CHARS: usize = 1000;
+
+/// Default overlap between consecutive chunks in characters
+const DEFAULT_CHUNK_OVERLAP: usize = 100;
+
+/// Default number of chunks retrieved per question
+const DEFAULT_TOP_K: usize = 4;
+
+/// A piece of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// Unique identifier, `{source}#{index}` for chunks made by [`Rag::add_document`]
+    pub id: String,
+    /// Where the text comes from, e.g. a file name or URL
+    pub source: String,
+    /// The chunk's text
+    pub text: String,
+}
+
+/// A retrieved chunk with its similarity to the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredChunk {
+    /// The chunk
+    pub chunk: Chunk,
+    /// Cosine similarity to the query, from -1.0 to 1.0
+    pub score: f32,
+}
+
+/// Source of embedding vectors for chunks and queries
+///
+/// Documents and queries must be embedded by the same model. Services that optimize
+/// embeddings for retrieval can treat the two calls differently.
+pub trait Embedder: Send + Sync {
+    /// Embed document chunks, returning one vector per text in order
+    fn embed_documents<'a>(&'a self, texts: &'a [String]) -> BoxFuture<'a, Result<Vec<Vec<f32>>>>;
+
+    /// Embed a search query
+    fn embed_query<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<Vec<f32>>>;
+}
+
+/// An [`Embedder`] backed by the Gemini embeddings API
+///
+/// Documents are embedded with `batchEmbedContents` as `RETRIEVAL_DOCUMENT` and queries as
+/// `RETRIEVAL_QUERY`.
+#[derive(Clone)]
+pub struct GeminiEmbedder {
+    client: GeminiClient,
+    model: Option<String>,
+}
+
+impl GeminiEmbedder {
+    /// Embed with `gemini-embedding-001`
+    pub fn new(client: GeminiClient) -> Self {
+        Self {
+            client,
+            model: None,
+        }
+    }
+
+    /// Embed with another model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    async fn embed(&self, texts: &[String], task_type: EmbeddingTaskType) -> Result<Vec<Vec<f32>>> {
+        let contents = texts
+            .iter()
+            .map(|text| Content::user(text.as_str()))
+            .collect();
+        let embeddings = self
+            .client
+            .batch_embed_contents(self.model.as_deref(), contents, Some(task_type))
+            .await?;
+        Ok(embeddings
+            .into_iter()
+            .map(|embedding| embedding.values)
+            .collect())
+    }
+}
+
+impl Embedder for GeminiEmbedder {
+    fn embed_documents<'a>(&'a self, texts: &'a [String]) -> BoxFuture<'a, Result<Vec<Vec<f32>>>> {
+        Box::pin(self.embed(texts, EmbeddingTaskType::RetrievalDocument))
+    }
+
+    fn embed_query<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<Vec<f32>>> {
+        Box::pin(async move {
+            let mut embeddings = self
+                .embed(&[text.to_string()], EmbeddingTaskType::RetrievalQuery)
+                .await?;
+            embeddings
+                .pop()
+                .ok_or_else(|| Error::InvalidResponse("No embedding returned".to_string()))
+        })
+    }
+}
+
+/// Storage for embedded chunks with nearest-neighbour search
+pub trait VectorStore: Send + Sync {
+    /// Store chunks with their embeddings, replacing chunks with the same id
+    fn upsert<'a>(&'a self, entries: Vec<(Chunk, Vec<f32>)>) -> BoxFuture<'a, Result<()>>;
+
+    /// The `top_k` chunks most similar to `embedding`, best first
+    fn search<'a>(
+        &'a self,
+        embedding: &'a [f32],
+        top_k: usize,
+    ) -> BoxFuture<'a, Result<Vec<ScoredChunk>>>;
+}
+
+/// A vector store searched by brute force in memory
+#[derive(Debug, Default)]
+pub struct InMemoryVectorStore {
+    entries: RwLock<Vec<(Chunk, Vec<f32>)>>,
+}
+
+impl InMemoryVectorStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored chunks
+    pub fn len(&self) -> usize {
+        self.entries.read().map_or(0, |entries| entries.len())
+    }
+
+    /// Whether the store holds no chunks
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl VectorStore for InMemoryVectorStore {
+    fn upsert<'a>(&'a self, new_entries: Vec<(Chunk, Vec<f32>)>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut entries = self
+                .entries
+                .write()
+                .map_err(|_| Error::Config("Vector store lock poisoned".to_string()))?;
+            for (chunk, embedding) in new_entries {
+                match entries.iter_mut().find(|(stored, _)| stored.id == chunk.id) {
+                    Some(entry) => *entry = (chunk, embedding),
+                    None => entries.push((chunk, embedding)),
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn search<'a>(
+        &'a self,
+        embedding: &'a [f32],
+        top_k: usize,
+    ) -> BoxFuture<'a, Result<Vec<ScoredChunk>>> {
+        Box::pin(async move {
+            let entries = self
+                .entries
+                .read()
+                .map_err(|_| Error::Config("Vector store lock poisoned".to_string()))?;
+            let mut scored: Vec<ScoredChunk> = entries
+                .iter()
+                .map(|(chunk, stored)| ScoredChunk {
+                    chunk: chunk.clone(),
+                    score: cosine_similarity(embedding, stored),
+                })
+                .collect();
+            scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+            scored.truncate(top_k);
+            Ok(scored)
+        })
+    }
+}
+
+/// Cosine similarity of two vectors; 0.0 if either is zero or their lengths differ
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// Split text into chunks of at most `max_chars` characters, overlapping by up to `overlap`
+///
+/// Chunks end at the last paragraph break, line break or space in the window when there is
+/// one, and the overlap starts at a word, so words are not cut in half.
+pub fn chunk_text(text: &str, max_chars: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let max_chars = max_chars.max(1);
+    let overlap = overlap.min(max_chars / 2);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut end = (start + max_chars).min(chars.len());
+        if end < chars.len() {
+            let window = &chars[start..end];
+            let break_at = |pattern: &dyn Fn(usize) -> bool| {
+                (1..window.len()).rev().find(|&i| pattern(i)).map(|i| i + 1)
+            };
+            let boundary = break_at(&|i| window[i].1 == '\n' && window[i - 1].1 == '\n')
+                .or_else(|| break_at(&|i| window[i].1 == '\n'))
+                .or_else(|| break_at(&|i| window[i].1.is_whitespace()));
+            if let Some(boundary) = boundary.filter(|&b| b > overlap) {
+                end = start + boundary;
+            }
+        }
+
+        let byte_start = chars[start].0;
+        let byte_end = chars.get(end).map_or(text.len(), |(i, _)| *i);
+        let chunk = text[byte_start..byte_end].trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_string());
+        }
+        if end == chars.len() {
+            break;
+        }
+
+        // Start the overlap at a word
+        start = end - overlap;
+        if !chars[start - 1].1.is_whitespace() {
+            if let Some(offset) = chars[start..end]
+                .iter()
+                .position(|(_, c)| c.is_whitespace())
+            {
+                start += offset + 1;
+            }
+        }
+    }
+    chunks
+}
+
+/// Number the chunks as context and ask the model to answer from them with citations
+pub fn build_prompt(question: &str, chunks: &[ScoredChunk]) -> String {
+    let context: Vec<String> = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, scored)| {
+            format!(
+                "[{}] ({})\n{}",
+                i + 1,
+                scored.chunk.source,
+                scored.chunk.text
+            )
+        })
+        .collect();
+    format!(
+        "Answer the question using only the numbered sources below. Cite the sources you use \
+         as [1], [2], ... after the sentences they support. If the sources do not contain the \
+         answer, say so.\n\nSources:\n{}\n\nQuestion: {}",
+        context.join("\n\n"),
+        question
+    )
+}
+
+/// An answer with the chunks it was generated from
+#[derive(Debug, Clone)]
+pub struct RagAnswer {
+    /// The model's answer, citing sources as `[n]`
+    pub response: GenerateContentResponse,
+    /// The retrieved chunks; `[n]` in the answer refers to `sources[n - 1]`
+    pub sources: Vec<ScoredChunk>,
+}
+
+/// Chunking, embedding, retrieval and generation over a vector store
+pub struct Rag<E: Embedder, S: VectorStore> {
+    client: GeminiClient,
+    embedder: E,
+    store: S,
+    chunk_chars: usize,
+    chunk_overlap: usize,
+    top_k: usize,
+}
+
+impl<E: Embedder, S: VectorStore> Rag<E, S> {
+    /// Embed with `embedder`, retrieve from `store` and answer with `client`
+    pub fn new(client: GeminiClient, embedder: E, store: S) -> Self {
+        Self {
+            client,
+            embedder,
+            store,
+            chunk_chars: DEFAULT_CHUNK_CHARS,
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+            top_k: DEFAULT_TOP_K,
+        }
+    }
+
+    /// Chunk length and overlap in characters (default 1000 and 100)
+    pub fn with_chunking(mut self, max_chars: usize, overlap: usize) -> Self {
+        self.chunk_chars = max_chars;
+        self.chunk_overlap = overlap;
+        self
+    }
+
+    /// Number of chunks retrieved per question (default 4)
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// The underlying store
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Chunk, embed and store a document, returning the number of chunks added
+    pub async fn add_document(&self, source: impl Into<String>, text: &str) -> Result<usize> {
+        let source = source.into();
+        let texts = chunk_text(text, self.chunk_chars, self.chunk_overlap);
+        if texts.is_empty() {
+            return Ok(0);
+        }
+
+        let embeddings = self.embedder.embed_documents(&texts).await?;
+        if embeddings.len() != texts.len() {
+            return Err(Error::InvalidResponse(format!(
+                "Expected {} embeddings, got {}",
+                texts.len(),
+                embeddings.len()
+            )));
+        }
+        let count = texts.len();
+        let entries = texts
+            .into_iter()
+            .zip(embeddings)
+            .enumerate()
+            .map(|(i, (text, embedding))| {
+                let chunk = Chunk {
+                    id: format!("{}#{}", source, i),
+                    source: source.clone(),
+                    text,
+                };
+                (chunk, embedding)
+            })
+            .collect();
+        self.store.upsert(entries).await?;
+        debug!("Added {} chunks from {}", count, source);
+        Ok(count)
+    }
+
+    /// The chunks most relevant to a query
+    pub async fn retrieve(&self, query: &str) -> Result<Vec<ScoredChunk>> {
+        let embedding = self.embedder.embed_query(query).await?;
+        self.store.search(&embedding, self.top_k).await
+    }
+
+    /// Retrieve chunks for a question and answer it from them
+    pub async fn answer(&self, model: Option<&str>, question: &str) -> Result<RagAnswer> {
+        let sources = self.retrieve(question).await?;
+        let request = GenerateContentRequest::new(build_prompt(question, &sources));
+        let response = self.client.generate_content(model, request).await?;
+        Ok(RagAnswer { response, sources })
+    }
+}
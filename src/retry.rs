@@ -0,0 +1,44 @@
+//! Retry policies deciding which failures are retried
+
+use crate::{error::Error, models::GenerateContentResponse};
+
+/// Decides whether a failed (or unsatisfactory) request is sent again
+///
+/// The number of attempts and the delay between them still come from
+/// [`RetryConfig`](crate::config::RetryConfig); a policy only decides *whether* to retry.
+///
+/// ```
+/// use gemini_rust::{Error, RetryPolicy};
+///
+/// /// Also retry request timeouts, but never retry 500s
+/// struct Cautious;
+///
+/// impl RetryPolicy for Cautious {
+///     fn should_retry(&self, error: &Error, _attempt: u32) -> bool {
+///         match error {
+///             Error::Api { status: 408, .. } => true,
+///             Error::Api { status: 500, .. } => false,
+///             _ => error.is_retryable(),
+///         }
+///     }
+/// }
+/// ```
+pub trait RetryPolicy: Send + Sync {
+    /// Whether to retry after `error` on the given (1-based) attempt
+    fn should_retry(&self, error: &Error, attempt: u32) -> bool {
+        let _ = attempt;
+        error.is_retryable()
+    }
+
+    /// Whether to retry a successful `generate_content` response, e.g. one with no candidates
+    fn should_retry_response(&self, response: &GenerateContentResponse) -> bool {
+        let _ = response;
+        false
+    }
+}
+
+/// Retries what [`Error::is_retryable`] reports as retryable, and no successful responses
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultRetryPolicy;
+
+impl RetryPolicy for DefaultRetryPolicy {}
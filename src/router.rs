@@ -0,0 +1,220 @@
+//! Routing requests to models by task complexity
+//!
+//! A [`Router`] rates each request's [`TaskComplexity`] — with rules over the prompt, a
+//! cheap classification call, or a fallback level — and sends it to the route configured for
+//! that level, so expensive models only see the prompts that need them.
+
+use crate::{
+    client::GeminiClient,
+    error::Result,
+    models::{
+        GenerateContentRequest, GenerateContentResponse, GenerationConfig, Part, ResponseSchema,
+        Role, SchemaType,
+    },
+    thinking::TaskComplexity,
+};
+use std::collections::BTreeMap;
+use tracing::{debug, warn};
+
+/// A model and the generation settings to use with it
+#[derive(Debug, Clone)]
+pub struct Route {
+    /// Model name
+    pub model: String,
+    /// Settings filling in whatever the request leaves unset
+    pub generation_config: Option<GenerationConfig>,
+}
+
+impl Route {
+    /// Route to a model with the request's own settings
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            generation_config: None,
+        }
+    }
+
+    /// Fill unset request settings from `config`
+    pub fn with_generation_config(mut self, config: GenerationConfig) -> Self {
+        self.generation_config = Some(config);
+        self
+    }
+}
+
+/// A response and how it was routed
+#[derive(Debug, Clone)]
+pub struct RoutedResponse {
+    /// The rated complexity
+    pub complexity: TaskComplexity,
+    /// The model that answered
+    pub model: String,
+    /// The response
+    pub response: GenerateContentResponse,
+}
+
+type Rule = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Dispatches requests to models by task complexity
+///
+/// A complexity without a route goes to the next more complex level that has one, then to
+/// the next simpler one, and to the client's default model if no route is set.
+pub struct Router {
+    client: GeminiClient,
+    routes: BTreeMap<TaskComplexity, Route>,
+    rules: Vec<(Rule, TaskComplexity)>,
+    classifier_model: Option<String>,
+    fallback: TaskComplexity,
+}
+
+impl Router {
+    /// Create a router with no routes, rating every request `Moderate`
+    pub fn new(client: GeminiClient) -> Self {
+        Self {
+            client,
+            routes: BTreeMap::new(),
+            rules: Vec::new(),
+            classifier_model: None,
+            fallback: TaskComplexity::Moderate,
+        }
+    }
+
+    /// Send requests of a complexity to a route
+    pub fn route(mut self, complexity: TaskComplexity, route: Route) -> Self {
+        self.routes.insert(complexity, route);
+        self
+    }
+
+    /// Rate requests whose prompt matches `matches` as `complexity`
+    ///
+    /// Rules are tried in the order they were added, before any classification call. The
+    /// prompt is the text of the last user turn.
+    pub fn rule(
+        mut self,
+        matches: impl Fn(&str) -> bool + Send + Sync + 'static,
+        complexity: TaskComplexity,
+    ) -> Self {
+        self.rules.push((Box::new(matches), complexity));
+        self
+    }
+
+    /// Ask `model` to rate requests no rule matched
+    pub fn classify_with(mut self, model: impl Into<String>) -> Self {
+        self.classifier_model = Some(model.into());
+        self
+    }
+
+    /// Complexity for requests neither rules nor the classifier rated (default `Moderate`)
+    pub fn fallback(mut self, complexity: TaskComplexity) -> Self {
+        self.fallback = complexity;
+        self
+    }
+
+    /// Rate a request's complexity
+    pub async fn rate(&self, request: &GenerateContentRequest) -> Result<TaskComplexity> {
+        let prompt = prompt_text(request);
+        if let Some((_, complexity)) = self.rules.iter().find(|(rule, _)| rule(&prompt)) {
+            return Ok(*complexity);
+        }
+        match &self.classifier_model {
+            Some(model) => self.classify(model, &prompt).await,
+            None => Ok(self.fallback),
+        }
+    }
+
+    /// Rate a request and send it to the matching route
+    pub async fn generate(&self, mut request: GenerateContentRequest) -> Result<RoutedResponse> {
+        let complexity = self.rate(&request).await?;
+        let route = self
+            .routes
+            .range(complexity..)
+            .next()
+            .or_else(|| self.routes.range(..complexity).next_back())
+            .map(|(_, route)| route);
+
+        let model = match route {
+            Some(route) => {
+                if let Some(defaults) = &route.generation_config {
+                    request
+                        .generation_config
+                        .get_or_insert_with(Default::default)
+                        .merge_defaults(defaults);
+                }
+                route.model.clone()
+            }
+            None => self.client.config().get_model_name(None),
+        };
+        debug!("Routing {:?} request to {}", complexity, model);
+
+        let response = self.client.generate_content(Some(&model), request).await?;
+        Ok(RoutedResponse {
+            complexity,
+            model,
+            response,
+        })
+    }
+
+    async fn classify(&self, model: &str, prompt: &str) -> Result<TaskComplexity> {
+        let labels = ["SIMPLE", "MODERATE", "COMPLEX", "VERY_COMPLEX"];
+        let mut request = GenerateContentRequest::new(format!(
+            "Rate how much reasoning it takes to answer the request below. SIMPLE: fact \
+             lookup or small talk. MODERATE: short explanations or comparisons. COMPLEX: \
+             multi-step analysis. VERY_COMPLEX: deep research, proofs or large designs.\n\n\
+             Request:\n{}",
+            prompt
+        ));
+        request.generation_config = Some(GenerationConfig {
+            response_mime_type: Some("text/x.enum".to_string()),
+            response_schema: Some(ResponseSchema {
+                schema_type: SchemaType::String,
+                format: Some("enum".to_string()),
+                description: None,
+                nullable: None,
+                enum_values: Some(labels.iter().map(|label| label.to_string()).collect()),
+                properties: None,
+                required: None,
+                property_ordering: None,
+                items: None,
+                min_items: None,
+                max_items: None,
+            }),
+            ..Default::default()
+        });
+
+        let response = self.client.generate_content(Some(model), request).await?;
+        let label = response.text().unwrap_or_default();
+        Ok(match label.trim() {
+            "SIMPLE" => TaskComplexity::Simple,
+            "MODERATE" => TaskComplexity::Moderate,
+            "COMPLEX" => TaskComplexity::Complex,
+            "VERY_COMPLEX" => TaskComplexity::VeryComplex,
+            other => {
+                warn!(
+                    "Unexpected complexity label {:?}, using {:?}",
+                    other, self.fallback
+                );
+                self.fallback
+            }
+        })
+    }
+}
+
+/// Text of the last user turn
+fn prompt_text(request: &GenerateContentRequest) -> String {
+    request
+        .contents
+        .iter()
+        .rev()
+        .find(|content| content.role == Role::User)
+        .map(|content| {
+            content
+                .parts
+                .iter()
+                .filter_map(|part| match part {
+                    Part::Text { text, .. } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default()
+}
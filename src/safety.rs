@@ -0,0 +1,243 @@
+//! Retrying safety-blocked requests
+//!
+//! Benign prompts sometimes trip the safety filters. [`GeminiClient::generate_with_safety_retry`]
+//! reruns a blocked request with alternative phrasings of the last user message supplied by the
+//! caller and then, if allowed, with safety thresholds relaxed no further than a bound,
+//! reporting which [`SafetyAdjustment`] produced the answer.
+
+use crate::{
+    client::GeminiClient,
+    error::Result,
+    models::{
+        BlockReason, FinishReason, GenerateContentRequest, GenerateContentResponse,
+        HarmBlockThreshold, HarmCategory, HarmProbability, Part, Role, SafetySetting,
+    },
+};
+use tracing::{debug, warn};
+
+/// Every category the safety settings cover
+const HARM_CATEGORIES: [HarmCategory; 4] = [
+    HarmCategory::HateSpeech,
+    HarmCategory::DangerousContent,
+    HarmCategory::SexuallyExplicit,
+    HarmCategory::Harassment,
+];
+
+/// How to rerun a safety-blocked request
+#[derive(Debug, Clone, Default)]
+pub struct SafetyRetry {
+    rephrasings: Vec<String>,
+    relax_to: Option<HarmBlockThreshold>,
+}
+
+impl SafetyRetry {
+    /// Do not retry until rephrasings or relaxed settings are added
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Try this wording of the last user message if the request is blocked
+    ///
+    /// Rephrasings are tried in the order they were added, with the original safety settings.
+    pub fn rephrase(mut self, text: impl Into<String>) -> Self {
+        self.rephrasings.push(text.into());
+        self
+    }
+
+    /// As a last resort, loosen the blocking categories to at most `threshold`
+    ///
+    /// Categories already set looser than `threshold` are left alone, so the retry never
+    /// blocks less than the bound allows.
+    pub fn relax_to(mut self, threshold: HarmBlockThreshold) -> Self {
+        self.relax_to = Some(threshold);
+        self
+    }
+}
+
+/// A change made to a blocked request
+#[derive(Debug, Clone)]
+pub enum SafetyAdjustment {
+    /// The text of the last user message was replaced by a rephrasing
+    Rephrased {
+        /// Index of the rephrasing in the order added
+        index: usize,
+        /// The text sent instead
+        text: String,
+    },
+    /// Safety settings were relaxed for these categories
+    RelaxedSafety {
+        /// The settings added to the request
+        settings: Vec<SafetySetting>,
+    },
+}
+
+/// The outcome of [`GeminiClient::generate_with_safety_retry`]
+#[derive(Debug, Clone)]
+pub struct SafetyRetryOutcome {
+    /// The last response; still blocked if every retry was
+    pub response: GenerateContentResponse,
+    /// The change behind the response, `None` if it answers the original request
+    pub adjustment: Option<SafetyAdjustment>,
+    /// Number of requests sent
+    pub attempts: u32,
+}
+
+impl GenerateContentResponse {
+    /// Whether the prompt or the first candidate was blocked by the safety filters
+    pub fn is_safety_blocked(&self) -> bool {
+        let prompt_blocked = self
+            .prompt_feedback
+            .as_ref()
+            .is_some_and(|feedback| matches!(feedback.block_reason, Some(BlockReason::Safety)));
+        let candidate_blocked = self
+            .candidates
+            .first()
+            .is_some_and(|candidate| candidate.finish_reason == Some(FinishReason::Safety));
+        prompt_blocked || candidate_blocked
+    }
+
+    /// Categories rated medium or high for the prompt or the first candidate
+    fn flagged_categories(&self) -> Vec<HarmCategory> {
+        let prompt = self
+            .prompt_feedback
+            .as_ref()
+            .and_then(|feedback| feedback.safety_ratings.as_ref());
+        let candidate = self
+            .candidates
+            .first()
+            .and_then(|candidate| candidate.safety_ratings.as_ref());
+        let mut categories = Vec::new();
+        for rating in prompt.into_iter().chain(candidate).flatten() {
+            if matches!(
+                rating.probability,
+                HarmProbability::Medium | HarmProbability::High
+            ) && !categories.contains(&rating.category)
+            {
+                categories.push(rating.category);
+            }
+        }
+        categories
+    }
+}
+
+impl GeminiClient {
+    /// Generate content, rerunning the request as `retry` allows while it is safety-blocked
+    ///
+    /// The rerun changes one thing at a time: each rephrasing in turn, then the relaxed
+    /// settings with the original wording. Errors other than a block are returned as usual.
+    pub async fn generate_with_safety_retry(
+        &self,
+        model: Option<&str>,
+        request: GenerateContentRequest,
+        retry: &SafetyRetry,
+    ) -> Result<SafetyRetryOutcome> {
+        let mut response = self.generate_content(model, request.clone()).await?;
+        let mut attempts = 1;
+        if !response.is_safety_blocked() {
+            return Ok(SafetyRetryOutcome {
+                response,
+                adjustment: None,
+                attempts,
+            });
+        }
+        let flagged = response.flagged_categories();
+
+        let mut adjustments: Vec<(SafetyAdjustment, GenerateContentRequest)> = Vec::new();
+        if let Some(index) = request
+            .contents
+            .iter()
+            .rposition(|content| content.role == Role::User)
+        {
+            for (i, text) in retry.rephrasings.iter().enumerate() {
+                let mut rephrased = request.clone();
+                let parts = &mut rephrased.contents[index].parts;
+                parts.retain(|part| !matches!(part, Part::Text { .. }));
+                parts.insert(0, Part::from(text.as_str()));
+                adjustments.push((
+                    SafetyAdjustment::Rephrased {
+                        index: i,
+                        text: text.clone(),
+                    },
+                    rephrased,
+                ));
+            }
+        }
+        if let Some(threshold) = retry.relax_to {
+            let settings = self.relaxed_settings(&request, &flagged, threshold);
+            if !settings.is_empty() {
+                let mut relaxed = request.clone();
+                let safety_settings = relaxed.safety_settings.get_or_insert_with(Vec::new);
+                safety_settings.retain(|setting| {
+                    !settings
+                        .iter()
+                        .any(|relaxed| relaxed.category == setting.category)
+                });
+                safety_settings.extend(settings.iter().cloned());
+                adjustments.push((SafetyAdjustment::RelaxedSafety { settings }, relaxed));
+            }
+        }
+
+        let mut last_adjustment = None;
+        for (adjustment, request) in adjustments {
+            warn!("Request was safety-blocked, retrying with {:?}", adjustment);
+            response = self.generate_content(model, request).await?;
+            attempts += 1;
+            last_adjustment = Some(adjustment);
+            if !response.is_safety_blocked() {
+                break;
+            }
+        }
+        if response.is_safety_blocked() {
+            debug!("Request still blocked after {} attempts", attempts);
+        }
+        Ok(SafetyRetryOutcome {
+            response,
+            adjustment: last_adjustment,
+            attempts,
+        })
+    }
+
+    /// Settings loosening the flagged categories, or all of them if none were flagged, to
+    /// `threshold` where they are currently stricter
+    fn relaxed_settings(
+        &self,
+        request: &GenerateContentRequest,
+        flagged: &[HarmCategory],
+        threshold: HarmBlockThreshold,
+    ) -> Vec<SafetySetting> {
+        let categories = if flagged.is_empty() {
+            &HARM_CATEGORIES[..]
+        } else {
+            flagged
+        };
+        let current = |category: HarmCategory| {
+            request
+                .safety_settings
+                .iter()
+                .flatten()
+                .chain(&self.config().default_safety_settings)
+                .find(|setting| setting.category == category)
+                .map(|setting| setting.threshold)
+        };
+        categories
+            .iter()
+            .filter(|&&category| {
+                current(category).is_none_or(|current| looseness(current) < looseness(threshold))
+            })
+            .map(|&category| SafetySetting {
+                category,
+                threshold,
+            })
+            .collect()
+    }
+}
+
+/// Rank thresholds from strictest to loosest
+fn looseness(threshold: HarmBlockThreshold) -> u8 {
+    match threshold {
+        HarmBlockThreshold::BlockLowAndAbove => 0,
+        HarmBlockThreshold::BlockMediumAndAbove => 1,
+        HarmBlockThreshold::BlockOnlyHigh => 2,
+        HarmBlockThreshold::BlockNone => 3,
+    }
+}
@@ -0,0 +1,202 @@
+//! Conversion from `schemars` JSON schemas into Gemini response schemas
+
+use crate::{
+    error::{Error, Result},
+    models::{ResponseSchema, SchemaType},
+};
+use schemars::{
+    gen::SchemaSettings,
+    schema::{InstanceType, Schema, SchemaObject, SingleOrVec},
+    JsonSchema,
+};
+use std::collections::HashMap;
+
+/// Formats the Gemini API understands; anything else is dropped
+const SUPPORTED_FORMATS: &[&str] = &["int32", "int64", "float", "double", "date-time", "enum"];
+
+/// Generate a response schema for a Rust type
+pub(crate) fn response_schema_for<T: JsonSchema>() -> Result<ResponseSchema> {
+    let root = SchemaSettings::openapi3()
+        .with(|settings| {
+            settings.inline_subschemas = true;
+            settings.option_add_null_type = false;
+        })
+        .into_generator()
+        .into_root_schema_for::<T>();
+
+    convert_object(root.schema)
+}
+
+fn convert(schema: Schema) -> Result<ResponseSchema> {
+    match schema {
+        Schema::Object(object) => convert_object(object),
+        Schema::Bool(_) => Err(Error::SchemaValidation(
+            "boolean schemas are not supported by the Gemini API".to_string(),
+        )),
+    }
+}
+
+fn convert_object(mut object: SchemaObject) -> Result<ResponseSchema> {
+    if let Some(reference) = &object.reference {
+        return Err(Error::SchemaValidation(format!(
+            "unresolved schema reference {} (recursive types are not supported)",
+            reference
+        )));
+    }
+
+    let description = object
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.description.clone());
+    let mut nullable = object
+        .extensions
+        .get("nullable")
+        .and_then(|value| value.as_bool());
+
+    // A single `allOf` entry is how schemars attaches metadata to an inlined subschema
+    if let Some(subschemas) = object.subschemas.take() {
+        if let Some(all_of) = subschemas.all_of {
+            if let [inner] = all_of.as_slice() {
+                let mut schema = convert(inner.clone())?;
+                schema.description = description.or(schema.description);
+                schema.nullable = nullable.or(schema.nullable);
+                return Ok(schema);
+            }
+        }
+
+        // Unit enums with documented variants are emitted as a `oneOf` of single-value enums
+        let variants = subschemas.one_of.or(subschemas.any_of).unwrap_or_default();
+        if !variants.is_empty() {
+            let mut values = Vec::new();
+            for variant in variants {
+                match variant {
+                    Schema::Object(SchemaObject {
+                        enum_values: Some(enum_values),
+                        ..
+                    }) => values.extend(enum_values),
+                    _ => {
+                        return Err(Error::SchemaValidation(
+                            "only unit-variant enums are supported in response schemas".to_string(),
+                        ))
+                    }
+                }
+            }
+            object.enum_values = Some(values);
+            object.instance_type = Some(InstanceType::String.into());
+        }
+    }
+
+    let schema_type = match object.instance_type {
+        Some(SingleOrVec::Single(instance_type)) => convert_type(*instance_type)?,
+        Some(SingleOrVec::Vec(types)) => {
+            let mut non_null = types.into_iter().filter(|t| *t != InstanceType::Null);
+            let instance_type = non_null.next().ok_or_else(|| {
+                Error::SchemaValidation("schema has no non-null type".to_string())
+            })?;
+            if non_null.next().is_some() {
+                return Err(Error::SchemaValidation(
+                    "union types are not supported in response schemas".to_string(),
+                ));
+            }
+            nullable = Some(true);
+            convert_type(instance_type)?
+        }
+        None if object.enum_values.is_some() => SchemaType::String,
+        None => {
+            return Err(Error::SchemaValidation(
+                "schema is missing a type".to_string(),
+            ))
+        }
+    };
+
+    let enum_values = object
+        .enum_values
+        .map(|values| {
+            values
+                .into_iter()
+                .filter(|value| !value.is_null())
+                .map(|value| match value {
+                    serde_json::Value::String(s) => Ok(s),
+                    other => Err(Error::SchemaValidation(format!(
+                        "enum value {} is not a string",
+                        other
+                    ))),
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?;
+
+    let (properties, required, property_ordering) = match object.object {
+        Some(validation) => {
+            let mut properties = HashMap::new();
+            let mut ordering = Vec::new();
+            for (name, schema) in validation.properties {
+                properties.insert(name.clone(), convert(schema)?);
+                ordering.push(name);
+            }
+            let required: Vec<String> = ordering
+                .iter()
+                .filter(|name| validation.required.contains(*name))
+                .cloned()
+                .collect();
+            (
+                Some(properties),
+                (!required.is_empty()).then_some(required),
+                (!ordering.is_empty()).then_some(ordering),
+            )
+        }
+        None => (None, None, None),
+    };
+
+    let (items, min_items, max_items) = match object.array {
+        Some(validation) => {
+            let items = match validation.items {
+                Some(SingleOrVec::Single(schema)) => Some(Box::new(convert(*schema)?)),
+                Some(SingleOrVec::Vec(_)) => {
+                    return Err(Error::SchemaValidation(
+                        "tuple types are not supported in response schemas".to_string(),
+                    ))
+                }
+                None => None,
+            };
+            (
+                items,
+                validation.min_items.map(|n| n as i32),
+                validation.max_items.map(|n| n as i32),
+            )
+        }
+        None => (None, None, None),
+    };
+
+    let format = object
+        .format
+        .filter(|format| SUPPORTED_FORMATS.contains(&format.as_str()));
+
+    Ok(ResponseSchema {
+        schema_type,
+        format,
+        description,
+        nullable,
+        enum_values,
+        properties,
+        required,
+        property_ordering,
+        items,
+        min_items,
+        max_items,
+    })
+}
+
+fn convert_type(instance_type: InstanceType) -> Result<SchemaType> {
+    match instance_type {
+        InstanceType::String => Ok(SchemaType::String),
+        InstanceType::Integer => Ok(SchemaType::Integer),
+        InstanceType::Number => Ok(SchemaType::Number),
+        InstanceType::Boolean => Ok(SchemaType::Boolean),
+        InstanceType::Array => Ok(SchemaType::Array),
+        InstanceType::Object => Ok(SchemaType::Object),
+        InstanceType::Null => Err(Error::SchemaValidation(
+            "null type is not supported in response schemas".to_string(),
+        )),
+    }
+}
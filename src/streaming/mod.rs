@@ -1,45 +1,421 @@
 //! Streaming support for Gemini API responses
 
+#[cfg(feature = "functions")]
+use crate::functions::FunctionCall;
 use crate::{
+    audio::AudioChunk,
+    client::GeminiClient,
     error::{Error, Result},
-    models::GenerateContentResponse,
+    models::{
+        Candidate, CitationMetadata, Content, FinishReason, GenerateContentRequest,
+        GenerateContentResponse, Part, PromptFeedback, Role, SafetyRating, UsageMetadata,
+    },
 };
 use futures::{Stream, StreamExt as FuturesStreamExt};
 use reqwest::Response;
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::{broadcast, mpsc};
+use tracing::warn;
 
-/// Parse a streaming response into a stream of results
+/// Parse a server-sent events (`alt=sse`) streaming response into a stream of results
 pub fn parse_stream(response: Response) -> impl Stream<Item = Result<GenerateContentResponse>> {
-    let stream = response.bytes_stream();
+    use futures::TryStreamExt;
 
+    parse_sse_byte_stream(
+        response
+            .bytes_stream()
+            .map_err(|e| Error::Http(e.without_url())),
+    )
+}
+
+impl GeminiClient {
+    /// Stream content generation, resuming after mid-stream failures
+    ///
+    /// When the stream fails with a retryable or transport error, the request is sent again
+    /// with the text received so far appended as a model turn, up to `max_resumes` times; the
+    /// model then continues on a best-effort basis. Once resumes are exhausted (or the error is
+    /// not retryable) the stream ends with `Error::StreamInterrupted` carrying the partial text.
+    pub async fn stream_generate_content_resumable(
+        &self,
+        model: Option<&str>,
+        request: GenerateContentRequest,
+        max_resumes: u32,
+    ) -> Result<impl Stream<Item = Result<GenerateContentResponse>>> {
+        let stream = self.stream_generate_content(model, request.clone()).await?;
+        let state = ResumeState {
+            client: self.clone(),
+            model: model.map(str::to_string),
+            request,
+            stream: Box::pin(stream),
+            text: String::new(),
+            chunks: 0,
+            resumes_left: max_resumes,
+            done: false,
+        };
+
+        Ok(futures::stream::unfold(state, |mut state| async move {
+            if state.done {
+                return None;
+            }
+            loop {
+                let error = match FuturesStreamExt::next(&mut state.stream).await {
+                    Some(Ok(chunk)) => {
+                        if let Some(text) = chunk.text() {
+                            state.text.push_str(&text);
+                        }
+                        state.chunks += 1;
+                        return Some((Ok(chunk), state));
+                    }
+                    // The text is tracked here across resumes, so unwrap the per-stream error
+                    Some(Err(Error::StreamInterrupted { source, .. })) => *source,
+                    Some(Err(e)) => e,
+                    None => return None,
+                };
+
+                let resumable = error.is_retryable()
+                    || matches!(&error, Error::Streaming(_))
+                    || matches!(&error, Error::Shared(cause) if matches!(**cause, Error::Streaming(_)));
+                if resumable && state.resumes_left > 0 {
+                    state.resumes_left -= 1;
+                    warn!(
+                        "Stream failed after {} bytes of text, resuming: {}",
+                        state.text.len(),
+                        error
+                    );
+
+                    let mut request = state.request.clone();
+                    if !state.text.is_empty() {
+                        request.contents.push(Content::model(state.text.clone()));
+                    }
+                    match state
+                        .client
+                        .stream_generate_content(state.model.as_deref(), request)
+                        .await
+                    {
+                        Ok(stream) => {
+                            state.stream = Box::pin(stream);
+                            continue;
+                        }
+                        Err(e) => warn!("Failed to resume stream: {}", e),
+                    }
+                }
+
+                state.done = true;
+                let interrupted = Error::StreamInterrupted {
+                    partial_text: state.text.clone(),
+                    chunks_received: state.chunks,
+                    source: Box::new(error),
+                };
+                return Some((Err(interrupted), state));
+            }
+        }))
+    }
+}
+
+/// State of a resumable stream
+struct ResumeState<S> {
+    client: GeminiClient,
+    model: Option<String>,
+    request: GenerateContentRequest,
+    stream: Pin<Box<S>>,
+    text: String,
+    chunks: usize,
+    resumes_left: u32,
+    done: bool,
+}
+
+/// Wrap errors after the first chunk into `Error::StreamInterrupted`
+///
+/// The error carries the first candidate's text received so far and the number of chunks,
+/// so callers can keep what was generated before the failure. Errors before any chunk
+/// arrived are passed through unchanged.
+pub fn with_partial_on_error<S>(stream: S) -> impl Stream<Item = Result<GenerateContentResponse>>
+where
+    S: Stream<Item = Result<GenerateContentResponse>>,
+{
+    let mut text = String::new();
+    let mut chunks = 0;
+    FuturesStreamExt::map(stream, move |item| match item {
+        Ok(chunk) => {
+            if let Some(delta) = chunk.text() {
+                text.push_str(&delta);
+            }
+            chunks += 1;
+            Ok(chunk)
+        }
+        Err(error @ Error::StreamInterrupted { .. }) => Err(error),
+        Err(error) if chunks == 0 => Err(error),
+        Err(error) => Err(Error::StreamInterrupted {
+            partial_text: text.clone(),
+            chunks_received: chunks,
+            source: Box::new(error),
+        }),
+    })
+}
+
+/// Fail a stream with `Error::Timeout` when no item arrives within `timeout`
+///
+/// The stream ends after the timeout error. `None` disables the check.
+pub fn with_idle_timeout<S, T>(
+    stream: S,
+    timeout: Option<Duration>,
+) -> impl Stream<Item = Result<T>>
+where
+    S: Stream<Item = Result<T>>,
+{
+    futures::stream::unfold(
+        (Box::pin(stream), false),
+        move |(mut stream, timed_out)| async move {
+            if timed_out {
+                return None;
+            }
+            let Some(timeout) = timeout else {
+                let item = FuturesStreamExt::next(&mut stream).await?;
+                return Some((item, (stream, false)));
+            };
+            match tokio::time::timeout(timeout, FuturesStreamExt::next(&mut stream)).await {
+                Ok(item) => Some((item?, (stream, false))),
+                Err(_) => Some((Err(Error::Timeout(timeout)), (stream, true))),
+            }
+        },
+    )
+}
+
+/// Parse a stream of raw server-sent event bytes into a stream of results
+///
+/// Each event's data is decoded as one response; events without data are skipped. Errors
+/// from the byte stream are passed through, so transport failures keep their classification.
+pub fn parse_sse_byte_stream<S, B, E>(
+    stream: S,
+) -> impl Stream<Item = Result<GenerateContentResponse>>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Into<Error>,
+{
+    parse_sse_byte_stream_bounded(stream, usize::MAX)
+}
+
+/// Like [`parse_sse_byte_stream`], but fails once more than `max_buffered` bytes are held
+/// without completing an event
+///
+/// The byte stream is only polled when the consumer asks for the next response and no
+/// parsed events are pending, so a slow consumer stalls the HTTP body instead of growing a
+/// buffer.
+pub fn parse_sse_byte_stream_bounded<S, B, E>(
+    stream: S,
+    max_buffered: usize,
+) -> impl Stream<Item = Result<GenerateContentResponse>>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Into<Error>,
+{
     futures::stream::unfold(
-        (stream, Vec::new()),
-        |(mut stream, mut buffer)| async move {
+        (stream, SseParser::new(), VecDeque::new(), None, false),
+        move |(mut stream, mut parser, mut pending, mut overflow, mut ended)| async move {
             loop {
+                if let Some(event) = pending.pop_front() {
+                    let SseEvent { data, .. } = event;
+                    let result = serde_json::from_str(&data).map_err(Error::Json);
+                    return Some((result, (stream, parser, pending, overflow, ended)));
+                }
+                // Events completed before the limit was hit are still delivered
+                if let Some(error) = overflow.take() {
+                    return Some((Err(error), (stream, parser, pending, None, true)));
+                }
+                if ended {
+                    return None;
+                }
+
                 match FuturesStreamExt::next(&mut stream).await {
                     Some(Ok(chunk)) => {
-                        buffer.extend_from_slice(&chunk);
+                        pending.extend(parser.push(chunk.as_ref()));
+                        if parser.buffered_len() > max_buffered {
+                            overflow = Some(Error::Streaming(format!(
+                                "Stream buffered more than {} bytes without a complete event",
+                                max_buffered
+                            )));
+                            parser = SseParser::new();
+                        }
+                    }
+                    Some(Err(e)) => {
+                        return Some((Err(e.into()), (stream, parser, pending, overflow, ended)));
+                    }
+                    None => {
+                        pending.extend(parser.finish());
+                        ended = true;
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// A server-sent event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event type, if the server named one
+    pub event: Option<String>,
+    /// Data lines joined with `\n`
+    pub data: String,
+}
+
+/// Incremental server-sent events parser
+///
+/// Handles `\n`, `\r\n` and `\r` line endings split across chunks, comment lines, and
+/// multi-line data fields.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    line: Vec<u8>,
+    skip_lf: bool,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Create a parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed bytes, returning the events they complete
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for &byte in bytes {
+            if self.skip_lf {
+                self.skip_lf = false;
+                if byte == b'\n' {
+                    continue;
+                }
+            }
+            match byte {
+                b'\r' => {
+                    self.skip_lf = true;
+                    events.extend(self.end_line());
+                }
+                b'\n' => events.extend(self.end_line()),
+                _ => self.line.push(byte),
+            }
+        }
+        events
+    }
+
+    /// Bytes held for the event currently being parsed
+    pub fn buffered_len(&self) -> usize {
+        self.line.len() + self.data.iter().map(String::len).sum::<usize>()
+    }
+
+    /// Flush a final event that was not followed by a blank line
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        if !self.line.is_empty() {
+            self.end_line();
+        }
+        self.dispatch()
+    }
+
+    fn end_line(&mut self) -> Option<SseEvent> {
+        let line = std::mem::take(&mut self.line);
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line[0] == b':' {
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(&line);
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+        match field {
+            "data" => self.data.push(value.to_string()),
+            "event" => self.event = Some(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        Some(SseEvent { event, data })
+    }
+}
+
+/// Parse a stream of raw byte chunks into a stream of results
+///
+/// Accepts the JSON-array framing (`[{...},{...}]`) as well as bare concatenated objects.
+pub fn parse_byte_stream<S, B, E>(stream: S) -> impl Stream<Item = Result<GenerateContentResponse>>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Into<Error>,
+{
+    parse_byte_stream_bounded(stream, usize::MAX)
+}
 
-                        // Try to parse complete JSON objects from buffer
-                        if let Some((result, remaining)) = try_parse_json(&buffer) {
-                            buffer = remaining;
-                            return Some((result, (stream, buffer)));
+/// Like [`parse_byte_stream`], but fails once more than `max_buffered` bytes are held
+/// without completing an element
+///
+/// As with [`parse_sse_byte_stream_bounded`], the byte stream is only polled when no parsed
+/// elements are pending.
+pub fn parse_byte_stream_bounded<S, B, E>(
+    stream: S,
+    max_buffered: usize,
+) -> impl Stream<Item = Result<GenerateContentResponse>>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Into<Error>,
+{
+    futures::stream::unfold(
+        (
+            stream,
+            JsonArrayParser::new(),
+            VecDeque::<Result<Vec<u8>>>::new(),
+            false,
+        ),
+        move |(mut stream, mut parser, mut pending, mut ended)| async move {
+            loop {
+                if let Some(element) = pending.pop_front() {
+                    let result = element
+                        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(Error::Json));
+                    return Some((result, (stream, parser, pending, ended)));
+                }
+                if ended {
+                    return None;
+                }
+
+                match FuturesStreamExt::next(&mut stream).await {
+                    Some(Ok(chunk)) => {
+                        pending.extend(parser.push(chunk.as_ref()));
+                        // Elements completed before the limit was hit are still delivered
+                        if parser.buffered_len() > max_buffered {
+                            pending.push_back(Err(Error::Streaming(format!(
+                                "Stream buffered more than {} bytes without a complete element",
+                                max_buffered
+                            ))));
+                            ended = true;
                         }
                     }
                     Some(Err(e)) => {
-                        return Some((
-                            Err(Error::Streaming(format!("Stream error: {}", e))),
-                            (stream, buffer),
-                        ));
+                        return Some((Err(e.into()), (stream, parser, pending, ended)));
                     }
                     None => {
-                        // Stream ended, try to parse any remaining data
-                        if !buffer.is_empty() {
-                            if let Ok(response) = serde_json::from_slice(&buffer) {
-                                return Some((Ok(response), (stream, Vec::new())));
-                            }
+                        if let Err(e) = parser.finish() {
+                            pending.push_back(Err(e));
                         }
-                        return None;
+                        ended = true;
                     }
                 }
             }
@@ -47,112 +423,572 @@ pub fn parse_stream(response: Response) -> impl Stream<Item = Result<GenerateCon
     )
 }
 
-/// Try to parse a complete JSON object from the buffer
-fn try_parse_json(buffer: &[u8]) -> Option<(Result<GenerateContentResponse>, Vec<u8>)> {
-    // Look for complete JSON objects by counting braces
-    let mut brace_count = 0;
-    let mut in_string = false;
-    let mut escape_next = false;
-    let mut json_end = None;
+/// Incremental parser for a streamed JSON array of objects
+///
+/// Splits `[{...}, {...}]` into the raw bytes of each object regardless of where chunk
+/// boundaries fall, skipping the brackets, separating commas, and whitespace.
+#[derive(Debug, Default)]
+pub struct JsonArrayParser {
+    element: Vec<u8>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// Inside stray bytes between elements, already reported
+    malformed: bool,
+}
 
-    for (i, &byte) in buffer.iter().enumerate() {
-        if escape_next {
-            escape_next = false;
-            continue;
-        }
+impl JsonArrayParser {
+    /// Create a parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed bytes, returning the objects they complete
+    ///
+    /// A run of stray bytes between elements is reported as a single error, and parsing
+    /// picks up again at the next object or separator.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Result<Vec<u8>>> {
+        let mut elements = Vec::new();
+        for &byte in bytes {
+            if self.depth == 0 {
+                match byte {
+                    b'{' => {
+                        self.malformed = false;
+                        self.depth = 1;
+                        self.element.push(byte);
+                    }
+                    b'[' | b']' | b',' => self.malformed = false,
+                    byte if byte.is_ascii_whitespace() => {}
+                    _ if self.malformed => {}
+                    byte => {
+                        self.malformed = true;
+                        elements.push(Err(Error::Streaming(format!(
+                            "Unexpected byte {:?} between stream elements",
+                            byte as char
+                        ))));
+                    }
+                }
+                continue;
+            }
 
-        match byte {
-            b'"' if !in_string => in_string = true,
-            b'"' if in_string => in_string = false,
-            b'\\' if in_string => escape_next = true,
-            b'{' if !in_string => brace_count += 1,
-            b'}' if !in_string => {
-                brace_count -= 1;
-                if brace_count == 0 && i > 0 {
-                    json_end = Some(i + 1);
-                    break;
+            self.element.push(byte);
+            if self.in_string {
+                match byte {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
                 }
+                continue;
+            }
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        elements.push(Ok(std::mem::take(&mut self.element)));
+                    }
+                }
+                _ => {}
             }
-            _ => {}
         }
+        elements
     }
 
-    if let Some(end) = json_end {
-        let json_bytes = &buffer[..end];
-        let remaining = buffer[end..].to_vec();
+    /// Bytes held for the object currently being parsed
+    pub fn buffered_len(&self) -> usize {
+        self.element.len()
+    }
 
-        match serde_json::from_slice(json_bytes) {
-            Ok(response) => Some((Ok(response), remaining)),
-            Err(e) => Some((Err(Error::Json(e)), remaining)),
+    /// Check that the stream did not end inside an object
+    pub fn finish(&mut self) -> Result<()> {
+        if self.depth > 0 {
+            let len = self.element.len();
+            *self = Self::default();
+            return Err(Error::Streaming(format!(
+                "Stream ended inside an element ({} bytes buffered)",
+                len
+            )));
         }
-    } else {
-        None
+        Ok(())
     }
 }
 
 /// Stream processor that accumulates partial responses
+///
+/// Every part of every candidate is merged: consecutive text (and thought) fragments are
+/// joined, other parts such as inline images are kept in order, and each candidate's finish
+/// reason, safety ratings, and grounding metadata are carried into [`finalize`](Self::finalize)
+/// along with the latest usage metadata. Accessors without a candidate index refer to the
+/// first candidate. Candidates are keyed by the index the server reports, so a sparse or
+/// very large index does not allocate slots for the candidates in between.
+///
+/// With the `functions` feature, function calls are also collected as they complete,
+/// including calls whose arguments are streamed across several chunks.
+#[derive(Default)]
 pub struct StreamAccumulator {
-    accumulated_text: String,
-    current_response: Option<GenerateContentResponse>,
+    candidates: BTreeMap<usize, CandidateState>,
+    prompt_feedback: Option<PromptFeedback>,
+    usage_metadata: Option<UsageMetadata>,
+    received: bool,
+    timing: StreamTiming,
 }
 
-impl Default for StreamAccumulator {
+/// Chunk arrival times, relative to when the accumulator was created
+#[derive(Debug)]
+struct StreamTiming {
+    started: Instant,
+    first_token: Option<Duration>,
+    last_chunk: Option<Duration>,
+    chunks: usize,
+}
+
+impl Default for StreamTiming {
     fn default() -> Self {
-        Self::new()
+        Self {
+            started: Instant::now(),
+            first_token: None,
+            last_chunk: None,
+            chunks: 0,
+        }
     }
 }
 
+/// Throughput metrics for one stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamStats {
+    /// Number of chunks received
+    pub chunk_count: usize,
+    /// Time until the first chunk carrying text
+    pub time_to_first_token: Option<Duration>,
+    /// Time until the last chunk
+    pub elapsed: Duration,
+    /// Output tokens reported by the latest usage metadata
+    pub output_tokens: Option<i32>,
+    /// Output tokens per second between the first token and the last chunk
+    pub tokens_per_second: Option<f64>,
+}
+
+/// Accumulated state of one candidate
+#[derive(Default)]
+struct CandidateState {
+    role: Option<Role>,
+    parts: Vec<Part>,
+    text: String,
+    #[cfg(feature = "thinking")]
+    thoughts: String,
+    finish_reason: Option<FinishReason>,
+    safety_ratings: Option<Vec<SafetyRating>>,
+    citation_metadata: Option<CitationMetadata>,
+    #[cfg(feature = "grounding")]
+    grounding_metadata: Option<crate::grounding::GroundingMetadata>,
+    #[cfg(feature = "grounding")]
+    url_context_metadata: Option<crate::grounding::UrlContextMetadata>,
+    #[cfg(feature = "functions")]
+    function_calls: Vec<FunctionCall>,
+    #[cfg(feature = "functions")]
+    completed_calls: usize,
+    #[cfg(feature = "functions")]
+    pending_call: Option<PendingCall>,
+}
+
 impl StreamAccumulator {
     /// Create a new stream accumulator
     pub fn new() -> Self {
-        Self {
-            accumulated_text: String::new(),
-            current_response: None,
-        }
+        Self::default()
     }
 
-    /// Process a streaming response chunk
+    /// Process a streaming response chunk, returning the first candidate's new text
     pub fn process_chunk(&mut self, response: GenerateContentResponse) -> Option<String> {
-        // Extract text from the response
-        let text = response.candidates.first().and_then(|candidate| {
-            candidate.content.parts.first().and_then(|part| {
-                if let crate::models::Part::Text { text } = part {
-                    Some(text.clone())
-                } else {
-                    None
-                }
-            })
-        });
+        self.received = true;
+        let now = self.timing.started.elapsed();
+        self.timing.chunks += 1;
+        self.timing.last_chunk = Some(now);
+        if response.prompt_feedback.is_some() {
+            self.prompt_feedback = response.prompt_feedback;
+        }
+        if response.usage_metadata.is_some() {
+            self.usage_metadata = response.usage_metadata;
+        }
 
-        if let Some(ref text) = text {
-            self.accumulated_text.push_str(text);
+        let mut first_text = None;
+        let mut any_text = false;
+        for (position, candidate) in response.candidates.into_iter().enumerate() {
+            let index = candidate
+                .index
+                .and_then(|index| usize::try_from(index).ok())
+                .unwrap_or(position);
+            let text = self.candidates.entry(index).or_default().merge(candidate);
+            any_text |= text.is_some();
+            if index == 0 {
+                first_text = text;
+            }
+        }
+        if any_text && self.timing.first_token.is_none() {
+            self.timing.first_token = Some(now);
         }
+        first_text
+    }
+
+    /// Measure timings from `started` (e.g. when the request was sent) instead of from
+    /// when the accumulator was created
+    pub fn with_start(mut self, started: Instant) -> Self {
+        self.timing.started = started;
+        self
+    }
 
-        self.current_response = Some(response);
-        text
+    /// Throughput metrics for the chunks processed so far
+    pub fn stats(&self) -> StreamStats {
+        let output_tokens = self
+            .usage_metadata
+            .as_ref()
+            .map(|usage| usage.candidates_token_count);
+        let elapsed = self.timing.last_chunk.unwrap_or_default();
+        let generating = elapsed.saturating_sub(self.timing.first_token.unwrap_or_default());
+        let tokens_per_second = output_tokens
+            .filter(|_| !generating.is_zero())
+            .map(|tokens| f64::from(tokens) / generating.as_secs_f64());
+
+        StreamStats {
+            chunk_count: self.timing.chunks,
+            time_to_first_token: self.timing.first_token,
+            elapsed,
+            output_tokens,
+            tokens_per_second,
+        }
     }
 
     /// Get the complete accumulated text
     pub fn get_accumulated_text(&self) -> &str {
-        &self.accumulated_text
+        self.candidate_text(0).unwrap_or_default()
+    }
+
+    /// Get the accumulated text of a candidate
+    pub fn candidate_text(&self, index: usize) -> Option<&str> {
+        self.candidates.get(&index).map(|state| state.text.as_str())
+    }
+
+    /// Number of candidates seen so far
+    pub fn candidate_count(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Get the thought summaries accumulated so far
+    #[cfg(feature = "thinking")]
+    pub fn get_accumulated_thoughts(&self) -> &str {
+        self.candidates
+            .get(&0)
+            .map(|state| state.thoughts.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Get all function calls completed so far
+    #[cfg(feature = "functions")]
+    pub fn function_calls(&self) -> &[FunctionCall] {
+        self.candidates
+            .get(&0)
+            .map(|state| state.function_calls.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Take the function calls completed since the last call to this method
+    #[cfg(feature = "functions")]
+    pub fn take_completed_calls(&mut self) -> Vec<FunctionCall> {
+        let Some(state) = self.candidates.get_mut(&0) else {
+            return Vec::new();
+        };
+        let new_calls = state.function_calls[state.completed_calls..].to_vec();
+        state.completed_calls = state.function_calls.len();
+        new_calls
+    }
+
+    /// Get the final response with every candidate's merged content and metadata
+    pub fn finalize(self) -> Option<GenerateContentResponse> {
+        if !self.received {
+            return None;
+        }
+
+        Some(GenerateContentResponse {
+            candidates: self
+                .candidates
+                .into_iter()
+                .map(|(index, state)| state.finish(index))
+                .collect(),
+            prompt_feedback: self.prompt_feedback,
+            usage_metadata: self.usage_metadata,
+        })
+    }
+}
+
+impl CandidateState {
+    /// Merge a chunk of this candidate, returning its new text
+    fn merge(&mut self, candidate: Candidate) -> Option<String> {
+        self.role = Some(candidate.content.role);
+        if candidate.finish_reason.is_some() {
+            self.finish_reason = candidate.finish_reason;
+        }
+        if candidate.safety_ratings.is_some() {
+            self.safety_ratings = candidate.safety_ratings;
+        }
+        if candidate.citation_metadata.is_some() {
+            self.citation_metadata = candidate.citation_metadata;
+        }
+        #[cfg(feature = "grounding")]
+        if candidate.grounding_metadata.is_some() {
+            self.grounding_metadata = candidate.grounding_metadata;
+        }
+        #[cfg(feature = "grounding")]
+        if candidate.url_context_metadata.is_some() {
+            self.url_context_metadata = candidate.url_context_metadata;
+        }
+
+        let mut new_text: Option<String> = None;
+        for part in candidate.content.parts {
+            match part {
+                Part::Text {
+                    text,
+                    thought_signature,
+                } => {
+                    self.text.push_str(&text);
+                    new_text.get_or_insert_with(String::new).push_str(&text);
+                    match self.parts.last_mut() {
+                        Some(Part::Text {
+                            text: last,
+                            thought_signature: last_signature,
+                        }) => {
+                            last.push_str(&text);
+                            if thought_signature.is_some() {
+                                *last_signature = thought_signature;
+                            }
+                        }
+                        _ => self.parts.push(Part::Text {
+                            text,
+                            thought_signature,
+                        }),
+                    }
+                }
+                #[cfg(feature = "thinking")]
+                Part::Thought {
+                    text,
+                    thought,
+                    thought_signature,
+                } => {
+                    self.thoughts.push_str(&text);
+                    match self.parts.last_mut() {
+                        Some(Part::Thought {
+                            text: last,
+                            thought_signature: last_signature,
+                            ..
+                        }) => {
+                            last.push_str(&text);
+                            if thought_signature.is_some() {
+                                *last_signature = thought_signature;
+                            }
+                        }
+                        _ => self.parts.push(Part::Thought {
+                            text,
+                            thought,
+                            thought_signature,
+                        }),
+                    }
+                }
+                #[cfg(feature = "functions")]
+                Part::FunctionCall {
+                    function_call,
+                    thought_signature,
+                } => self.merge_function_call(&function_call, thought_signature),
+                other => self.parts.push(other),
+            }
+        }
+        new_text
     }
 
-    /// Get the final response with complete text
-    pub fn finalize(mut self) -> Option<GenerateContentResponse> {
-        if let Some(mut response) = self.current_response.take() {
-            // Update the response with the complete accumulated text
-            if let Some(candidate) = response.candidates.first_mut() {
-                if let Some(crate::models::Part::Text { text }) =
-                    candidate.content.parts.first_mut()
+    /// Build the final candidate
+    #[allow(unused_mut)]
+    fn finish(mut self, index: usize) -> Candidate {
+        #[cfg(feature = "functions")]
+        if let Some(pending) = self.pending_call.take() {
+            let (function_call, thought_signature) = pending.finish();
+            self.complete_call(function_call, thought_signature);
+        }
+
+        Candidate {
+            content: Content {
+                role: self.role.unwrap_or(Role::Model),
+                parts: self.parts,
+            },
+            finish_reason: self.finish_reason,
+            safety_ratings: self.safety_ratings,
+            citation_metadata: self.citation_metadata,
+            index: Some(index as i32),
+            #[cfg(feature = "grounding")]
+            grounding_metadata: self.grounding_metadata,
+            #[cfg(feature = "grounding")]
+            url_context_metadata: self.url_context_metadata,
+        }
+    }
+
+    /// Record a completed function call
+    #[cfg(feature = "functions")]
+    fn complete_call(&mut self, function_call: FunctionCall, thought_signature: Option<String>) {
+        self.function_calls.push(function_call.clone());
+        self.parts.push(Part::FunctionCall {
+            function_call,
+            thought_signature,
+        });
+    }
+
+    /// Merge a function call part into the pending call or the completed list
+    ///
+    /// The signature, which Gemini sends on the first fragment, stays with the call.
+    #[cfg(feature = "functions")]
+    fn merge_function_call(&mut self, call: &FunctionCall, thought_signature: Option<String>) {
+        let streamed = call.partial_args.is_some() || call.will_continue.is_some();
+        if !streamed && self.pending_call.is_none() {
+            self.complete_call(call.clone(), thought_signature);
+            return;
+        }
+
+        // A named chunk for a different function starts a new call
+        let starts_new = self
+            .pending_call
+            .as_ref()
+            .is_some_and(|pending| !call.name.is_empty() && call.name != pending.name);
+        if starts_new {
+            if let Some(pending) = self.pending_call.take() {
+                let (function_call, signature) = pending.finish();
+                self.complete_call(function_call, signature);
+            }
+        }
+
+        let pending = self.pending_call.get_or_insert_with(|| PendingCall {
+            name: call.name.clone(),
+            args: serde_json::Value::Object(Default::default()),
+            continuing_paths: Default::default(),
+            thought_signature: None,
+        });
+        pending.merge(call);
+        if thought_signature.is_some() {
+            pending.thought_signature = thought_signature;
+        }
+
+        if call.will_continue != Some(true) {
+            if let Some(pending) = self.pending_call.take() {
+                let (function_call, signature) = pending.finish();
+                self.complete_call(function_call, signature);
+            }
+        }
+    }
+}
+
+/// A function call whose arguments are still being streamed
+#[cfg(feature = "functions")]
+struct PendingCall {
+    name: String,
+    args: serde_json::Value,
+    /// Paths whose string value continues in the next fragment
+    continuing_paths: std::collections::HashSet<String>,
+    thought_signature: Option<String>,
+}
+
+#[cfg(feature = "functions")]
+impl PendingCall {
+    fn merge(&mut self, call: &FunctionCall) {
+        if self.name.is_empty() {
+            self.name = call.name.clone();
+        }
+
+        if let serde_json::Value::Object(args) = &mut self.args {
+            args.extend(call.args.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+
+        for partial in call.partial_args.iter().flatten() {
+            let Some(slot) = json_path_slot(&mut self.args, &partial.json_path) else {
+                continue;
+            };
+
+            match (slot, &partial.string_value) {
+                (serde_json::Value::String(existing), Some(piece))
+                    if self.continuing_paths.contains(&partial.json_path) =>
                 {
-                    *text = self.accumulated_text;
+                    existing.push_str(piece);
                 }
+                (slot, _) => *slot = partial.value(),
+            }
+
+            if partial.will_continue == Some(true) {
+                self.continuing_paths.insert(partial.json_path.clone());
+            } else {
+                self.continuing_paths.remove(&partial.json_path);
             }
-            Some(response)
-        } else {
-            None
         }
     }
+
+    fn finish(self) -> (FunctionCall, Option<String>) {
+        let args = match self.args {
+            serde_json::Value::Object(args) => args.into_iter().collect(),
+            _ => Default::default(),
+        };
+        let call = FunctionCall {
+            name: self.name,
+            args,
+            ..Default::default()
+        };
+        (call, self.thought_signature)
+    }
+}
+
+/// Largest array index a streamed argument path may create
+#[cfg(feature = "functions")]
+const MAX_PARTIAL_ARG_INDEX: usize = 4096;
+
+/// Resolve (creating as needed) the value at a JSON path like `$.a.b[0]`
+///
+/// Paths with an array index above [`MAX_PARTIAL_ARG_INDEX`] resolve to `None`, so a bad
+/// index cannot make the arguments grow without bound.
+#[cfg(feature = "functions")]
+fn json_path_slot<'a>(
+    root: &'a mut serde_json::Value,
+    path: &str,
+) -> Option<&'a mut serde_json::Value> {
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = root;
+
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        let (key, indices) = match segment.find('[') {
+            Some(bracket) => (&segment[..bracket], &segment[bracket..]),
+            None => (segment, ""),
+        };
+
+        if !key.is_empty() {
+            if !current.is_object() {
+                *current = serde_json::Value::Object(Default::default());
+            }
+            current = current
+                .as_object_mut()?
+                .entry(key)
+                .or_insert(serde_json::Value::Null);
+        }
+
+        for index in indices.split('[').filter(|index| !index.is_empty()) {
+            let index: usize = index.strip_suffix(']')?.parse().ok()?;
+            if index > MAX_PARTIAL_ARG_INDEX {
+                warn!("Ignoring streamed argument with array index {}", index);
+                return None;
+            }
+            if !current.is_array() {
+                *current = serde_json::Value::Array(Vec::new());
+            }
+            let items = current.as_array_mut()?;
+            if items.len() <= index {
+                items.resize(index + 1, serde_json::Value::Null);
+            }
+            current = &mut items[index];
+        }
+    }
+
+    Some(current)
 }
 
 /// Extension trait for working with streaming responses
@@ -166,8 +1002,8 @@ pub trait GeminiStreamExt: Stream {
         Box::pin(FuturesStreamExt::filter_map(self, |item| async move {
             match item.into() {
                 Ok(response) => response.candidates.first().and_then(|candidate| {
-                    candidate.content.parts.first().and_then(|part| {
-                        if let crate::models::Part::Text { text } = part {
+                    candidate.content.parts.iter().find_map(|part| {
+                        if let crate::models::Part::Text { text, .. } = part {
                             Some(Ok(text.clone()))
                         } else {
                             None
@@ -178,6 +1014,402 @@ pub trait GeminiStreamExt: Stream {
             }
         }))
     }
+
+    /// Drive the stream to completion and merge it into a single response
+    ///
+    /// The result has the same shape as [`GeminiClient::generate_content`] output. Fails on the
+    /// first stream error, or if the stream ends without any response.
+    fn collect_response(self) -> Pin<Box<dyn Future<Output = Result<GenerateContentResponse>>>>
+    where
+        Self: Sized + 'static,
+        Self::Item: Into<Result<GenerateContentResponse>>,
+    {
+        Box::pin(async move {
+            let stream = self;
+            futures::pin_mut!(stream);
+            let mut accumulator = StreamAccumulator::new();
+            while let Some(item) = FuturesStreamExt::next(&mut stream).await {
+                accumulator.process_chunk(item.into()?);
+            }
+            accumulator
+                .finalize()
+                .ok_or_else(|| Error::Streaming("Stream ended without any response".to_string()))
+        })
+    }
+
+    /// Split a multi-candidate stream into `count` independent per-candidate streams
+    ///
+    /// See [`CandidateStream`].
+    fn split_candidates(self, count: usize) -> Vec<CandidateStream<Self>>
+    where
+        Self: Sized + Stream<Item = Result<GenerateContentResponse>>,
+    {
+        let shared = Arc::new(Mutex::new(SplitState {
+            source: Box::pin(self),
+            queues: (0..count).map(|_| VecDeque::new()).collect(),
+            wakers: vec![None; count],
+            closed: vec![false; count],
+            done: false,
+        }));
+        (0..count)
+            .map(|index| CandidateStream {
+                index,
+                shared: shared.clone(),
+            })
+            .collect()
+    }
+
+    /// Read the streamed answer text as UTF-8 bytes
+    fn into_async_read(self) -> TextReader<Self>
+    where
+        Self: Sized + Stream<Item = Result<GenerateContentResponse>>,
+    {
+        TextReader::new(self)
+    }
+
+    /// Turn streamed responses into typed events for the first candidate
+    ///
+    /// Thought deltas arrive only when the request enables
+    /// [`include_thoughts`](crate::thinking::ThinkingConfig::include_thoughts). Tool calls are
+    /// emitted once complete, even when their arguments are streamed over several chunks.
+    fn events(self) -> Pin<Box<dyn Stream<Item = Result<StreamEvent>>>>
+    where
+        Self: Sized + 'static,
+        Self::Item: Into<Result<GenerateContentResponse>>,
+    {
+        #[cfg(feature = "functions")]
+        let mut calls = StreamAccumulator::new();
+
+        let events = FuturesStreamExt::map(self, move |item| {
+            let response = match item.into() {
+                Ok(response) => response,
+                Err(e) => return vec![Err(e)],
+            };
+
+            let mut events = Vec::new();
+            let candidate = response.candidates.first();
+            for part in candidate.iter().flat_map(|c| &c.content.parts) {
+                match part {
+                    #[cfg(feature = "thinking")]
+                    Part::Thought { text, .. } => {
+                        events.push(Ok(StreamEvent::ThoughtDelta(text.clone())))
+                    }
+                    Part::Text { text, .. } => {
+                        events.push(Ok(StreamEvent::TextDelta(text.clone())))
+                    }
+                    _ => {}
+                }
+            }
+
+            #[cfg(feature = "functions")]
+            {
+                calls.process_chunk(response.clone());
+                events.extend(
+                    calls
+                        .take_completed_calls()
+                        .into_iter()
+                        .map(|call| Ok(StreamEvent::ToolCall(call))),
+                );
+            }
+
+            if let Some(usage) = &response.usage_metadata {
+                events.push(Ok(StreamEvent::UsageUpdate(usage.clone())));
+            }
+            if let Some(reason) = candidate.and_then(|c| c.finish_reason) {
+                events.push(Ok(StreamEvent::Finished(reason)));
+            }
+            events
+        });
+
+        Box::pin(FuturesStreamExt::flat_map(events, futures::stream::iter))
+    }
+
+    /// Turn streamed responses into the audio chunks of the first candidate
+    ///
+    /// Text and other parts are skipped. Pass the chunks to [`crate::audio::write_wav`] to
+    /// save them.
+    fn audio(self) -> Pin<Box<dyn Stream<Item = Result<AudioChunk>>>>
+    where
+        Self: Sized + 'static,
+        Self::Item: Into<Result<GenerateContentResponse>>,
+    {
+        let chunks = FuturesStreamExt::map(self, |item| match item.into() {
+            Ok(response) => match response.audio_chunks() {
+                Ok(chunks) => chunks.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            },
+            Err(e) => vec![Err(e)],
+        });
+
+        Box::pin(FuturesStreamExt::flat_map(chunks, futures::stream::iter))
+    }
 }
 
 impl<T> GeminiStreamExt for T where T: Stream {}
+
+/// Forward each chunk's answer text into an mpsc channel
+///
+/// Returns early (successfully) if the receiver is dropped, or with the first stream error.
+pub async fn forward_text<S>(stream: S, tx: mpsc::Sender<String>) -> Result<()>
+where
+    S: Stream<Item = Result<GenerateContentResponse>>,
+{
+    futures::pin_mut!(stream);
+    while let Some(chunk) = FuturesStreamExt::next(&mut stream).await {
+        if let Some(text) = chunk?.text() {
+            if tx.send(text).await.is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Broadcast each chunk's answer text to every subscriber
+///
+/// Chunks sent while there are no subscribers are dropped.
+pub async fn broadcast_text<S>(stream: S, tx: &broadcast::Sender<String>) -> Result<()>
+where
+    S: Stream<Item = Result<GenerateContentResponse>>,
+{
+    futures::pin_mut!(stream);
+    while let Some(chunk) = FuturesStreamExt::next(&mut stream).await {
+        if let Some(text) = chunk?.text() {
+            let _ = tx.send(text);
+        }
+    }
+    Ok(())
+}
+
+/// One candidate's share of a stream requested with `candidate_count > 1`
+///
+/// Chunks are routed by `candidate.index`. Each chunk carries only this candidate, re-indexed
+/// to 0, so [`StreamAccumulator`] and [`collect_response`](GeminiStreamExt::collect_response)
+/// treat it as a single-candidate stream; usage metadata and prompt feedback are copied to
+/// every candidate. Chunks for candidates that are not being polled are buffered until they
+/// are; dropping a candidate's stream stops buffering for it. An error from the underlying
+/// stream is delivered to every candidate as [`Error::Shared`], wrapping the original error.
+pub struct CandidateStream<S> {
+    index: usize,
+    shared: Arc<Mutex<SplitState<S>>>,
+}
+
+struct SplitState<S> {
+    source: Pin<Box<S>>,
+    queues: Vec<VecDeque<Result<GenerateContentResponse>>>,
+    wakers: Vec<Option<Waker>>,
+    closed: Vec<bool>,
+    done: bool,
+}
+
+impl<S> CandidateStream<S> {
+    /// Index of the candidate this stream carries
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<S> SplitState<S> {
+    fn distribute(&mut self, response: GenerateContentResponse) {
+        if response.candidates.is_empty() {
+            for (index, queue) in self.queues.iter_mut().enumerate() {
+                if !self.closed[index] {
+                    queue.push_back(Ok(response.clone()));
+                }
+            }
+            return;
+        }
+
+        for (position, mut candidate) in response.candidates.into_iter().enumerate() {
+            let index = candidate
+                .index
+                .and_then(|index| usize::try_from(index).ok())
+                .unwrap_or(position);
+            if self.closed.get(index) != Some(&false) {
+                continue;
+            }
+            let queue = &mut self.queues[index];
+            candidate.index = Some(0);
+            queue.push_back(Ok(GenerateContentResponse {
+                candidates: vec![candidate],
+                prompt_feedback: response.prompt_feedback.clone(),
+                usage_metadata: response.usage_metadata.clone(),
+            }));
+        }
+    }
+
+    /// Wake the other candidates so they re-check their queues or poll the source themselves
+    fn wake_others(&mut self, index: usize) {
+        for (other, waker) in self.wakers.iter_mut().enumerate() {
+            if other != index {
+                if let Some(waker) = waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
+
+impl<S> Stream for CandidateStream<S>
+where
+    S: Stream<Item = Result<GenerateContentResponse>>,
+{
+    type Item = Result<GenerateContentResponse>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let index = self.index;
+        let mut state = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(item) = state.queues[index].pop_front() {
+                return Poll::Ready(Some(item));
+            }
+            if state.done {
+                return Poll::Ready(None);
+            }
+
+            match state.source.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(response))) => state.distribute(response),
+                Poll::Ready(Some(Err(e))) => {
+                    let siblings: Vec<usize> = (0..state.queues.len())
+                        .filter(|&other| other != index && !state.closed[other])
+                        .collect();
+                    if siblings.is_empty() {
+                        state.queues[index].push_back(Err(e));
+                    } else {
+                        let shared = Arc::new(e);
+                        for other in siblings {
+                            state.queues[other].push_back(Err(Error::Shared(shared.clone())));
+                        }
+                        state.queues[index].push_back(Err(Error::Shared(shared)));
+                    }
+                }
+                Poll::Ready(None) => state.done = true,
+                Poll::Pending => {
+                    state.wakers[index] = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+            state.wake_others(index);
+        }
+    }
+}
+
+impl<S> Drop for CandidateStream<S> {
+    fn drop(&mut self) {
+        // Another candidate may be waiting on a source wakeup that was routed to this one
+        let mut state = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        state.closed[self.index] = true;
+        state.queues[self.index].clear();
+        state.wake_others(self.index);
+    }
+}
+
+/// [`AsyncRead`] adapter yielding the streamed answer text as UTF-8 bytes
+///
+/// Stream errors are reported as `io::ErrorKind::Other` with the original error inside.
+pub struct TextReader<S> {
+    stream: Pin<Box<S>>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<S> TextReader<S> {
+    /// Wrap a response stream
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl<S> AsyncRead for TextReader<S>
+where
+    S: Stream<Item = Result<GenerateContentResponse>>,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        while this.position >= this.buffer.len() {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    this.buffer = chunk.text().unwrap_or_default().into_bytes();
+                    this.position = 0;
+                }
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(std::io::Error::other(e))),
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let available = &this.buffer[this.position..];
+        let len = available.len().min(buf.remaining());
+        buf.put_slice(&available[..len]);
+        this.position += len;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A typed piece of streamed output
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// New answer text
+    TextDelta(String),
+    /// New thought summary text
+    #[cfg(feature = "thinking")]
+    ThoughtDelta(String),
+    /// A complete function call
+    #[cfg(feature = "functions")]
+    ToolCall(FunctionCall),
+    /// Token usage reported so far
+    UsageUpdate(UsageMetadata),
+    /// Generation finished
+    Finished(FinishReason),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(json: serde_json::Value) -> GenerateContentResponse {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn accumulator_keys_candidates_by_reported_index() {
+        let mut accumulator = StreamAccumulator::new();
+        accumulator.process_chunk(chunk(serde_json::json!({ "candidates": [
+            { "content": { "role": "model", "parts": [{ "text": "a" }] }, "index": 0 },
+            { "content": { "role": "model", "parts": [{ "text": "b" }] }, "index": 2_000_000_000 }
+        ] })));
+        accumulator.process_chunk(chunk(serde_json::json!({ "candidates": [
+            { "content": { "role": "model", "parts": [{ "text": "c" }] }, "index": 2_000_000_000 }
+        ] })));
+
+        assert_eq!(accumulator.candidate_count(), 2);
+        assert_eq!(accumulator.candidate_text(2_000_000_000), Some("bc"));
+        let response = accumulator.finalize().unwrap();
+        let indices: Vec<_> = response.candidates.iter().map(|c| c.index).collect();
+        assert_eq!(indices, vec![Some(0), Some(2_000_000_000)]);
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn json_path_slot_creates_nested_values_and_caps_indices() {
+        let mut args = serde_json::json!({});
+        *json_path_slot(&mut args, "$.stops[1].city").unwrap() = "Oslo".into();
+        assert_eq!(
+            args,
+            serde_json::json!({ "stops": [null, { "city": "Oslo" }] })
+        );
+
+        assert!(json_path_slot(&mut args, "$.stops[18446744073709551615]").is_none());
+        assert!(json_path_slot(&mut args, "$.stops[4097]").is_none());
+        assert!(json_path_slot(&mut args, "$.stops[x]").is_none());
+        assert_eq!(args["stops"].as_array().unwrap().len(), 2);
+    }
+}
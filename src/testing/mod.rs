@@ -0,0 +1,190 @@
+//! Fault injection for resilience testing
+//!
+//! A [`FaultInjector`] installed on a [`GeminiClient`](crate::GeminiClient) adds simulated
+//! latency, synthetic API errors, and corrupted stream chunks in front of the real transport,
+//! so applications can exercise their retry and fallback handling without depending on the
+//! API actually misbehaving.
+
+use crate::error::{ApiErrorCode, Error};
+use rand::Rng;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::debug;
+
+/// Distribution used to sample simulated request latency
+#[derive(Debug, Clone, Copy, Default)]
+pub enum LatencyDistribution {
+    /// No added latency
+    #[default]
+    None,
+    /// Always add the same delay
+    Fixed(Duration),
+    /// Delay sampled uniformly between `min` and `max`
+    Uniform {
+        /// Lower bound of the delay
+        min: Duration,
+        /// Upper bound of the delay
+        max: Duration,
+    },
+    /// Delay sampled from an exponential distribution (long tail)
+    Exponential {
+        /// Mean delay
+        mean: Duration,
+    },
+}
+
+impl LatencyDistribution {
+    /// Sample a delay from the distribution
+    pub fn sample(&self) -> Duration {
+        let mut rng = rand::thread_rng();
+        match *self {
+            LatencyDistribution::None => Duration::ZERO,
+            LatencyDistribution::Fixed(delay) => delay,
+            LatencyDistribution::Uniform { min, max } => {
+                if max <= min {
+                    min
+                } else {
+                    rng.gen_range(min..=max)
+                }
+            }
+            LatencyDistribution::Exponential { mean } => {
+                let u: f64 = rng.gen_range(0.0..1.0);
+                Duration::from_secs_f64(-mean.as_secs_f64() * (1.0 - u).ln())
+            }
+        }
+    }
+}
+
+/// A failure mode that can be injected in place of a real response
+#[derive(Debug, Clone, Copy)]
+pub enum InjectedFault {
+    /// Quota exhaustion (HTTP 429)
+    RateLimit {
+        /// Retry delay reported with the error
+        retry_after: Option<Duration>,
+    },
+    /// An API error with the given HTTP status
+    Status(u16),
+    /// The request timing out
+    Timeout(Duration),
+}
+
+impl InjectedFault {
+    fn into_error(self) -> Error {
+        match self {
+            InjectedFault::RateLimit { retry_after } => Error::RateLimit {
+                retry_after,
+                quota_violations: Vec::new(),
+            },
+            InjectedFault::Status(status) => Error::Api {
+                status,
+                code: ApiErrorCode::from_http_status(status),
+                message: format!("Injected fault (status {})", status),
+                details: None,
+                response: Box::default(),
+            },
+            InjectedFault::Timeout(duration) => Error::Timeout(duration),
+        }
+    }
+}
+
+/// Configurable fault injection for requests and streams
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    latency: LatencyDistribution,
+    error_rate: f64,
+    faults: Vec<InjectedFault>,
+    malformed_chunk_rate: f64,
+    stream_error_rate: f64,
+}
+
+impl Default for FaultInjector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FaultInjector {
+    /// Create an injector that does nothing until configured
+    pub fn new() -> Self {
+        Self {
+            latency: LatencyDistribution::None,
+            error_rate: 0.0,
+            faults: vec![
+                InjectedFault::Status(503),
+                InjectedFault::RateLimit { retry_after: None },
+            ],
+            malformed_chunk_rate: 0.0,
+            stream_error_rate: 0.0,
+        }
+    }
+
+    /// Add latency sampled from the given distribution before every request
+    pub fn latency(mut self, latency: LatencyDistribution) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Fail the given fraction of requests (0.0-1.0)
+    pub fn error_rate(mut self, rate: f64) -> Self {
+        self.error_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the faults to choose from when a request is failed
+    pub fn faults(mut self, faults: Vec<InjectedFault>) -> Self {
+        self.faults = faults;
+        self
+    }
+
+    /// Corrupt the given fraction of stream chunks (0.0-1.0)
+    pub fn malformed_chunk_rate(mut self, rate: f64) -> Self {
+        self.malformed_chunk_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Abort the stream with an error on the given fraction of chunks (0.0-1.0)
+    pub fn stream_error_rate(mut self, rate: f64) -> Self {
+        self.stream_error_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Apply simulated latency and possibly return an injected error
+    pub(crate) async fn before_request(&self) -> Option<Error> {
+        let delay = self.latency.sample();
+        if !delay.is_zero() {
+            debug!("Injecting {:?} of latency", delay);
+            sleep(delay).await;
+        }
+
+        if self.faults.is_empty() || !rand::thread_rng().gen_bool(self.error_rate) {
+            return None;
+        }
+
+        let index = rand::thread_rng().gen_range(0..self.faults.len());
+        let fault = self.faults[index];
+        debug!("Injecting fault: {:?}", fault);
+        Some(fault.into_error())
+    }
+
+    /// Possibly corrupt or fail a chunk of a streaming response
+    #[cfg(feature = "streaming")]
+    pub(crate) fn apply_to_chunk(&self, chunk: &[u8]) -> crate::error::Result<Vec<u8>> {
+        let mut rng = rand::thread_rng();
+
+        if rng.gen_bool(self.stream_error_rate) {
+            debug!("Injecting stream error");
+            return Err(Error::Streaming("Injected stream failure".to_string()));
+        }
+
+        if !chunk.is_empty() && rng.gen_bool(self.malformed_chunk_rate) {
+            debug!("Injecting malformed chunk");
+            let cut = rng.gen_range(0..chunk.len());
+            let mut corrupted = chunk[..cut].to_vec();
+            corrupted.extend_from_slice(b"\x00}{\"malformed");
+            return Ok(corrupted);
+        }
+
+        Ok(chunk.to_vec())
+    }
+}
@@ -1,23 +1,99 @@
-//! Thinking mode configuration for Gemini 2.5 models
+//! Thinking mode configuration for Gemini 2.5 and Gemini 3 models
+//!
+//! [`ThinkingConfig::thinking_budget`] used to be a bare [`ThinkingBudget`]. Code building the
+//! struct directly should wrap existing values in `Some(..)` and read the field with
+//! `if let Some(budget)`; it is `None`, and omitted from the request, when `thinking_level`
+//! is used instead.
 
-use serde::{Deserialize, Serialize};
+use crate::{
+    error::{Error, Result},
+    models::{
+        model_generation, FinishReason, GenerateContentRequest, GenerateContentResponse,
+        UsageMetadata,
+    },
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
 
 /// Configuration for thinking mode
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The default sets nothing, leaving thinking to the model's own default; use
+/// [`ThinkingConfig::auto`] to request a dynamic budget explicitly.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ThinkingConfig {
     /// Number of thinking tokens the model can use (0-24576)
-    pub thinking_budget: ThinkingBudget,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<ThinkingBudget>,
+
+    /// Relative amount of thinking (Gemini 3 models), instead of a token budget
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_level: Option<ThinkingLevel>,
+
+    /// Return thought summaries alongside the answer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_thoughts: Option<bool>,
+}
+
+/// Relative thinking effort for Gemini 3 models
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ThinkingLevel {
+    /// Minimal reasoning, lowest latency
+    Low,
+    /// Balanced reasoning
+    Medium,
+    /// Deep reasoning
+    High,
 }
 
 /// Thinking budget specification
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-#[serde(untagged)]
+///
+/// Serialized as the integer the API expects: `-1` for dynamic thinking, `0` to disable
+/// thinking, or the token count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThinkingBudget {
     /// Exact number of tokens
     Tokens(u32),
-    /// Let the model decide based on complexity
+    /// Let the model decide based on complexity (same as `Dynamic`)
     Auto,
+    /// Dynamic thinking: the model adjusts the budget to the request
+    Dynamic,
+    /// No thinking
+    Disabled,
+}
+
+impl ThinkingBudget {
+    /// The value sent to the API
+    pub fn as_i32(self) -> i32 {
+        match self {
+            ThinkingBudget::Tokens(tokens) => tokens.min(i32::MAX as u32) as i32,
+            ThinkingBudget::Auto | ThinkingBudget::Dynamic => -1,
+            ThinkingBudget::Disabled => 0,
+        }
+    }
+}
+
+impl Serialize for ThinkingBudget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_i32(self.as_i32())
+    }
+}
+
+impl<'de> Deserialize<'de> for ThinkingBudget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        match i64::deserialize(deserializer)? {
+            -1 => Ok(ThinkingBudget::Dynamic),
+            0 => Ok(ThinkingBudget::Disabled),
+            tokens if tokens > 0 && tokens <= u32::MAX as i64 => {
+                Ok(ThinkingBudget::Tokens(tokens as u32))
+            }
+            other => Err(serde::de::Error::custom(format!(
+                "invalid thinking budget {}",
+                other
+            ))),
+        }
+    }
 }
 
 impl ThinkingConfig {
@@ -28,32 +104,120 @@ impl ThinkingConfig {
             "Thinking budget cannot exceed 24576 tokens"
         );
         Self {
-            thinking_budget: ThinkingBudget::Tokens(tokens),
+            thinking_budget: Some(ThinkingBudget::Tokens(tokens)),
+            thinking_level: None,
+            include_thoughts: None,
         }
     }
 
     /// Create a configuration that lets the model decide thinking budget
     pub fn auto() -> Self {
+        Self::dynamic()
+    }
+
+    /// Create a dynamic thinking configuration (budget `-1`)
+    pub fn dynamic() -> Self {
         Self {
-            thinking_budget: ThinkingBudget::Auto,
+            thinking_budget: Some(ThinkingBudget::Dynamic),
+            thinking_level: None,
+            include_thoughts: None,
         }
     }
 
     /// Disable thinking mode
     pub fn disabled() -> Self {
         Self {
-            thinking_budget: ThinkingBudget::Tokens(0),
+            thinking_budget: Some(ThinkingBudget::Disabled),
+            thinking_level: None,
+            include_thoughts: None,
         }
     }
+
+    /// Create a configuration with a thinking level (Gemini 3 models)
+    pub fn with_level(level: ThinkingLevel) -> Self {
+        Self {
+            thinking_budget: None,
+            thinking_level: Some(level),
+            include_thoughts: None,
+        }
+    }
+
+    /// Default configuration for a model: a high thinking level on Gemini 3 and later,
+    /// dynamic budget otherwise
+    pub fn for_model(model: &str) -> Self {
+        if model_generation(model).is_some_and(|major| major >= 3) {
+            Self::with_level(ThinkingLevel::High)
+        } else {
+            Self::dynamic()
+        }
+    }
+
+    /// Request thought summaries in responses
+    pub fn with_thoughts(mut self) -> Self {
+        self.include_thoughts = Some(true);
+        self
+    }
+
+    /// Check that at most one of budget and level is set
+    pub fn validate(&self) -> Result<()> {
+        if self.thinking_budget.is_some() && self.thinking_level.is_some() {
+            return Err(Error::Config(
+                "thinking_budget and thinking_level cannot both be set".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the configuration against a model; thinking levels require Gemini 3 or later
+    ///
+    /// Models whose generation cannot be read from the name, such as `gemini-flash-latest`
+    /// or tuned models, are assumed to support levels.
+    pub fn validate_for_model(&self, model: &str) -> Result<()> {
+        self.validate()?;
+        let supports_level = model_generation(model).is_none_or(|major| major >= 3);
+        if self.thinking_level.is_some() && !supports_level {
+            return Err(Error::Config(format!(
+                "{} does not support thinking_level; use thinking_budget instead",
+                model
+            )));
+        }
+        Ok(())
+    }
 }
 
-impl Default for ThinkingConfig {
-    fn default() -> Self {
-        Self::auto()
+impl GenerateContentResponse {
+    /// Whether thinking consumed the whole output token limit, leaving no answer
+    pub fn thinking_exhausted_output(&self) -> bool {
+        let hit_limit = self
+            .candidates
+            .first()
+            .is_some_and(|candidate| candidate.finish_reason == Some(FinishReason::MaxTokens));
+        let thought = self
+            .usage_metadata
+            .as_ref()
+            .and_then(|usage| usage.thoughts_token_count)
+            .is_some_and(|tokens| tokens > 0);
+        let answered = self.text().is_some_and(|text| !text.trim().is_empty());
+
+        hit_limit && thought && !answered
     }
 }
 
-/// Extension trait for GenerationConfig to easily set thinking mode
+impl GenerateContentRequest {
+    /// Check the thinking configuration, if any, against a model
+    pub fn validate_thinking(&self, model: &str) -> Result<()> {
+        match self
+            .generation_config
+            .as_ref()
+            .and_then(|config| config.thinking_config.as_ref())
+        {
+            Some(thinking) => thinking.validate_for_model(model),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Extension trait for GenerationConfig and GenerateContentRequest to easily set thinking mode
 pub trait ThinkingExt {
     /// Apply thinking configuration to generation config
     fn with_thinking(self, config: ThinkingConfig) -> Self;
@@ -61,6 +225,8 @@ pub trait ThinkingExt {
     fn with_thinking_budget(self, tokens: u32) -> Self;
     /// Enable auto thinking mode
     fn with_auto_thinking(self) -> Self;
+    /// Set a thinking level (Gemini 3 models)
+    fn with_thinking_level(self, level: ThinkingLevel) -> Self;
     /// Disable thinking mode
     fn without_thinking(self) -> Self;
 }
@@ -82,6 +248,49 @@ impl ThinkingExt for crate::models::GenerationConfig {
         self.with_thinking(ThinkingConfig::auto())
     }
 
+    /// Set a thinking level (Gemini 3 models)
+    fn with_thinking_level(self, level: ThinkingLevel) -> Self {
+        self.with_thinking(ThinkingConfig::with_level(level))
+    }
+
+    /// Disable thinking mode
+    fn without_thinking(self) -> Self {
+        self.with_thinking(ThinkingConfig::disabled())
+    }
+}
+
+impl ThinkingExt for GenerateContentRequest {
+    /// Apply thinking configuration, creating the generation config if needed
+    ///
+    /// An `include_thoughts` setting already on the request is kept unless the new
+    /// configuration sets it.
+    fn with_thinking(mut self, mut config: ThinkingConfig) -> Self {
+        let generation_config = self.generation_config.get_or_insert_with(Default::default);
+        if config.include_thoughts.is_none() {
+            config.include_thoughts = generation_config
+                .thinking_config
+                .as_ref()
+                .and_then(|existing| existing.include_thoughts);
+        }
+        generation_config.thinking_config = Some(config);
+        self
+    }
+
+    /// Set a specific thinking budget in tokens
+    fn with_thinking_budget(self, tokens: u32) -> Self {
+        self.with_thinking(ThinkingConfig::with_budget(tokens))
+    }
+
+    /// Enable auto thinking mode
+    fn with_auto_thinking(self) -> Self {
+        self.with_thinking(ThinkingConfig::auto())
+    }
+
+    /// Set a thinking level (Gemini 3 models)
+    fn with_thinking_level(self, level: ThinkingLevel) -> Self {
+        self.with_thinking(ThinkingConfig::with_level(level))
+    }
+
     /// Disable thinking mode
     fn without_thinking(self) -> Self {
         self.with_thinking(ThinkingConfig::disabled())
@@ -89,9 +298,44 @@ impl ThinkingExt for crate::models::GenerationConfig {
 }
 
 /// Helper to determine appropriate thinking budget based on task complexity
-pub struct ThinkingBudgetCalculator;
+///
+/// [`estimate`](Self::estimate) uses fixed heuristics. A calculator instance can also
+/// [`record`](Self::record) observed thinking usage and then
+/// [`estimate_calibrated`](Self::estimate_calibrated) from it.
+#[derive(Debug, Clone, Default)]
+pub struct ThinkingBudgetCalculator {
+    /// Observed thinking tokens per complexity: (total, samples)
+    observed: HashMap<TaskComplexity, (u64, u64)>,
+}
 
 impl ThinkingBudgetCalculator {
+    /// Create a calculator with no observations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the thinking tokens a response used for a task of the given complexity
+    pub fn record(&mut self, task_type: TaskComplexity, usage: &UsageMetadata) {
+        if let Some(tokens) = usage.thoughts_token_count {
+            let entry = self.observed.entry(task_type).or_default();
+            entry.0 += tokens.max(0) as u64;
+            entry.1 += 1;
+        }
+    }
+
+    /// Estimate a budget, preferring observed usage for this complexity when available
+    ///
+    /// Calibrated budgets are the observed average plus 25% headroom.
+    pub fn estimate_calibrated(&self, prompt: &str, task_type: TaskComplexity) -> u32 {
+        match self.observed.get(&task_type) {
+            Some(&(total, samples)) if samples > 0 => {
+                let average = total / samples;
+                ((average + average / 4) as u32).min(24576)
+            }
+            _ => Self::estimate(prompt, task_type),
+        }
+    }
+
     /// Estimate thinking budget based on prompt characteristics
     pub fn estimate(prompt: &str, task_type: TaskComplexity) -> u32 {
         let base_budget = match task_type {
@@ -121,8 +365,10 @@ impl ThinkingBudgetCalculator {
     }
 }
 
-/// Task complexity levels for thinking budget estimation
-#[derive(Debug, Clone, Copy)]
+/// Task complexity levels for thinking budget estimation and model routing
+///
+/// Levels are ordered from simplest to most complex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TaskComplexity {
     /// Simple queries, fact retrieval
     Simple,
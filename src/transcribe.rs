@@ -0,0 +1,455 @@
+//! Audio transcription
+//!
+//! [`GeminiClient::transcribe`] uploads an audio file, asks for a timestamped transcript with
+//! `audioTimestamp` enabled and parses it into a [`Transcript`]. 16-bit PCM WAV files longer
+//! than [`TranscribeOptions::max_segment`] are cut into overlapping pieces first, and the
+//! timestamps of later pieces are shifted by their offset so the transcript reads as one
+//! recording. Other formats are sent whole.
+
+use crate::{
+    audio::{read_wav, wav_duration, write_wav, AudioChunk},
+    client::GeminiClient,
+    error::{Error, Result},
+    models::{Content, GenerateContentRequest, GenerationConfig, Part, Role},
+};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Default longest piece sent in one request
+const DEFAULT_MAX_SEGMENT: Duration = Duration::from_secs(10 * 60);
+
+/// Default audio shared by consecutive pieces
+const DEFAULT_OVERLAP: Duration = Duration::from_secs(5);
+
+/// How to transcribe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscribeOptions {
+    max_segment: Duration,
+    overlap: Duration,
+    speakers: bool,
+    language: Option<String>,
+    delete_uploads: bool,
+}
+
+impl Default for TranscribeOptions {
+    fn default() -> Self {
+        Self {
+            max_segment: DEFAULT_MAX_SEGMENT,
+            overlap: DEFAULT_OVERLAP,
+            speakers: false,
+            language: None,
+            delete_uploads: true,
+        }
+    }
+}
+
+impl TranscribeOptions {
+    /// Ten-minute pieces overlapping by five seconds, no speaker labels, any language
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Longest piece of audio sent in one request (default ten minutes)
+    ///
+    /// Only 16-bit PCM WAV files can be split. Other WAV encodings longer than this are
+    /// rejected; compressed formats are sent whole, whatever their length, so keep them
+    /// within what the model accepts in one request.
+    pub fn max_segment(mut self, max: Duration) -> Self {
+        self.max_segment = max.max(Duration::from_secs(1));
+        self
+    }
+
+    /// Audio shared by consecutive pieces, so words at a cut are heard whole (default five
+    /// seconds, at most half a piece)
+    ///
+    /// Segments from the shared stretch are taken from the earlier piece up to its middle
+    /// and from the later piece after it.
+    pub fn overlap(mut self, overlap: Duration) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Label each segment with its speaker
+    pub fn speakers(mut self, speakers: bool) -> Self {
+        self.speakers = speakers;
+        self
+    }
+
+    /// Transcribe in this language, e.g. `"German"`
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Delete uploaded audio once it has been transcribed (default true)
+    pub fn delete_uploads(mut self, delete: bool) -> Self {
+        self.delete_uploads = delete;
+        self
+    }
+
+    fn prompt(&self) -> String {
+        let mut prompt = "Transcribe this audio verbatim. Split the transcript into segments \
+                          of a sentence or two, giving each segment's start and end time as \
+                          MM:SS from the beginning of the audio."
+            .to_string();
+        if self.speakers {
+            prompt.push_str(" Label each segment with its speaker, e.g. \"Speaker 1\".");
+        }
+        if let Some(language) = &self.language {
+            prompt.push_str(&format!(" Write the transcript in {}.", language));
+        }
+        prompt
+    }
+}
+
+/// A stretch of speech with its position in the recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptSegment {
+    /// Offset of the start from the beginning of the recording
+    pub start: Duration,
+    /// Offset of the end from the beginning of the recording
+    pub end: Duration,
+    /// Speaker label, when speakers were requested
+    pub speaker: Option<String>,
+    /// What was said
+    pub text: String,
+}
+
+/// A timestamped transcript of a whole recording
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript {
+    /// Segments in order
+    pub segments: Vec<TranscriptSegment>,
+}
+
+impl Transcript {
+    /// The transcript text without timestamps, one segment per line
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match &segment.speaker {
+                Some(speaker) => format!("{}: {}", speaker, segment.text),
+                None => segment.text.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// A segment as the model writes it
+#[derive(Debug, Deserialize)]
+struct RawSegment {
+    start: String,
+    end: String,
+    #[serde(default)]
+    speaker: Option<String>,
+    text: String,
+}
+
+impl GeminiClient {
+    /// Transcribe an audio file
+    ///
+    /// The MIME type comes from the file extension. Pieces of a split WAV file are
+    /// transcribed one after another. Fails with `Error::Config` for a WAV file longer than
+    /// [`TranscribeOptions::max_segment`] that is not 16-bit PCM.
+    pub async fn transcribe(
+        &self,
+        model: Option<&str>,
+        path: impl AsRef<Path>,
+        options: &TranscribeOptions,
+    ) -> Result<Transcript> {
+        let path = path.as_ref();
+        let mime_type = audio_mime_type(path)?;
+        let bytes = tokio::fs::read(path).await?;
+
+        let pieces: Vec<(Duration, Vec<u8>)> = if mime_type == "audio/wav" {
+            match read_wav(&bytes) {
+                Ok(audio) => {
+                    let mut pieces = Vec::new();
+                    for (offset, chunk) in overlapping_pieces(&audio, options) {
+                        let mut wav = Vec::new();
+                        write_wav(&mut wav, std::slice::from_ref(&chunk))?;
+                        pieces.push((offset, wav));
+                    }
+                    pieces
+                }
+                Err(e) => {
+                    let duration = wav_duration(&bytes)?;
+                    if duration > options.max_segment {
+                        return Err(Error::Config(format!(
+                            "{} is {:?} long, over the {:?} segment limit, and cannot be \
+                             split: {}",
+                            path.display(),
+                            duration,
+                            options.max_segment,
+                            e
+                        )));
+                    }
+                    vec![(Duration::ZERO, bytes)]
+                }
+            }
+        } else {
+            vec![(Duration::ZERO, bytes)]
+        };
+        debug!("Transcribing {} in {} pieces", path.display(), pieces.len());
+
+        let mut transcribed = Vec::with_capacity(pieces.len());
+        for (offset, data) in pieces {
+            let segments = self
+                .transcribe_piece(model, data, mime_type, options)
+                .await?;
+            transcribed.push((offset, segments));
+        }
+        merge_pieces(transcribed, overlap(options))
+    }
+
+    async fn transcribe_piece(
+        &self,
+        model: Option<&str>,
+        data: Vec<u8>,
+        mime_type: &str,
+        options: &TranscribeOptions,
+    ) -> Result<Vec<RawSegment>> {
+        let file = self.upload_file(data, mime_type, None).await?;
+        let file_name = file.name.clone();
+
+        let result = async {
+            let file = self.wait_until_active(file).await?;
+            let uri = file.uri.ok_or_else(|| {
+                Error::InvalidResponse(format!("Uploaded file {} has no URI", file.name))
+            })?;
+            let request = GenerateContentRequest {
+                generation_config: Some(GenerationConfig {
+                    audio_timestamp: Some(true),
+                    response_mime_type: Some("application/json".to_string()),
+                    response_json_schema: Some(segments_schema(options.speakers)),
+                    ..Default::default()
+                }),
+                ..GenerateContentRequest::new(Content {
+                    role: Role::User,
+                    parts: vec![Part::file(mime_type, uri), Part::text(options.prompt())],
+                })
+            };
+            self.generate_json(model, request).await
+        }
+        .await;
+
+        if options.delete_uploads {
+            if let Err(e) = self.delete_file(&file_name).await {
+                warn!("Failed to delete uploaded file {}: {}", file_name, e);
+            }
+        }
+        result
+    }
+}
+
+/// Overlap between pieces, capped at half a piece
+fn overlap(options: &TranscribeOptions) -> Duration {
+    options.overlap.min(options.max_segment / 2)
+}
+
+/// Cut audio into pieces of at most `max_segment`, each starting `overlap` before the end of
+/// the previous one, with the offset of each piece
+fn overlapping_pieces(
+    audio: &AudioChunk,
+    options: &TranscribeOptions,
+) -> Vec<(Duration, AudioChunk)> {
+    let frame_len = 2 * audio.channels.max(1) as usize;
+    let rate = audio.sample_rate.max(1) as f64;
+    let frames = audio.data.len() / frame_len;
+    let piece = ((options.max_segment.as_secs_f64() * rate) as usize).max(1);
+    let shared = ((overlap(options).as_secs_f64() * rate) as usize).min(piece - 1);
+
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + piece).min(frames);
+        pieces.push((
+            Duration::from_secs_f64(start as f64 / rate),
+            AudioChunk {
+                data: audio.data[start * frame_len..end * frame_len].to_vec(),
+                sample_rate: audio.sample_rate,
+                channels: audio.channels,
+            },
+        ));
+        if end == frames {
+            return pieces;
+        }
+        start = end - shared;
+    }
+}
+
+/// Join the segments of each piece into one transcript
+///
+/// Segments starting in a stretch shared by two pieces come from the earlier piece up to the
+/// middle of the stretch and from the later piece after it.
+fn merge_pieces(pieces: Vec<(Duration, Vec<RawSegment>)>, overlap: Duration) -> Result<Transcript> {
+    let seams: Vec<Duration> = pieces
+        .iter()
+        .skip(1)
+        .map(|(offset, _)| *offset + overlap / 2)
+        .chain([Duration::MAX])
+        .collect();
+    let mut transcript = Transcript::default();
+    let mut from = Duration::ZERO;
+    for ((offset, segments), until) in pieces.into_iter().zip(seams) {
+        for segment in segments {
+            let start = offset + parse_timestamp(&segment.start)?;
+            if start < from || start >= until {
+                continue;
+            }
+            transcript.segments.push(TranscriptSegment {
+                start,
+                end: offset + parse_timestamp(&segment.end)?,
+                speaker: segment.speaker.filter(|speaker| !speaker.is_empty()),
+                text: segment.text.trim().to_string(),
+            });
+        }
+        from = until;
+    }
+    Ok(transcript)
+}
+
+/// JSON Schema for the list of segments the model returns
+fn segments_schema(speakers: bool) -> serde_json::Value {
+    let mut required = vec!["start", "end", "text"];
+    if speakers {
+        required.push("speaker");
+    }
+    serde_json::json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "start": { "type": "string" },
+                "end": { "type": "string" },
+                "speaker": { "type": "string" },
+                "text": { "type": "string" }
+            },
+            "required": required
+        }
+    })
+}
+
+fn audio_mime_type(path: &Path) -> Result<&'static str> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "wav" => Ok("audio/wav"),
+        "mp3" => Ok("audio/mp3"),
+        "aiff" | "aif" => Ok("audio/aiff"),
+        "aac" => Ok("audio/aac"),
+        "ogg" => Ok("audio/ogg"),
+        "flac" => Ok("audio/flac"),
+        _ => Err(Error::Config(format!(
+            "Unknown audio type for {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Parse `SS`, `MM:SS` or `HH:MM:SS`, each optionally with a fraction of a second
+fn parse_timestamp(timestamp: &str) -> Result<Duration> {
+    let invalid = || Error::InvalidResponse(format!("Invalid timestamp: {:?}", timestamp));
+    let mut seconds = 0.0;
+    for field in timestamp.trim().split(':') {
+        let value: f64 = field.trim().parse().map_err(|_| invalid())?;
+        seconds = seconds * 60.0 + value;
+    }
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: &str, text: &str) -> RawSegment {
+        RawSegment {
+            start: start.to_string(),
+            end: start.to_string(),
+            speaker: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn pieces_overlap_and_cover_the_audio() {
+        let audio = AudioChunk {
+            data: vec![0; 2 * 5000],
+            sample_rate: 1000,
+            channels: 1,
+        };
+        let options = TranscribeOptions::new()
+            .max_segment(Duration::from_secs(2))
+            .overlap(Duration::from_millis(500));
+        let pieces = overlapping_pieces(&audio, &options);
+
+        let offsets: Vec<_> = pieces
+            .iter()
+            .map(|(offset, _)| offset.as_millis())
+            .collect();
+        assert_eq!(offsets, [0, 1500, 3000]);
+        let lengths: Vec<_> = pieces
+            .iter()
+            .map(|(_, chunk)| chunk.data.len() / 2)
+            .collect();
+        assert_eq!(lengths, [2000, 2000, 2000]);
+    }
+
+    #[test]
+    fn overlap_is_capped_at_half_a_piece() {
+        let options = TranscribeOptions::new()
+            .max_segment(Duration::from_secs(2))
+            .overlap(Duration::from_secs(30));
+        assert_eq!(overlap(&options), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn merge_takes_shared_stretch_from_each_side_of_its_middle() {
+        // Pieces at 0s and 10s sharing 2s, so the seam is at 11s
+        let pieces = vec![
+            (
+                Duration::ZERO,
+                vec![segment("00:09", "before"), segment("00:11.5", "late copy")],
+            ),
+            (
+                Duration::from_secs(10),
+                vec![
+                    segment("00:00.5", "early copy"),
+                    segment("00:01.5", "after"),
+                ],
+            ),
+        ];
+        let transcript = merge_pieces(pieces, Duration::from_secs(2)).unwrap();
+        let texts: Vec<_> = transcript
+            .segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect();
+        assert_eq!(texts, ["before", "after"]);
+        assert_eq!(transcript.segments[1].start, Duration::from_millis(11_500));
+    }
+
+    #[test]
+    fn timestamps_parse_in_every_form() {
+        assert_eq!(parse_timestamp("7").unwrap(), Duration::from_secs(7));
+        assert_eq!(
+            parse_timestamp("01:02.5").unwrap(),
+            Duration::from_millis(62_500)
+        );
+        assert_eq!(
+            parse_timestamp("1:00:00").unwrap(),
+            Duration::from_secs(3600)
+        );
+        assert!(parse_timestamp("soon").is_err());
+        assert!(parse_timestamp("-1").is_err());
+    }
+}
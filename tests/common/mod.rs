@@ -0,0 +1,248 @@
+//! Minimal HTTP server for exercising the client without the real API
+
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls;
+
+/// A request received by the mock server
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    /// Header names are lowercased
+    pub headers: Vec<(String, String)>,
+    pub body: serde_json::Value,
+}
+
+/// Handle to a running mock server
+pub struct MockServer {
+    pub base_url: String,
+    pub requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    /// Start a server answering each request with `respond(method, path) -> (status, body)`
+    pub async fn start<F>(respond: F) -> Self
+    where
+        F: Fn(&str, &str) -> (u16, serde_json::Value) + Send + Sync + 'static,
+    {
+        Self::start_raw(move |method, path| {
+            let (status, body) = respond(method, path);
+            (status, "application/json", body.to_string())
+        })
+        .await
+    }
+
+    /// Start a server answering with `respond(method, path) -> (status, content type, body)`
+    pub async fn start_raw<F>(respond: F) -> Self
+    where
+        F: Fn(&str, &str) -> (u16, &'static str, String) + Send + Sync + 'static,
+    {
+        Self::start_truncating(move |method, path| {
+            let (status, content_type, body) = respond(method, path);
+            (status, content_type, body, false)
+        })
+        .await
+    }
+
+    /// Like [`start_raw`](Self::start_raw), but responses flagged `true` advertise a longer
+    /// body than they send, so the client sees the connection drop mid-body
+    pub async fn start_truncating<F>(respond: F) -> Self
+    where
+        F: Fn(&str, &str) -> (u16, &'static str, String, bool) + Send + Sync + 'static,
+    {
+        Self::start_full(move |method, path| {
+            let (status, content_type, body, truncated) = respond(method, path);
+            let headers = vec![("content-type".to_string(), content_type.to_string())];
+            (status, headers, body, truncated)
+        })
+        .await
+    }
+
+    /// Start a JSON server whose responses carry extra headers
+    pub async fn start_with_headers<F>(respond: F) -> Self
+    where
+        F: Fn(&str, &str) -> (u16, Vec<(String, String)>, serde_json::Value)
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self::start_full(move |method, path| {
+            let (status, mut headers, body) = respond(method, path);
+            headers.push(("content-type".to_string(), "application/json".to_string()));
+            (status, headers, body.to_string(), false)
+        })
+        .await
+    }
+
+    /// Start a server answering with `respond(method, path) -> (status, headers, body)`
+    pub async fn start_raw_with_headers<F>(respond: F) -> Self
+    where
+        F: Fn(&str, &str) -> (u16, Vec<(String, String)>, String) + Send + Sync + 'static,
+    {
+        Self::start_full(move |method, path| {
+            let (status, headers, body) = respond(method, path);
+            (status, headers, body, false)
+        })
+        .await
+    }
+
+    async fn start_full<F>(respond: F) -> Self
+    where
+        F: Fn(&str, &str) -> (u16, Vec<(String, String)>, String, bool) + Send + Sync + 'static,
+    {
+        Self::serve(None, respond).await
+    }
+
+    /// Like [`start_raw`](Self::start_raw), but serving HTTPS for `localhost`; also returns
+    /// the PEM certificate to trust
+    pub async fn start_https<F>(respond: F) -> (Self, String)
+    where
+        F: Fn(&str, &str) -> (u16, &'static str, String) + Send + Sync + 'static,
+    {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_pem = certified.cert.pem();
+        let key =
+            rustls::pki_types::PrivateKeyDer::Pkcs8(certified.key_pair.serialize_der().into());
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![certified.cert.der().clone()], key)
+        .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+        let server = Self::serve(Some(acceptor), move |method, path| {
+            let (status, content_type, body) = respond(method, path);
+            let headers = vec![("content-type".to_string(), content_type.to_string())];
+            (status, headers, body, false)
+        })
+        .await;
+        (server, cert_pem)
+    }
+
+    async fn serve<F>(tls: Option<tokio_rustls::TlsAcceptor>, respond: F) -> Self
+    where
+        F: Fn(&str, &str) -> (u16, Vec<(String, String)>, String, bool) + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let base_url = match tls {
+            Some(_) => format!("https://localhost:{}", port),
+            None => format!("http://127.0.0.1:{}", port),
+        };
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let respond = Arc::new(respond);
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((socket, _)) = listener.accept().await else {
+                    break;
+                };
+                let recorded = recorded.clone();
+                let respond = respond.clone();
+                let tls = tls.clone();
+                tokio::spawn(async move {
+                    match tls {
+                        Some(acceptor) => {
+                            if let Ok(socket) = acceptor.accept(socket).await {
+                                answer(socket, &recorded, respond.as_ref()).await;
+                            }
+                        }
+                        None => answer(socket, &recorded, respond.as_ref()).await,
+                    }
+                });
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    /// Requests received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Read one request from `socket`, record it, and write the response
+async fn answer<S, F>(mut socket: S, recorded: &Mutex<Vec<RecordedRequest>>, respond: &F)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: Fn(&str, &str) -> (u16, Vec<(String, String)>, String, bool),
+{
+    let Some(request) = read_request(&mut socket).await else {
+        return;
+    };
+    let (status, headers, body, truncated) = respond(&request.method, &request.path);
+    recorded.lock().unwrap().push(request);
+
+    let headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+        .collect();
+    let response = format!(
+        "HTTP/1.1 {} OK\r\n{}content-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len() + if truncated { 64 } else { 0 },
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+async fn read_request<S: AsyncRead + Unpin>(socket: &mut S) -> Option<RecordedRequest> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let (path, query) = (path.to_string(), query.to_string());
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while buffer.len() < header_end + content_length {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let body = serde_json::from_slice(&buffer[header_end..]).unwrap_or(serde_json::Value::Null);
+    Some(RecordedRequest {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    })
+}
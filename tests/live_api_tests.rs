@@ -61,8 +61,10 @@ async fn test_structured_output_json() -> Result<()> {
     let client = create_test_client().await?;
 
     // Create generation config with structured output
-    let mut generation_config = GenerationConfig::default();
-    generation_config.response_mime_type = Some("application/json".to_string());
+    let generation_config = GenerationConfig {
+        response_mime_type: Some("application/json".to_string()),
+        ..Default::default()
+    };
 
     let request = GenerateContentRequest {
         contents: vec![Content::user(
@@ -88,7 +90,7 @@ async fn test_structured_output_json() -> Result<()> {
     );
 
     // Try to parse the JSON response
-    if let Some(Part::Text { text }) = candidate.content.parts.first() {
+    if let Some(Part::Text { text, .. }) = candidate.content.parts.first() {
         let person: Person = serde_json::from_str(text).map_err(|e| {
             anyhow::anyhow!("Failed to parse JSON response: {}\nResponse: {}", e, text)
         })?;
@@ -133,6 +135,7 @@ async fn test_caching_functionality() -> Result<()> {
     let cache_config = CacheConfig {
         ttl: Some(300), // 5 minutes TTL
         display_name: Some("test-cache".to_string()),
+        ..Default::default()
     };
 
     // Try to create cached content - if it fails, skip the test
@@ -175,7 +178,7 @@ async fn test_caching_functionality() -> Result<()> {
     assert!(!response.candidates.is_empty(), "No candidates in response");
 
     if let Some(candidate) = response.candidates.first() {
-        if let Some(Part::Text { text }) = candidate.content.parts.first() {
+        if let Some(Part::Text { text, .. }) = candidate.content.parts.first() {
             println!("✅ Cache usage test passed");
             println!("Response with cached context: {}", text);
         }
@@ -187,7 +190,7 @@ async fn test_caching_functionality() -> Result<()> {
         caches
             .cached_contents
             .as_ref()
-            .map_or(false, |c| !c.is_empty()),
+            .is_some_and(|c| !c.is_empty()),
         "Should have at least one cached content"
     );
 
@@ -235,7 +238,7 @@ async fn test_grounding_functionality() -> Result<()> {
         "No parts in candidate content"
     );
 
-    if let Some(Part::Text { text }) = candidate.content.parts.first() {
+    if let Some(Part::Text { text, .. }) = candidate.content.parts.first() {
         // The response should contain information that suggests it used web search
         assert!(!text.is_empty(), "Response should not be empty");
         println!("✅ Grounding test passed");
@@ -265,7 +268,8 @@ async fn test_thinking_budget() -> Result<()> {
     {
         use gemini_rust::thinking::{ThinkingBudget, ThinkingConfig};
         generation_config.thinking_config = Some(ThinkingConfig {
-            thinking_budget: ThinkingBudget::Tokens(1000),
+            thinking_budget: Some(ThinkingBudget::Tokens(1000)),
+            ..Default::default()
         });
     }
 
@@ -292,7 +296,7 @@ async fn test_thinking_budget() -> Result<()> {
         "No parts in candidate content"
     );
 
-    if let Some(Part::Text { text }) = candidate.content.parts.first() {
+    if let Some(Part::Text { text, .. }) = candidate.content.parts.first() {
         // The response should show step-by-step thinking
         assert!(!text.is_empty(), "Response should not be empty");
         println!("✅ Thinking budget test passed");
@@ -363,7 +367,7 @@ async fn test_tool_calling() -> Result<()> {
     let mut found_function_call = false;
     for part in &candidate.content.parts {
         match part {
-            Part::FunctionCall { function_call } => {
+            Part::FunctionCall { function_call, .. } => {
                 assert_eq!(
                     function_call.name, "calculate",
                     "Function name should be 'calculate'"
@@ -372,7 +376,7 @@ async fn test_tool_calling() -> Result<()> {
                 println!("✅ Function call found: {}", function_call.name);
                 println!("Function args: {:?}", function_call.args);
             }
-            Part::Text { text } => {
+            Part::Text { text, .. } => {
                 println!("Response text: {}", text);
             }
             _ => {}
@@ -384,7 +388,7 @@ async fn test_tool_calling() -> Result<()> {
         println!("✅ Tool calling test passed - function call detected");
     } else {
         // Check if the response at least mentions the calculation
-        if let Some(Part::Text { text }) = candidate.content.parts.first() {
+        if let Some(Part::Text { text, .. }) = candidate.content.parts.first() {
             assert!(
                 text.contains("42") || text.contains("15") || text.contains("27"),
                 "Response should reference the calculation: {}",
@@ -425,7 +429,7 @@ async fn test_basic_generation() -> Result<()> {
         "No parts in candidate content"
     );
 
-    if let Some(Part::Text { text }) = candidate.content.parts.first() {
+    if let Some(Part::Text { text, .. }) = candidate.content.parts.first() {
         assert!(!text.is_empty(), "Response text should not be empty");
         assert!(text.len() > 10, "Response should be substantial");
         println!("✅ Basic generation test passed");
@@ -463,7 +467,8 @@ async fn test_combined_features() -> Result<()> {
     {
         use gemini_rust::thinking::{ThinkingBudget, ThinkingConfig};
         generation_config.thinking_config = Some(ThinkingConfig {
-            thinking_budget: ThinkingBudget::Tokens(500),
+            thinking_budget: Some(ThinkingBudget::Tokens(500)),
+            ..Default::default()
         });
     }
 
@@ -501,8 +506,8 @@ async fn test_combined_features() -> Result<()> {
     // Print all parts of the response
     for (i, part) in candidate.content.parts.iter().enumerate() {
         match part {
-            Part::Text { text } => println!("Text part {}: {}", i, text),
-            Part::FunctionCall { function_call } => {
+            Part::Text { text, .. } => println!("Text part {}: {}", i, text),
+            Part::FunctionCall { function_call, .. } => {
                 println!(
                     "Function call {}: {} with args {:?}",
                     i, function_call.name, function_call.args
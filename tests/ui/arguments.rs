@@ -0,0 +1,9 @@
+use gemini_rust::gemini_tool;
+
+/// Greet someone
+#[gemini_tool(name = "hello")]
+async fn greet(name: String) -> String {
+    format!("Hello, {}", name)
+}
+
+fn main() {}
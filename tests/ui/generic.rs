@@ -0,0 +1,9 @@
+use gemini_rust::gemini_tool;
+
+/// Echo a value
+#[gemini_tool]
+async fn echo<T: serde::Serialize>(value: T) -> T {
+    value
+}
+
+fn main() {}
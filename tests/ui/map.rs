@@ -0,0 +1,9 @@
+use gemini_rust::gemini_tool;
+
+/// Count the tags
+#[gemini_tool]
+async fn count_tags(tags: std::collections::HashMap<String, String>) -> usize {
+    tags.len()
+}
+
+fn main() {}
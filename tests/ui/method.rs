@@ -0,0 +1,13 @@
+use gemini_rust::gemini_tool;
+
+struct Counter;
+
+impl Counter {
+    /// Count to a number
+    #[gemini_tool]
+    async fn count(&self, to: i64) -> i64 {
+        to
+    }
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use gemini_rust::gemini_tool;
+
+/// Add a pair of numbers
+#[gemini_tool]
+async fn add((a, b): (i64, i64)) -> i64 {
+    a + b
+}
+
+fn main() {}
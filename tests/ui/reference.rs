@@ -0,0 +1,9 @@
+use gemini_rust::gemini_tool;
+
+/// Shout a word
+#[gemini_tool]
+async fn shout(word: &str) -> String {
+    word.to_uppercase()
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use gemini_rust::gemini_tool;
+
+/// Measure the distance to a point
+#[gemini_tool]
+async fn distance(point: (f64, f64)) -> f64 {
+    point.0.hypot(point.1)
+}
+
+fn main() {}
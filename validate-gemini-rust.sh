@@ -134,12 +134,12 @@ done
 # 12. Check for issues in specific files
 echo -e "\n${YELLOW}12. Checking for specific issues...${NC}"
 
-# Check if GroundingConfig::to_tools has feature flag
-echo -n "  Checking GroundingConfig::to_tools feature flag... "
+# Grounding tools live in models, so grounding must not depend on the functions feature
+echo -n "  Checking grounding does not require the functions feature... "
 if grep -q '#\[cfg(feature = "functions")\]' src/grounding/mod.rs; then
-    print_status 0 "Feature flag present"
+    print_status 1 "Grounding is gated on functions"
 else
-    print_status 1 "Feature flag missing"
+    print_status 0 "Grounding is independent of functions"
 fi
 
 # Check if plans directory was removed